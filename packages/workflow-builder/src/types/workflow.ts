@@ -4,8 +4,9 @@
 
 export interface WorkflowNode {
   id: string;
-  type: 'activity' | 'agent' | 'signal' | 'trigger' | 'condition' | 'end' | 'api-endpoint' | 'phase' | 'retry' | 'state-variable' | 'data-in' | 'data-out' | 'kong-logging' | 'kong-cache' | 'kong-cors' | 'graphql-gateway' | 'mcp-server' | 'child-workflow' | 'loop' | 'conditional';
+  type: 'activity' | 'agent' | 'signal' | 'update' | 'trigger' | 'condition' | 'end' | 'api-endpoint' | 'phase' | 'retry' | 'timer' | 'approval' | 'state-variable' | 'data-in' | 'data-out' | 'kong-logging' | 'kong-cache' | 'kong-cors' | 'graphql-gateway' | 'mcp-server' | 'child-workflow' | 'loop' | 'map' | 'conditional';
   position: { x: number; y: number };
+  parentId?: string; // Phase node containing this node
   data: {
     label: string;
     componentId?: string;
@@ -17,7 +18,24 @@ export interface WorkflowNode {
       initialInterval?: string; // For exponential backoff (e.g., '1s', '5m')
       maxInterval?: string;
       backoffCoefficient?: number;
+      nonRetryableErrors?: string[]; // Error types that fail without retrying
     };
+    compensationActivity?: string; // Activity that undoes this one if a later step fails
+    duration?: string; // Timer: fixed wait (e.g., '30m', 'PT1H')
+    durationExpression?: string; // Timer: expression for milliseconds or a duration string
+    until?: string; // Timer: RFC 3339 timestamp or an expression for the deadline
+    signalName?: string; // Signal to wait for; ends a timer's wait early, or delivers an approval decision
+    approvers?: string[]; // Approval: users or groups whose decision is accepted
+    timeout?: string; // Approval: how long to wait before taking the 'timed_out' branch
+    collection?: string; // Loop/Map: expression for the items to iterate over
+    itemVariable?: string; // Loop/Map: name bound to the current item (default 'item')
+    maxConcurrency?: number; // Map: items running at once (default 10)
+    maxItems?: number; // Map: largest collection accepted; larger ones fail the map
+    model?: string; // Agent: model identifier passed to the model provider
+    systemPrompt?: string; // Agent: system prompt
+    tools?: string[]; // Agent: activities the model may call, by activity name
+    maxTurns?: number; // Agent: model calls before the agent fails (default 10)
+    outputSchema?: Array<{ name: string; type: string; required?: boolean; description?: string }>; // Agent: fields of the final output
   };
 }
 
@@ -38,6 +56,7 @@ export interface WorkflowDefinition {
     timeout?: string;
     retryPolicy?: Record<string, any>;
     description?: string;
+    parallelCompensation?: boolean; // Run compensations concurrently instead of newest first
   };
 }
 
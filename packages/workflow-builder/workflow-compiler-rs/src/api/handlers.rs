@@ -264,6 +264,12 @@ fn convert_validation_error(error: &ValidationError) -> CompilerError {
             node_id: Some(node_id.clone()),
             severity: "error".to_string(),
         },
+        ValidationError::InvalidSettings(_) => CompilerError {
+            code: "INVALID_SETTINGS".to_string(),
+            message: error.to_string(),
+            node_id: None,
+            severity: "error".to_string(),
+        },
         ValidationError::MissingRequiredField(_) => CompilerError {
             code: "MISSING_REQUIRED_FIELD".to_string(),
             message: error.to_string(),
@@ -5,7 +5,9 @@ use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
-use crate::schema::{NodeType, RetryPolicy, WorkflowDefinition, WorkflowNode, WorkflowVariable};
+use crate::schema::{
+    LongRunningConfig, NodeType, RetryPolicy, WorkflowDefinition, WorkflowNode, WorkflowVariable,
+};
 
 /// Code generation errors
 #[derive(Debug, Error)]
@@ -46,11 +48,27 @@ struct WorkflowTemplateData {
     retry_policy: Option<RetryPolicyData>,
     has_queries: bool,
     has_signals: bool,
+    has_updates: bool,
+    has_handlers: bool,
+    waits_for_events: bool,
+    queues_signals: bool,
     is_long_running: bool,
+    long_running: Option<LongRunningData>,
+    preserves_state: bool,
+    continuation_type: String,
+    resumes_steps: bool,
+    replays_signals: bool,
+    is_service: bool,
+    shutdown_signal: String,
     variables: Vec<VariableData>,
     queries: Vec<QueryData>,
     signals: Vec<SignalData>,
+    updates: Vec<UpdateData>,
+    signal_handlers: Vec<EventHandlerData>,
+    update_handlers: Vec<EventHandlerData>,
     code_blocks: Vec<String>,
+    steps: Vec<StepData>,
+    shutdown_blocks: Vec<String>,
     input_type: String,
     output_type: String,
     imports: Vec<String>,
@@ -65,6 +83,22 @@ struct RetryPolicyData {
     backoff_coefficient: f64,
 }
 
+/// Continue-as-new limits of a long-running workflow
+#[derive(Debug, Serialize)]
+struct LongRunningData {
+    max_history_events: u32,
+    max_duration_ms: u64,
+}
+
+impl From<&LongRunningConfig> for LongRunningData {
+    fn from(config: &LongRunningConfig) -> Self {
+        LongRunningData {
+            max_history_events: config.max_history_events,
+            max_duration_ms: config.max_duration_ms,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct VariableData {
     name: String,
@@ -84,6 +118,29 @@ struct SignalData {
     param_types: String,
 }
 
+#[derive(Debug, Serialize)]
+struct UpdateData {
+    name: String,
+    param_types: String,
+    return_type: String,
+}
+
+/// Step of a long-running task workflow that a continued run can skip
+#[derive(Debug, Serialize)]
+struct StepData {
+    index: usize,
+    code: String,
+    next: Option<usize>,
+}
+
+/// Handler subgraph that a service workflow's event loop dispatches to
+#[derive(Debug, Serialize)]
+struct EventHandlerData {
+    name: String,
+    code_blocks: Vec<String>,
+    result_var: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct ProxyDeclaration {
     var_name: String,
@@ -175,9 +232,45 @@ impl<'a> CodeGenerator<'a> {
         let start_node = workflow
             .find_trigger()
             .ok_or(GenerationError::NoStartNode)?;
-        let code_blocks = self.generate_workflow_body(workflow, start_node)?;
-        let (has_signals, signals) = self.extract_signals(workflow);
+        let is_service = workflow.settings.is_service();
+        let signals = self.extract_signals(workflow);
+        let updates = self.extract_updates(workflow);
+        let has_signals = !signals.is_empty() || is_service;
+        let has_updates = !updates.is_empty();
         let proxy_declarations = self.build_proxy_declarations(workflow);
+        let long_running = workflow
+            .settings
+            .long_running
+            .as_ref()
+            .filter(|lr| lr.auto_continue_as_new);
+        let is_long_running = long_running.is_some();
+
+        // Long-running task workflows continue as new between steps and resume
+        // from the next one; the Trigger node is replaced by the restored start
+        let resumes_steps = is_long_running && !is_service;
+        // Their signals are queued and handled between steps, so the signals
+        // still queued when they continue as new are handled by the next run
+        let replays_signals = resumes_steps && !signals.is_empty();
+        let (code_blocks, steps) = if resumes_steps {
+            (vec![], self.generate_steps(workflow, start_node))
+        } else {
+            (self.generate_workflow_body(workflow, start_node)?, vec![])
+        };
+
+        // Service workflows run their End node once the shutdown signal stops the loop
+        let (signal_handlers, update_handlers, shutdown_blocks) = if is_service {
+            (
+                self.generate_event_handlers(workflow, NodeType::Signal),
+                self.generate_event_handlers(workflow, NodeType::Update),
+                workflow
+                    .find_end_nodes()
+                    .first()
+                    .map(|end| vec![self.generate_node_code(end, &HashSet::new())])
+                    .unwrap_or_default(),
+            )
+        } else {
+            (vec![], vec![], vec![])
+        };
 
         let data = WorkflowTemplateData {
             version: env!("CARGO_PKG_VERSION").to_string(),
@@ -194,11 +287,27 @@ impl<'a> CodeGenerator<'a> {
                 .map(|rp| self.convert_retry_policy(rp)),
             has_queries: false,
             has_signals,
-            is_long_running: workflow.settings.is_long_running(),
+            has_updates,
+            has_handlers: has_signals || has_updates,
+            waits_for_events: is_long_running || is_service,
+            queues_signals: is_service || replays_signals,
+            is_long_running,
+            long_running: long_running.map(LongRunningData::from),
+            preserves_state: long_running.is_some_and(|lr| lr.preserve_state),
+            continuation_type: format!("{}Continuation", workflow.function_name()),
+            resumes_steps,
+            replays_signals,
+            is_service,
+            shutdown_signal: workflow.settings.shutdown_signal().to_string(),
             variables: self.convert_variables(&workflow.variables),
             queries: vec![],
             signals,
+            updates,
+            signal_handlers,
+            update_handlers,
             code_blocks,
+            steps,
+            shutdown_blocks,
             input_type: "Record<string, unknown>".to_string(),
             output_type: "WorkflowResult".to_string(),
             imports: vec![],
@@ -267,14 +376,61 @@ impl<'a> CodeGenerator<'a> {
     }
 
     /// Generate the workflow body by traversing the graph
+    ///
+    /// Service workflows stop at Signal, Update and End nodes: the body only
+    /// initializes the service, and the event loop takes over from there.
     fn generate_workflow_body(
         &self,
         workflow: &WorkflowDefinition,
         start_node: &WorkflowNode,
     ) -> Result<Vec<String>, GenerationError> {
-        let mut code_blocks = Vec::new();
+        let nodes = self.collect_region(
+            workflow,
+            vec![start_node.id.as_str()],
+            workflow.settings.is_service(),
+        );
+        Ok(self.generate_region_code(&nodes))
+    }
+
+    /// Generate the body of a long-running task workflow as numbered steps,
+    /// one per node after the Trigger node
+    fn generate_steps(
+        &self,
+        workflow: &WorkflowDefinition,
+        start_node: &WorkflowNode,
+    ) -> Vec<StepData> {
+        let nodes: Vec<_> = self
+            .collect_region(workflow, vec![start_node.id.as_str()], false)
+            .into_iter()
+            .filter(|n| !n.node_type.is_trigger())
+            .collect();
+        let code_blocks = self.generate_region_code(&nodes);
+        let count = code_blocks.len();
+
+        code_blocks
+            .into_iter()
+            .enumerate()
+            .map(|(index, code)| StepData {
+                index,
+                code: code.replace('\n', "\n  "),
+                next: Some(index + 1).filter(|&next| next < count),
+            })
+            .collect()
+    }
+
+    /// Collect the nodes reachable from the entry nodes, in generation order
+    ///
+    /// With `stop_at_events`, Signal, Update and End nodes are neither
+    /// included nor followed.
+    fn collect_region<'w>(
+        &self,
+        workflow: &'w WorkflowDefinition,
+        entry: Vec<&'w str>,
+        stop_at_events: bool,
+    ) -> Vec<&'w WorkflowNode> {
+        let mut nodes = Vec::new();
         let mut visited = HashSet::new();
-        let mut queue = vec![start_node.id.as_str()];
+        let mut queue = entry;
 
         while let Some(node_id) = queue.pop() {
             if visited.contains(node_id) {
@@ -283,10 +439,10 @@ impl<'a> CodeGenerator<'a> {
             visited.insert(node_id.to_string());
 
             if let Some(node) = workflow.find_node(node_id) {
-                let code = self.generate_node_code(node, &visited);
-                if !code.is_empty() {
-                    code_blocks.push(code);
+                if stop_at_events && (node.node_type.is_event_handler() || node.is_end()) {
+                    continue;
                 }
+                nodes.push(node);
 
                 // Add connected nodes to queue
                 for edge in workflow.edges_from(node_id) {
@@ -297,7 +453,59 @@ impl<'a> CodeGenerator<'a> {
             }
         }
 
-        Ok(code_blocks)
+        nodes
+    }
+
+    /// Generate the code blocks for a region of the graph
+    fn generate_region_code(&self, nodes: &[&WorkflowNode]) -> Vec<String> {
+        let mut visited = HashSet::new();
+        let mut code_blocks = Vec::new();
+        for node in nodes {
+            visited.insert(node.id.clone());
+            let code = self.generate_node_code(node, &visited);
+            if !code.is_empty() {
+                code_blocks.push(code);
+            }
+        }
+        code_blocks
+    }
+
+    /// Generate the handler subgraphs of a service workflow's Signal or Update
+    /// nodes, one per signal or update name
+    fn generate_event_handlers(
+        &self,
+        workflow: &WorkflowDefinition,
+        node_type: NodeType,
+    ) -> Vec<EventHandlerData> {
+        let mut handlers: Vec<(&str, Vec<&WorkflowNode>)> = Vec::new();
+        for node in workflow.nodes.iter().filter(|n| n.node_type == node_type) {
+            let Some(name) = node.event_name() else {
+                continue;
+            };
+            let entry = workflow
+                .edges_from(&node.id)
+                .into_iter()
+                .map(|e| e.target.as_str())
+                .collect();
+            let region = self.collect_region(workflow, entry, true);
+            match handlers.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, nodes)) => nodes.extend(region),
+                None => handlers.push((name, region)),
+            }
+        }
+
+        handlers
+            .into_iter()
+            .map(|(name, nodes)| EventHandlerData {
+                name: name.to_string(),
+                code_blocks: self.generate_region_code(&nodes),
+                result_var: nodes
+                    .iter()
+                    .rev()
+                    .find(|n| n.node_type.is_activity())
+                    .map(|n| result_var(n)),
+            })
+            .collect()
     }
 
     /// Generate code for a single node
@@ -309,8 +517,11 @@ impl<'a> CodeGenerator<'a> {
             },
             NodeType::Activity | NodeType::Agent => {
                 let activity_name = node.activity_name().unwrap_or("unknownActivity");
-                let var_name = format!("result_{}", node.id.replace('-', "_"));
-                format!("const {} = await acts.{}(input);", var_name, activity_name)
+                format!(
+                    "const {} = await acts.{}(input);",
+                    result_var(node),
+                    activity_name
+                )
             },
             NodeType::End => {
                 // Stop component: record workflow completion
@@ -366,21 +577,35 @@ impl<'a> CodeGenerator<'a> {
             .collect()
     }
 
-    /// Extract signal definitions
-    fn extract_signals(&self, workflow: &WorkflowDefinition) -> (bool, Vec<SignalData>) {
-        let signals: Vec<SignalData> = workflow
-            .nodes
-            .iter()
-            .filter(|n| n.node_type == NodeType::Signal)
-            .filter_map(|n| {
-                n.data.signal_name.as_ref().map(|name| SignalData {
-                    name: name.clone(),
-                    param_types: "unknown".to_string(),
-                })
+    /// Extract signal definitions, one per signal name
+    fn extract_signals(&self, workflow: &WorkflowDefinition) -> Vec<SignalData> {
+        event_names(workflow, NodeType::Signal)
+            .into_iter()
+            .map(|name| SignalData {
+                name: name.to_string(),
+                param_types: "unknown".to_string(),
             })
-            .collect();
+            .collect()
+    }
 
-        (!signals.is_empty(), signals)
+    /// Extract update definitions, one per update name
+    ///
+    /// Only service workflows answer updates with the result of their handler
+    /// subgraph; task workflows acknowledge them.
+    fn extract_updates(&self, workflow: &WorkflowDefinition) -> Vec<UpdateData> {
+        let return_type = if workflow.settings.is_service() {
+            "unknown"
+        } else {
+            "void"
+        };
+        event_names(workflow, NodeType::Update)
+            .into_iter()
+            .map(|name| UpdateData {
+                name: name.to_string(),
+                param_types: "unknown".to_string(),
+                return_type: return_type.to_string(),
+            })
+            .collect()
     }
 
     /// Build proxy declarations for activities
@@ -467,10 +692,31 @@ impl<'a> CodeGenerator<'a> {
     }
 }
 
+/// Name of the variable holding a node's result
+fn result_var(node: &WorkflowNode) -> String {
+    format!("result_{}", node.id.replace('-', "_"))
+}
+
+/// Distinct signal or update names handled by nodes of the given type
+fn event_names(workflow: &WorkflowDefinition, node_type: NodeType) -> Vec<&str> {
+    let mut names = Vec::new();
+    for name in workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == node_type)
+        .filter_map(|n| n.event_name())
+    {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{NodeData, Position, WorkflowEdge, WorkflowSettings};
+    use crate::schema::{NodeData, Position, VariableType, WorkflowEdge, WorkflowSettings};
 
     fn create_simple_workflow() -> WorkflowDefinition {
         WorkflowDefinition {
@@ -693,4 +939,277 @@ mod tests {
             "Workflow function should return Promise<WorkflowResult>"
         );
     }
+
+    fn create_long_running_workflow(preserve_state: bool) -> WorkflowDefinition {
+        let mut workflow = create_simple_workflow();
+        workflow.variables = vec![WorkflowVariable::new("processed", VariableType::Number)];
+        workflow.settings.long_running = Some(LongRunningConfig {
+            auto_continue_as_new: true,
+            max_history_events: 5000,
+            max_duration_ms: 3_600_000,
+            preserve_state,
+        });
+        workflow
+    }
+
+    #[test]
+    fn test_long_running_continues_as_new_at_limits() {
+        let workflow = create_long_running_workflow(true);
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap();
+
+        assert!(code.workflow.contains("const MAX_HISTORY_EVENTS = 5000;"));
+        assert!(code
+            .workflow
+            .contains("const MAX_RUN_DURATION_MS = 3600000;"));
+        assert!(code
+            .workflow
+            .contains("workflowInfo().historyLength >= MAX_HISTORY_EVENTS"));
+        assert!(code
+            .workflow
+            .contains("Date.now() - runStartedAt >= MAX_RUN_DURATION_MS"));
+        assert!(code
+            .workflow
+            .contains("continuation?: TestWorkflowContinuation"));
+        assert!(code
+            .workflow
+            .contains("await continueAsNew<typeof TestWorkflow>(input, {"));
+        assert!(code
+            .workflow
+            .contains("await condition(allHandlersFinished);"));
+    }
+
+    #[test]
+    fn test_long_running_resumes_from_next_step() {
+        let mut workflow = create_long_running_workflow(true);
+        workflow.nodes.insert(2, activity("second", "sendEmail"));
+        workflow.edges[1].target = "second".to_string();
+        workflow
+            .edges
+            .push(WorkflowEdge::new("e3", "second", "end"));
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap();
+
+        assert!(code
+            .workflow
+            .contains("const resumeStep = continuation?.step ?? 0;"));
+        assert!(code.workflow.contains("new Date(continuation.startedAt)"));
+        assert!(!code.workflow.contains("{ startedAt: new Date() }"));
+        assert!(code
+            .workflow
+            .contains("  step: number;\n  startedAt: string;\n"));
+        assert!(code.workflow.contains("      step: nextStep,\n"));
+
+        let steps: Vec<_> = code.workflow.match_indices("if (resumeStep <= ").collect();
+        assert_eq!(steps.len(), 3);
+        assert!(code.workflow.contains("await checkpoint(1);"));
+        assert!(code.workflow.contains("await checkpoint(2);"));
+        assert!(!code.workflow.contains("await checkpoint(3);"));
+    }
+
+    #[test]
+    fn test_long_running_carries_variables_and_signals() {
+        let mut workflow = create_long_running_workflow(true);
+        workflow.nodes.push(WorkflowNode {
+            id: "approval".to_string(),
+            node_type: NodeType::Signal,
+            data: NodeData {
+                label: "Approval".to_string(),
+                signal_name: Some("approve".to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+        });
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap();
+
+        assert!(code
+            .workflow
+            .contains("let processed: number = continuation?.variables.processed ?? 0;"));
+        assert!(code.workflow.contains("        processed,\n"));
+    }
+
+    #[test]
+    fn test_long_running_replays_signals_in_next_run() {
+        let mut workflow = create_long_running_workflow(true);
+        workflow.nodes.push(WorkflowNode {
+            id: "approval".to_string(),
+            node_type: NodeType::Signal,
+            data: NodeData {
+                label: "Approval".to_string(),
+                signal_name: Some("approve".to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+        });
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap();
+        let workflow_code = &code.workflow;
+
+        // A signal is queued when it arrives, and the queue is carried into
+        // the next run when the workflow continues as new
+        assert!(workflow_code.contains("  pendingSignals: PendingSignal[];\n"));
+        assert!(workflow_code.contains("pendingSignals.push({ name: 'approve', payload });"));
+        assert!(workflow_code.contains(
+            "const pendingSignals: PendingSignal[] = continuation?.pendingSignals ?? [];"
+        ));
+        let handled = workflow_code.find("    handleSignals();\n    if (!shouldContinueAsNew())");
+        let carried = workflow_code.find("      pendingSignals,\n    });");
+        assert!(handled.is_some() && carried.is_some() && handled < carried);
+
+        // The next run handles the carried signals before resuming its steps
+        assert!(workflow_code.contains(
+            "        case 'approve':\n          \
+             console.log('Received approve signal', signal.payload);\n"
+        ));
+        let replayed = workflow_code.find("  handleSignals();\n  if (resumeStep <= 0) {");
+        assert!(replayed.is_some());
+        assert_eq!(workflow_code.matches("handleSignals();").count(), 3);
+    }
+
+    #[test]
+    fn test_long_running_without_preserved_state() {
+        let workflow = create_long_running_workflow(false);
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap();
+
+        assert!(code.workflow.contains("let processed: number = 0;"));
+        assert!(!code.workflow.contains("variables: {"));
+        assert!(code.workflow.contains("      step: nextStep,\n"));
+    }
+
+    #[test]
+    fn test_short_workflow_does_not_continue_as_new() {
+        let workflow = create_simple_workflow();
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap();
+
+        assert!(!code.workflow.contains("continueAsNew"));
+        assert!(!code.workflow.contains("continuation?"));
+    }
+
+    fn node(id: &str, node_type: NodeType, data: NodeData) -> WorkflowNode {
+        WorkflowNode {
+            id: id.to_string(),
+            node_type,
+            data,
+            position: Position::default(),
+        }
+    }
+
+    fn activity(id: &str, activity_name: &str) -> WorkflowNode {
+        node(
+            id,
+            NodeType::Activity,
+            NodeData {
+                label: activity_name.to_string(),
+                activity_name: Some(activity_name.to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn create_service_workflow() -> WorkflowDefinition {
+        let mut workflow = WorkflowDefinition::new("wf_orders", "Order Service");
+        workflow.nodes = vec![
+            node("trigger", NodeType::Trigger, NodeData::default()),
+            activity("load", "loadCatalog"),
+            node(
+                "order",
+                NodeType::Signal,
+                NodeData {
+                    signal_name: Some("order".to_string()),
+                    ..Default::default()
+                },
+            ),
+            activity("process", "processOrder"),
+            node(
+                "quote",
+                NodeType::Update,
+                NodeData {
+                    update_name: Some("quote".to_string()),
+                    ..Default::default()
+                },
+            ),
+            activity("price", "priceOrder"),
+            node("end", NodeType::End, NodeData::default()),
+        ];
+        workflow.edges = vec![
+            WorkflowEdge::new("e1", "trigger", "load"),
+            WorkflowEdge::new("e2", "load", "order"),
+            WorkflowEdge::new("e3", "load", "quote"),
+            WorkflowEdge::new("e4", "order", "process"),
+            WorkflowEdge::new("e5", "quote", "price"),
+            WorkflowEdge::new("e6", "process", "end"),
+            WorkflowEdge::new("e7", "price", "end"),
+        ];
+        workflow.settings =
+            serde_json::from_value(serde_json::json!({ "_workflowType": "service" })).unwrap();
+        workflow
+    }
+
+    #[test]
+    fn test_service_dispatches_events_until_shutdown() {
+        let workflow = create_service_workflow();
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap().workflow;
+
+        assert!(code.contains("export const shutdownSignal = defineSignal('shutdown');"));
+        assert!(code.contains("while (!shutdownRequested) {"));
+        assert!(code.contains("await condition(() => hasPendingEvents() || shutdownRequested);"));
+
+        // Initialization runs once; handler subgraphs run from the event loop
+        assert_eq!(code.matches("await acts.loadCatalog(input);").count(), 1);
+        assert!(code.contains(
+            "        case 'order': {\n          const result_process = await acts.processOrder(input);"
+        ));
+        assert!(code.contains("pendingSignals.push({ name: 'order', payload });"));
+
+        // End runs once, after the loop
+        assert_eq!(code.matches("const stopOutput: StopOutput").count(), 1);
+        let loop_start = code.find("while (!shutdownRequested)").unwrap();
+        assert!(code.find("const stopOutput").unwrap() > loop_start);
+        assert!(!code.contains("continueAsNew"));
+    }
+
+    #[test]
+    fn test_service_answers_updates_from_handler_result() {
+        let workflow = create_service_workflow();
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap().workflow;
+
+        assert!(
+            code.contains("export const quoteUpdate = defineUpdate<unknown, [unknown]>('quote');")
+        );
+        assert!(code.contains("pendingUpdates.push(update);"));
+        assert!(code.contains("await condition(() => update.handled);"));
+        assert!(code.contains("update.result = result_price;"));
+    }
+
+    #[test]
+    fn test_long_running_service_continues_as_new_from_event_loop() {
+        let mut workflow = create_service_workflow();
+        workflow.settings.shutdown_signal = Some("stop".to_string());
+        workflow.settings.long_running = create_long_running_workflow(true).settings.long_running;
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap().workflow;
+
+        assert!(code.contains("export const shutdownSignal = defineSignal('stop');"));
+        assert!(code.contains("      await finishPendingEvents();\n      await continueAsNew"));
+        assert!(code.contains("MAX_RUN_DURATION_MS - (Date.now() - runStartedAt)"));
+        assert_eq!(code.matches("await continueAsNew").count(), 1);
+    }
+
+    #[test]
+    fn test_task_workflow_runs_to_completion() {
+        let mut workflow = create_service_workflow();
+        workflow.settings.workflow_type = None;
+        let generator = CodeGenerator::new().unwrap();
+        let code = generator.generate(&workflow).unwrap().workflow;
+
+        assert!(!code.contains("shutdownSignal"));
+        assert!(!code.contains("while (!shutdownRequested)"));
+        assert!(code.contains("const result_process = await acts.processOrder(input);"));
+        assert!(code.contains("defineUpdate<void, [unknown]>('quote')"));
+    }
 }
@@ -14,6 +14,6 @@ mod workflow;
 pub use components::{LogInput, LogLevel, LogOutput, StartInput, StartOutput, StopInput, StopOutput};
 pub use edge::WorkflowEdge;
 pub use node::{NodeData, NodeType, Position, RetryPolicy, RetryStrategy, WorkflowNode};
-pub use settings::WorkflowSettings;
+pub use settings::{LongRunningConfig, WorkflowSettings};
 pub use variable::{VariableType, WorkflowVariable};
 pub use workflow::WorkflowDefinition;
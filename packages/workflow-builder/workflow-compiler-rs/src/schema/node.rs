@@ -14,6 +14,7 @@ pub enum NodeType {
     Loop,
     ChildWorkflow,
     Signal,
+    Update,
     Phase,
     Retry,
     StateVariable,
@@ -45,6 +46,11 @@ impl NodeType {
         matches!(self, NodeType::Activity | NodeType::Agent)
     }
 
+    /// Check if this node type receives messages from clients
+    pub fn is_event_handler(&self) -> bool {
+        matches!(self, NodeType::Signal | NodeType::Update)
+    }
+
     /// Check if this node type is a Kong component
     pub fn is_kong_component(&self) -> bool {
         matches!(
@@ -105,6 +111,8 @@ pub struct NodeData {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signal_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Option<HashMap<String, serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<String>,
@@ -137,6 +145,15 @@ impl WorkflowNode {
             .or(self.data.component_name.as_deref())
     }
 
+    /// Get the signal or update name this node handles
+    pub fn event_name(&self) -> Option<&str> {
+        match self.node_type {
+            NodeType::Signal => self.data.signal_name.as_deref(),
+            NodeType::Update => self.data.update_name.as_deref(),
+            _ => None,
+        }
+    }
+
     /// Check if this node is a trigger
     pub fn is_trigger(&self) -> bool {
         self.node_type.is_trigger()
@@ -58,6 +58,9 @@ pub struct WorkflowSettings {
     /// Workflow type (task or service)
     #[serde(rename = "_workflowType", skip_serializing_if = "Option::is_none")]
     pub workflow_type: Option<WorkflowType>,
+    /// Signal that stops a service workflow
+    #[serde(rename = "_shutdownSignal", skip_serializing_if = "Option::is_none")]
+    pub shutdown_signal: Option<String>,
 }
 
 impl WorkflowSettings {
@@ -69,6 +72,16 @@ impl WorkflowSettings {
             .unwrap_or(false)
     }
 
+    /// Check if this is a service workflow that handles events until shut down
+    pub fn is_service(&self) -> bool {
+        self.workflow_type == Some(WorkflowType::Service)
+    }
+
+    /// Get the name of the signal that stops a service workflow
+    pub fn shutdown_signal(&self) -> &str {
+        self.shutdown_signal.as_deref().unwrap_or("shutdown")
+    }
+
     /// Get the default timeout
     pub fn default_timeout(&self) -> &str {
         self.timeout.as_deref().unwrap_or("1m")
@@ -90,6 +103,8 @@ mod tests {
         assert_eq!(settings.default_timeout(), "1m");
         assert_eq!(settings.task_queue(), "default");
         assert!(!settings.is_long_running());
+        assert!(!settings.is_service());
+        assert_eq!(settings.shutdown_signal(), "shutdown");
     }
 
     #[test]
@@ -119,5 +134,13 @@ mod tests {
         let json = r#"{"_workflowType": "service"}"#;
         let settings: WorkflowSettings = serde_json::from_str(json).unwrap();
         assert_eq!(settings.workflow_type, Some(WorkflowType::Service));
+        assert!(settings.is_service());
+    }
+
+    #[test]
+    fn test_shutdown_signal() {
+        let json = r#"{"_workflowType": "service", "_shutdownSignal": "stop"}"#;
+        let settings: WorkflowSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.shutdown_signal(), "stop");
     }
 }
@@ -41,6 +41,13 @@ pub fn validate_components(workflow: &WorkflowDefinition) -> ValidationResult {
                     });
                 }
             },
+            NodeType::Update if node.data.update_name.is_none() => {
+                // Update nodes must have an update_name
+                result.add_error(ValidationError::InvalidConfig {
+                    node_id: node.id.clone(),
+                    message: "Update node must have an update_name".to_string(),
+                });
+            },
             NodeType::ChildWorkflow => {
                 // Child workflow nodes need configuration
                 if node.data.config.is_none() {
@@ -70,6 +77,42 @@ pub fn validate_components(workflow: &WorkflowDefinition) -> ValidationResult {
             // Other node types don't have specific validation
             _ => {},
         }
+
+        // Signal and update names are emitted into the generated definitions
+        if let Some(name) = node.event_name() {
+            if !is_valid_event_name(name) {
+                result.add_error(ValidationError::InvalidConfig {
+                    node_id: node.id.clone(),
+                    message: format!("'{}' is not a valid signal or update name", name),
+                });
+            }
+        }
+    }
+
+    // Service workflows reserve the shutdown signal and should continue as new
+    if workflow.settings.is_service() {
+        let shutdown_signal = workflow.settings.shutdown_signal();
+        if !is_valid_event_name(shutdown_signal) {
+            result.add_error(ValidationError::InvalidSettings(format!(
+                "'{}' is not a valid shutdown signal name",
+                shutdown_signal
+            )));
+        }
+        for node in &workflow.nodes {
+            if node.event_name() == Some(shutdown_signal) {
+                result.add_error(ValidationError::InvalidConfig {
+                    node_id: node.id.clone(),
+                    message: format!(
+                        "'{}' is reserved for stopping the service workflow",
+                        shutdown_signal
+                    ),
+                });
+            }
+        }
+
+        if !workflow.settings.is_long_running() {
+            result.add_warning(ValidationWarning::ServiceWithoutContinueAsNew);
+        }
     }
 
     // Check for unused variables
@@ -85,6 +128,16 @@ pub fn validate_components(workflow: &WorkflowDefinition) -> ValidationResult {
     result
 }
 
+/// Check that a signal or update name is an identifier, so it can be quoted
+/// in the generated definitions and handler comments without escaping
+fn is_valid_event_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
 /// Find all variable names used in the workflow
 fn find_used_variables(workflow: &WorkflowDefinition) -> std::collections::HashSet<String> {
     let mut used = std::collections::HashSet::new();
@@ -272,4 +325,93 @@ mod tests {
             .iter()
             .any(|e| matches!(e, ValidationError::MissingActivityName { .. })));
     }
+
+    #[test]
+    fn test_service_reserves_shutdown_signal() {
+        let workflow = WorkflowDefinition {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            nodes: vec![
+                WorkflowNode {
+                    id: "trigger".to_string(),
+                    node_type: NodeType::Trigger,
+                    data: NodeData::default(),
+                    position: Position::default(),
+                },
+                WorkflowNode {
+                    id: "stop".to_string(),
+                    node_type: NodeType::Signal,
+                    data: NodeData {
+                        signal_name: Some("shutdown".to_string()),
+                        ..Default::default()
+                    },
+                    position: Position::default(),
+                },
+                WorkflowNode {
+                    id: "quote".to_string(),
+                    node_type: NodeType::Update,
+                    data: NodeData::default(),
+                    position: Position::default(),
+                },
+            ],
+            edges: vec![
+                WorkflowEdge::new("e1", "trigger", "stop"),
+                WorkflowEdge::new("e2", "trigger", "quote"),
+            ],
+            variables: vec![],
+            settings: serde_json::from_str(r#"{"_workflowType": "service"}"#).unwrap(),
+        };
+
+        let result = validate_components(&workflow);
+        assert!(result.errors.iter().any(|e| matches!(e, ValidationError::InvalidConfig { node_id, message } if node_id == "stop" && message.contains("reserved"))));
+        assert!(result.errors.iter().any(
+            |e| matches!(e, ValidationError::InvalidConfig { node_id, .. } if node_id == "quote")
+        ));
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| matches!(w, ValidationWarning::ServiceWithoutContinueAsNew)));
+    }
+
+    #[test]
+    fn test_invalid_event_names() {
+        let mut workflow = WorkflowDefinition {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            nodes: vec![
+                WorkflowNode {
+                    id: "trigger".to_string(),
+                    node_type: NodeType::Trigger,
+                    data: NodeData::default(),
+                    position: Position::default(),
+                },
+                WorkflowNode {
+                    id: "approve".to_string(),
+                    node_type: NodeType::Signal,
+                    data: NodeData {
+                        signal_name: Some("approve');\nprocess.exit(1); //".to_string()),
+                        ..Default::default()
+                    },
+                    position: Position::default(),
+                },
+            ],
+            edges: vec![WorkflowEdge::new("e1", "trigger", "approve")],
+            variables: vec![],
+            settings: serde_json::from_str(r#"{"_workflowType": "service"}"#).unwrap(),
+        };
+        workflow.settings.shutdown_signal = Some("stop\n".to_string());
+
+        let result = validate_components(&workflow);
+        assert!(result.errors.iter().any(
+            |e| matches!(e, ValidationError::InvalidConfig { node_id, .. } if node_id == "approve")
+        ));
+        assert!(result.errors.iter().any(
+            |e| matches!(e, ValidationError::InvalidSettings(message) if message.contains("stop"))
+        ));
+
+        workflow.settings.shutdown_signal = Some("stop".to_string());
+        workflow.nodes[1].data.signal_name = Some("approve".to_string());
+        let result = validate_components(&workflow);
+        assert!(result.errors.is_empty());
+    }
 }
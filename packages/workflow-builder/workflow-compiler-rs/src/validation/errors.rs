@@ -35,6 +35,9 @@ pub enum ValidationError {
     #[error("Node '{node_id}' has invalid configuration: {message}")]
     InvalidConfig { node_id: String, message: String },
 
+    #[error("Workflow settings are invalid: {0}")]
+    InvalidSettings(String),
+
     #[error("Workflow is missing required field: {0}")]
     MissingRequiredField(String),
 
@@ -56,6 +59,8 @@ pub enum ValidationWarning {
     UnusedVariable { var_name: String },
     /// Deprecated node type
     DeprecatedNodeType { node_id: String, node_type: String },
+    /// Service workflow never continues as new
+    ServiceWithoutContinueAsNew,
 }
 
 impl std::fmt::Display for ValidationWarning {
@@ -77,6 +82,13 @@ impl std::fmt::Display for ValidationWarning {
                     node_id, node_type
                 )
             },
+            ValidationWarning::ServiceWithoutContinueAsNew => {
+                write!(
+                    f,
+                    "Service workflow does not continue as new; its history will grow until \
+                     it is shut down."
+                )
+            },
         }
     }
 }
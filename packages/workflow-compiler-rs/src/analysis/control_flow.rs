@@ -0,0 +1,761 @@
+//! Control-flow graph construction.
+//!
+//! The control-flow graph is rooted at the trigger node. Edges that close a
+//! cycle (back-edges) are separated from the forward edges so that the forward
+//! graph is always acyclic, and dominators and post-dominators are computed on
+//! it to locate the node where diverging branches meet again.
+//!
+//! Edges to on-cancel cleanup branches are left out: a cleanup branch only
+//! runs once the workflow is cancelled, so it gets a graph of its own, rooted
+//! at the first node of the branch.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schema::{NodeType, WorkflowDefinition, WorkflowEdge, WorkflowNode};
+
+/// Forward control-flow view of a workflow definition
+pub struct ControlFlowGraph<'a> {
+    workflow: &'a WorkflowDefinition,
+    entry: Option<&'a str>,
+    successors: HashMap<&'a str, Vec<&'a WorkflowEdge>>,
+    predecessors: HashMap<&'a str, Vec<&'a WorkflowEdge>>,
+    back_edges: Vec<&'a WorkflowEdge>,
+    /// Reachable nodes in reverse postorder (a topological order of forward edges)
+    order: Vec<&'a str>,
+    /// Immediate dominator of every reachable node except the entry
+    idom: HashMap<&'a str, &'a str>,
+    /// Immediate post-dominator; absent when only the virtual exit post-dominates
+    ipdom: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> ControlFlowGraph<'a> {
+    /// Build the control-flow graph rooted at the workflow's trigger node
+    pub fn new(workflow: &'a WorkflowDefinition) -> Self {
+        let entry = workflow
+            .nodes
+            .iter()
+            .find(|n| n.node_type == NodeType::Trigger)
+            .map(|n| n.id.as_str());
+        Self::build(workflow, entry)
+    }
+
+    /// Build the control-flow graph of a cleanup branch, rooted at the node
+    /// its `cancel` edge leads to
+    pub fn rooted_at(workflow: &'a WorkflowDefinition, entry: &'a str) -> Self {
+        Self::build(workflow, Some(entry))
+    }
+
+    fn build(workflow: &'a WorkflowDefinition, entry: Option<&'a str>) -> Self {
+        let mut graph = Self {
+            workflow,
+            entry,
+            successors: HashMap::new(),
+            predecessors: HashMap::new(),
+            back_edges: Vec::new(),
+            order: Vec::new(),
+            idom: HashMap::new(),
+            ipdom: HashMap::new(),
+        };
+
+        if let Some(entry) = entry {
+            graph.classify_edges(entry);
+            graph.compute_dominators();
+        }
+
+        graph
+    }
+
+    /// The node the graph is rooted at: the trigger, or the first node of a
+    /// cleanup branch
+    pub fn entry(&self) -> Option<&'a str> {
+        self.entry
+    }
+
+    /// Look up a node by ID
+    pub fn node(&self, id: &str) -> Option<&'a WorkflowNode> {
+        self.workflow.get_node(id)
+    }
+
+    /// Reachable nodes in dependency order (every node follows its forward predecessors)
+    pub fn order(&self) -> &[&'a str] {
+        &self.order
+    }
+
+    /// Whether the node can be reached from the root
+    pub fn is_reachable(&self, id: &str) -> bool {
+        self.entry == Some(id) || self.idom.contains_key(id)
+    }
+
+    /// Forward edges leaving a node, in definition order
+    pub fn successors(&self, id: &str) -> &[&'a WorkflowEdge] {
+        self.successors.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Forward edges entering a node, in definition order
+    pub fn predecessors(&self, id: &str) -> &[&'a WorkflowEdge] {
+        self.predecessors.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Edges that close a cycle back to a node already on the current path
+    pub fn back_edges(&self) -> &[&'a WorkflowEdge] {
+        &self.back_edges
+    }
+
+    /// Whether the edge was classified as a back-edge
+    pub fn is_back_edge(&self, edge: &WorkflowEdge) -> bool {
+        self.back_edges.iter().any(|e| e.id == edge.id)
+    }
+
+    /// Immediate dominator of a node (`None` for the entry and unreachable nodes)
+    pub fn immediate_dominator(&self, id: &str) -> Option<&'a str> {
+        self.idom.get(id).copied()
+    }
+
+    /// Whether every path from the trigger to `node` passes through `dominator`
+    pub fn dominates(&self, dominator: &str, node: &str) -> bool {
+        if !self.is_reachable(node) {
+            return false;
+        }
+        let mut current = Some(node);
+        while let Some(id) = current {
+            if id == dominator {
+                return true;
+            }
+            current = self.immediate_dominator(id);
+        }
+        false
+    }
+
+    /// The node where all paths leaving `id` meet again.
+    ///
+    /// Returns `None` when the paths only meet at the end of the workflow.
+    pub fn immediate_post_dominator(&self, id: &str) -> Option<&'a str> {
+        self.ipdom.get(id).copied()
+    }
+
+    /// Whether `to` can be reached from `from` along forward edges
+    pub fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut stack = vec![from];
+        let mut seen = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == to {
+                return true;
+            }
+            if seen.insert(id) {
+                stack.extend(self.successors(id).iter().map(|e| e.target.as_str()));
+            }
+        }
+        false
+    }
+
+    /// Split the forward edges leaving a loop node into body and exit edges.
+    ///
+    /// Edges with a `body` or `done`/`exit` source handle are classified by
+    /// handle; otherwise an edge belongs to the body when it leads to a
+    /// back-edge returning to the loop.
+    pub fn loop_edges(&self, loop_id: &str) -> (Vec<&'a WorkflowEdge>, Vec<&'a WorkflowEdge>) {
+        let back_sources: Vec<&str> = self
+            .back_edges
+            .iter()
+            .filter(|e| e.target == loop_id)
+            .map(|e| e.source.as_str())
+            .collect();
+
+        self.successors(loop_id)
+            .iter()
+            .partition(|edge| match edge.source_handle.as_deref() {
+                Some("body") => true,
+                Some("done") | Some("exit") => false,
+                _ => back_sources
+                    .iter()
+                    .any(|source| self.reaches(&edge.target, source)),
+            })
+    }
+
+    /// Split the forward edges leaving a retry or map node into the `body`
+    /// edges entering its region and the edges taken once the region is done
+    pub fn region_edges(&self, id: &str) -> (Vec<&'a WorkflowEdge>, Vec<&'a WorkflowEdge>) {
+        self.successors(id)
+            .iter()
+            .partition(|edge| edge.source_handle.as_deref() == Some("body"))
+    }
+
+    /// Nodes in the region of a retry or map node: those reached through its
+    /// `body` edges before the target of its other edge, in traversal order
+    pub fn body_region(&self, id: &str) -> Vec<&'a str> {
+        let (body, exit) = self.region_edges(id);
+        let stop = exit.first().map(|e| e.target.as_str());
+
+        let mut region = Vec::new();
+        let mut stack: Vec<&'a str> = body.iter().rev().map(|e| e.target.as_str()).collect();
+        while let Some(node) = stack.pop() {
+            if Some(node) == stop || region.contains(&node) {
+                continue;
+            }
+            region.push(node);
+            stack.extend(
+                self.successors(node)
+                    .iter()
+                    .rev()
+                    .map(|e| e.target.as_str()),
+            );
+        }
+        region
+    }
+
+    /// The node whose result a map collects for each item: the last node of
+    /// the body that produces a result and runs for every item
+    pub fn item_result(&self, map_id: &str) -> Option<&'a WorkflowNode> {
+        let region = self.body_region(map_id);
+        let ends = self.region_ends(&region);
+
+        let candidates: Vec<&'a WorkflowNode> = region
+            .iter()
+            .filter_map(|id| self.node(id))
+            .filter(|n| n.produces_result() && ends.iter().all(|end| self.dominates(&n.id, end)))
+            .collect();
+        candidates.iter().copied().find(|n| {
+            candidates
+                .iter()
+                .all(|other| self.dominates(&other.id, &n.id))
+        })
+    }
+
+    /// Nodes of a retry region whose results are available once the region
+    /// succeeds: those that produce a result and run on every path through
+    /// it. A region that can end the workflow has none.
+    pub fn retry_results(&self, retry_id: &str) -> Vec<&'a WorkflowNode> {
+        let region = self.body_region(retry_id);
+        let nodes: Vec<&'a WorkflowNode> = region.iter().filter_map(|id| self.node(id)).collect();
+        if nodes.iter().any(|n| n.node_type == NodeType::End) {
+            return Vec::new();
+        }
+        let ends = self.region_ends(&region);
+        nodes
+            .into_iter()
+            .filter(|n| n.produces_result() && ends.iter().all(|end| self.dominates(&n.id, end)))
+            .collect()
+    }
+
+    /// Whether `node` runs once the retry region that ran `source` has
+    /// succeeded, so the result of `source` is available to it
+    pub fn runs_after_retry(&self, source: &str, node: &str) -> bool {
+        self.order.iter().any(|id| {
+            self.node(id)
+                .is_some_and(|n| n.node_type == NodeType::Retry)
+                && self.dominates(id, node)
+                && !self.body_region(id).contains(&node)
+                && self.retry_results(id).iter().any(|n| n.id == source)
+        })
+    }
+
+    /// Nodes through which paths leave a region, or end inside it
+    fn region_ends(&self, region: &[&'a str]) -> Vec<&'a str> {
+        region
+            .iter()
+            .copied()
+            .filter(|id| {
+                let edges = self.successors(id);
+                edges.is_empty() || edges.iter().any(|e| !region.contains(&e.target.as_str()))
+            })
+            .collect()
+    }
+
+    /// Whether the branches leaving `id` run concurrently: it has several
+    /// forward edges, and is not a node whose edges select one branch or
+    /// enter a region of its own
+    pub fn runs_branches_concurrently(&self, id: &str) -> bool {
+        let (Some(node), edges) = (self.node(id), self.successors(id)) else {
+            return false;
+        };
+        if edges.len() < 2 {
+            return false;
+        }
+        match node.node_type {
+            NodeType::Conditional | NodeType::Condition | NodeType::Loop => false,
+            NodeType::Map | NodeType::Retry => false,
+            NodeType::Phase => !self.workflow.has_members(id),
+            NodeType::Approval => !edges.iter().any(|e| e.source_handle.is_some()),
+            _ => !(node.handles_errors() && edges.iter().any(|e| e.is_error())),
+        }
+    }
+
+    /// For each edge leaving a node whose branches run concurrently, the
+    /// nodes of that branch whose results are available once the branches
+    /// meet again: those that run on every path of the branch and produce a
+    /// result, outside any phase nested in the branch
+    pub fn branch_results(&self, id: &str) -> Vec<Vec<&'a WorkflowNode>> {
+        let Some(node) = self.node(id) else {
+            return Vec::new();
+        };
+        let merge = self.immediate_post_dominator(id);
+        self.successors(id)
+            .iter()
+            .map(|edge| {
+                let entry = edge.target.as_str();
+                let mut results = Vec::new();
+                let mut current = Some(entry);
+                while let Some(step) = current.filter(|step| Some(*step) != merge) {
+                    if let Some(step_node) = self.node(step).filter(|n| {
+                        n.produces_result()
+                            && n.parent_id == node.parent_id
+                            && self.dominates(entry, &n.id)
+                    }) {
+                        results.push(step_node);
+                    }
+                    current = self.immediate_post_dominator(step);
+                }
+                results
+            })
+            .collect()
+    }
+
+    /// Whether `node` runs once the concurrent branch that ran `source` has
+    /// joined the others, so the result of `source` is available to it
+    pub fn runs_after_join(&self, source: &str, node: &str) -> bool {
+        self.order.iter().any(|id| {
+            self.runs_branches_concurrently(id)
+                && self
+                    .immediate_post_dominator(id)
+                    .is_some_and(|merge| self.dominates(merge, node))
+                && self
+                    .branch_results(id)
+                    .iter()
+                    .flatten()
+                    .any(|n| n.id == source)
+        })
+    }
+
+    /// Depth-first walk from the entry that splits forward edges from back-edges
+    fn classify_edges(&mut self, entry: &'a str) {
+        let mut outgoing: HashMap<&'a str, Vec<&'a WorkflowEdge>> = HashMap::new();
+        for edge in &self.workflow.edges {
+            if self.workflow.get_node(&edge.target).is_some() && !edge.is_cancel() {
+                outgoing.entry(edge.source.as_str()).or_default().push(edge);
+            }
+        }
+
+        // `true` while a node is on the current DFS path, `false` once finished
+        let mut on_path: HashMap<&'a str, bool> = HashMap::new();
+        let mut postorder = Vec::new();
+        // Stack of (node, index of the next outgoing edge to explore)
+        let mut stack: Vec<(&'a str, usize)> = vec![(entry, 0)];
+        on_path.insert(entry, true);
+
+        while let Some((node, next)) = stack.pop() {
+            let edges = outgoing.get(node).map(Vec::as_slice).unwrap_or(&[]);
+            if let Some(edge) = edges.get(next) {
+                stack.push((node, next + 1));
+                let target = edge.target.as_str();
+                if edge.is_loop_back() {
+                    self.back_edges.push(edge);
+                    continue;
+                }
+                match on_path.get(target) {
+                    Some(true) => self.back_edges.push(edge),
+                    Some(false) => self.add_forward_edge(edge),
+                    None => {
+                        self.add_forward_edge(edge);
+                        on_path.insert(target, true);
+                        stack.push((target, 0));
+                    }
+                }
+            } else {
+                on_path.insert(node, false);
+                postorder.push(node);
+            }
+        }
+
+        postorder.reverse();
+        self.order = postorder;
+    }
+
+    fn add_forward_edge(&mut self, edge: &'a WorkflowEdge) {
+        self.successors
+            .entry(edge.source.as_str())
+            .or_default()
+            .push(edge);
+        self.predecessors
+            .entry(edge.target.as_str())
+            .or_default()
+            .push(edge);
+    }
+
+    /// Compute dominators and post-dominators over the (acyclic) forward graph
+    fn compute_dominators(&mut self) {
+        let count = self.order.len();
+        let index: HashMap<&str, usize> = self
+            .order
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+
+        let preds: Vec<Vec<usize>> = self
+            .order
+            .iter()
+            .map(|id| {
+                self.predecessors(id)
+                    .iter()
+                    .map(|e| index[e.source.as_str()])
+                    .collect()
+            })
+            .collect();
+
+        // Post-dominators are dominators of the reversed graph, rooted at a
+        // virtual exit (index `count`) that every sink node flows into.
+        let exit = count;
+        let mut reverse_preds: Vec<Vec<usize>> = self
+            .order
+            .iter()
+            .map(|id| {
+                let succs: Vec<usize> = self
+                    .successors(id)
+                    .iter()
+                    .map(|e| index[e.target.as_str()])
+                    .collect();
+                if succs.is_empty() {
+                    vec![exit]
+                } else {
+                    succs
+                }
+            })
+            .collect();
+        reverse_preds.push(Vec::new());
+
+        let forward_order: Vec<usize> = (0..count).collect();
+        let reverse_order: Vec<usize> = std::iter::once(exit).chain((0..count).rev()).collect();
+
+        let dom = immediate_dominators(&forward_order, &preds);
+        let pdom = immediate_dominators(&reverse_order, &reverse_preds);
+
+        for (i, id) in self.order.iter().enumerate() {
+            if let Some(d) = dom[i].filter(|d| *d != i) {
+                self.idom.insert(id, self.order[d]);
+            }
+            if let Some(p) = pdom[i].filter(|p| *p != exit) {
+                self.ipdom.insert(id, self.order[p]);
+            }
+        }
+    }
+}
+
+/// Immediate dominators for an acyclic graph given in topological order.
+///
+/// `order[0]` is the root; `preds` is indexed by node. Returns the immediate
+/// dominator of each node (the root maps to itself).
+fn immediate_dominators(order: &[usize], preds: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let mut rank = vec![usize::MAX; preds.len()];
+    for (position, node) in order.iter().enumerate() {
+        rank[*node] = position;
+    }
+
+    let mut idom: Vec<Option<usize>> = vec![None; preds.len()];
+    let Some(&root) = order.first() else {
+        return idom;
+    };
+    idom[root] = Some(root);
+
+    for &node in &order[1..] {
+        let mut new_idom: Option<usize> = None;
+        for &pred in &preds[node] {
+            if idom[pred].is_none() {
+                continue;
+            }
+            new_idom = Some(match new_idom {
+                None => pred,
+                Some(current) => intersect(current, pred, &idom, &rank),
+            });
+        }
+        idom[node] = new_idom;
+    }
+
+    idom
+}
+
+fn intersect(mut a: usize, mut b: usize, idom: &[Option<usize>], rank: &[usize]) -> usize {
+    while a != b {
+        while rank[a] > rank[b] {
+            a = idom[a].unwrap_or(a);
+        }
+        while rank[b] > rank[a] {
+            b = idom[b].unwrap_or(b);
+        }
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{NodeData, Position};
+
+    fn node(id: &str, node_type: NodeType) -> WorkflowNode {
+        WorkflowNode {
+            id: id.to_string(),
+            node_type,
+            data: NodeData {
+                label: id.to_string(),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        }
+    }
+
+    fn workflow(nodes: Vec<WorkflowNode>, edges: &[(&str, &str)]) -> WorkflowDefinition {
+        WorkflowDefinition {
+            nodes,
+            edges: edges
+                .iter()
+                .enumerate()
+                .map(|(i, (s, t))| WorkflowEdge::new(format!("e{}", i), *s, *t))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_order_follows_edges_not_node_order() {
+        let wf = workflow(
+            vec![
+                node("end", NodeType::End),
+                node("b", NodeType::Activity),
+                node("a", NodeType::Activity),
+                node("start", NodeType::Trigger),
+            ],
+            &[("start", "a"), ("a", "b"), ("b", "end")],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+
+        assert_eq!(cfg.order(), &["start", "a", "b", "end"]);
+    }
+
+    #[test]
+    fn test_diamond_post_dominator() {
+        let wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("split", NodeType::Activity),
+                node("left", NodeType::Activity),
+                node("right", NodeType::Activity),
+                node("join", NodeType::Activity),
+                node("end", NodeType::End),
+            ],
+            &[
+                ("start", "split"),
+                ("split", "left"),
+                ("split", "right"),
+                ("left", "join"),
+                ("right", "join"),
+                ("join", "end"),
+            ],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+
+        assert_eq!(cfg.immediate_post_dominator("split"), Some("join"));
+        assert_eq!(cfg.immediate_dominator("join"), Some("split"));
+        assert!(cfg.dominates("split", "left"));
+        assert!(!cfg.dominates("left", "join"));
+    }
+
+    #[test]
+    fn test_concurrent_branch_results() {
+        let wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("split", NodeType::Parallel),
+                node("left", NodeType::Activity),
+                node("check", NodeType::Condition),
+                node("yes", NodeType::Activity),
+                node("after", NodeType::Activity),
+                node("right", NodeType::Activity),
+                node("join", NodeType::Join),
+                node("end", NodeType::End),
+            ],
+            &[
+                ("start", "split"),
+                ("split", "left"),
+                ("split", "right"),
+                ("left", "check"),
+                ("check", "yes"),
+                ("check", "after"),
+                ("yes", "after"),
+                ("after", "join"),
+                ("right", "join"),
+                ("join", "end"),
+            ],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+
+        // Only the steps every path of a branch runs are available at the join
+        assert!(cfg.runs_branches_concurrently("split"));
+        assert!(!cfg.runs_branches_concurrently("check"));
+        let results: Vec<Vec<&str>> = cfg
+            .branch_results("split")
+            .iter()
+            .map(|branch| branch.iter().map(|n| n.id.as_str()).collect())
+            .collect();
+        assert_eq!(results, vec![vec!["left", "after"], vec!["right"]]);
+        assert!(cfg.runs_after_join("after", "end"));
+        assert!(!cfg.runs_after_join("yes", "end"));
+        assert!(!cfg.runs_after_join("left", "right"));
+    }
+
+    #[test]
+    fn test_branches_to_separate_ends_have_no_merge() {
+        let wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("split", NodeType::Activity),
+                node("end-a", NodeType::End),
+                node("end-b", NodeType::End),
+            ],
+            &[("start", "split"), ("split", "end-a"), ("split", "end-b")],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+
+        assert_eq!(cfg.immediate_post_dominator("split"), None);
+    }
+
+    #[test]
+    fn test_back_edges_are_separated() {
+        let wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("loop", NodeType::Loop),
+                node("body", NodeType::Activity),
+                node("end", NodeType::End),
+            ],
+            &[
+                ("start", "loop"),
+                ("loop", "body"),
+                ("body", "loop"),
+                ("loop", "end"),
+            ],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+
+        assert_eq!(cfg.back_edges().len(), 1);
+        assert_eq!(cfg.back_edges()[0].source, "body");
+        assert!(cfg.successors("body").is_empty());
+        assert_eq!(cfg.predecessors("loop").len(), 1);
+    }
+
+    #[test]
+    fn test_loop_edges_split_body_and_exit() {
+        let wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("loop", NodeType::Loop),
+                node("body", NodeType::Activity),
+                node("end", NodeType::End),
+            ],
+            &[
+                ("start", "loop"),
+                ("loop", "end"),
+                ("loop", "body"),
+                ("body", "loop"),
+            ],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+        let (body, exit) = cfg.loop_edges("loop");
+
+        assert_eq!(body.len(), 1);
+        assert_eq!(body[0].target, "body");
+        assert_eq!(exit.len(), 1);
+        assert_eq!(exit[0].target, "end");
+    }
+
+    #[test]
+    fn test_marked_loop_back_edge_is_never_forward() {
+        let mut wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("loop", NodeType::Loop),
+                node("body", NodeType::Activity),
+            ],
+            // Exploring start -> body first would make body -> loop a forward edge
+            &[("start", "body"), ("start", "loop"), ("loop", "body")],
+        );
+        wf.edges
+            .push(WorkflowEdge::new("back", "body", "loop").as_loop_back());
+        let cfg = ControlFlowGraph::new(&wf);
+
+        assert_eq!(cfg.back_edges().len(), 1);
+        assert_eq!(cfg.back_edges()[0].id, "back");
+        assert_eq!(cfg.successors("loop").len(), 1);
+    }
+
+    #[test]
+    fn test_cleanup_branch_has_its_own_graph() {
+        let mut wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("work", NodeType::Activity),
+                node("cleanup", NodeType::Activity),
+                node("end", NodeType::End),
+            ],
+            &[("start", "work"), ("work", "end"), ("cleanup", "end")],
+        );
+        wf.edges
+            .push(WorkflowEdge::new("cancel", "start", "cleanup").with_source_handle("cancel"));
+
+        let cfg = ControlFlowGraph::new(&wf);
+        assert_eq!(cfg.successors("start").len(), 1);
+        assert!(!cfg.is_reachable("cleanup"));
+        assert_eq!(cfg.immediate_post_dominator("start"), Some("work"));
+
+        let cleanup = ControlFlowGraph::rooted_at(&wf, "cleanup");
+        assert_eq!(cleanup.order(), ["cleanup", "end"]);
+        assert!(!cleanup.is_reachable("work"));
+    }
+
+    #[test]
+    fn test_map_item_result_is_last_common_step() {
+        let mut wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("map", NodeType::Map),
+                node("fetch", NodeType::Activity),
+                node("charge", NodeType::Activity),
+                node("refund", NodeType::Activity),
+                node("done", NodeType::Activity),
+                node("end", NodeType::End),
+            ],
+            &[
+                ("start", "map"),
+                ("map", "done"),
+                ("fetch", "charge"),
+                ("fetch", "refund"),
+                ("charge", "done"),
+                ("refund", "done"),
+                ("done", "end"),
+            ],
+        );
+        wf.edges
+            .push(WorkflowEdge::new("body", "map", "fetch").with_source_handle("body"));
+
+        let cfg = ControlFlowGraph::new(&wf);
+        assert_eq!(cfg.body_region("map"), ["fetch", "charge", "refund"]);
+        // Only one of the branches runs for an item, so `fetch` is collected
+        assert_eq!(cfg.item_result("map").map(|n| n.id.as_str()), Some("fetch"));
+    }
+
+    #[test]
+    fn test_unreachable_nodes_are_excluded() {
+        let wf = workflow(
+            vec![
+                node("start", NodeType::Trigger),
+                node("end", NodeType::End),
+                node("island", NodeType::Activity),
+            ],
+            &[("start", "end")],
+        );
+        let cfg = ControlFlowGraph::new(&wf);
+
+        assert!(cfg.is_reachable("end"));
+        assert!(!cfg.is_reachable("island"));
+        assert_eq!(cfg.order().len(), 2);
+    }
+}
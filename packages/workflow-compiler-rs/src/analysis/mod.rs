@@ -0,0 +1,13 @@
+//! Workflow graph analysis.
+//!
+//! This module derives structural information from workflow edges and node
+//! configuration that is shared by validation and code generation, and infers
+//! the types of values flowing between nodes.
+
+mod control_flow;
+mod references;
+mod types;
+
+pub use control_flow::*;
+pub use references::*;
+pub use types::*;
@@ -0,0 +1,167 @@
+//! Data references embedded in node configuration.
+//!
+//! Mapping values may contain `{{...}}` references to the workflow input,
+//! state variables, or the output of another node, for example
+//! `{{nodes.fetch-order.output.id}}`. On a node's error branch,
+//! `{{nodes.<id>.error.message}}` reads the failure that sent it there, and
+//! inside the body of a map node `{{nodes.<id>.item}}` reads the current item.
+
+/// A reference to workflow data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataReference {
+    /// `input.<path>` - the workflow input
+    Input(Vec<String>),
+    /// `state.<path>` or `variables.<path>` - a workflow state variable
+    State(Vec<String>),
+    /// `nodes.<id>.output.<path>` - the result of another node
+    NodeOutput { node_id: String, path: Vec<String> },
+    /// `nodes.<id>.error.<path>` - the failure of a node with an error edge
+    NodeError { node_id: String, path: Vec<String> },
+    /// `nodes.<id>.item.<path>` - the current item of a map node
+    MapItem { node_id: String, path: Vec<String> },
+}
+
+impl DataReference {
+    /// Parse a reference expression (the text between `{{` and `}}`)
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = expr.trim();
+        let mut segments = expr.split('.').map(str::trim);
+        let root = segments.next().unwrap_or_default();
+        let rest: Vec<String> = segments.map(str::to_string).collect();
+
+        if rest.iter().any(String::is_empty) {
+            return Err(format!("reference '{}' has an empty path segment", expr));
+        }
+
+        match root {
+            "input" => Ok(DataReference::Input(rest)),
+            "state" | "variables" if !rest.is_empty() => Ok(DataReference::State(rest)),
+            "nodes" => match rest.as_slice() {
+                [node_id, output, path @ ..] if output == "output" => {
+                    Ok(DataReference::NodeOutput {
+                        node_id: node_id.clone(),
+                        path: path.to_vec(),
+                    })
+                }
+                [node_id, error, path @ ..] if error == "error" => Ok(DataReference::NodeError {
+                    node_id: node_id.clone(),
+                    path: path.to_vec(),
+                }),
+                [node_id, item, path @ ..] if item == "item" => Ok(DataReference::MapItem {
+                    node_id: node_id.clone(),
+                    path: path.to_vec(),
+                }),
+                _ => Err(format!(
+                    "node reference '{}' must have the form nodes.<id>.output, \
+                     nodes.<id>.error or nodes.<id>.item",
+                    expr
+                )),
+            },
+            "" => Err("empty reference".to_string()),
+            _ => Err(format!(
+                "reference '{}' must start with input, state, variables or nodes",
+                expr
+            )),
+        }
+    }
+
+    /// The referenced node, for node output, error and item references
+    pub fn node_id(&self) -> Option<&str> {
+        match self {
+            DataReference::NodeOutput { node_id, .. }
+            | DataReference::NodeError { node_id, .. }
+            | DataReference::MapItem { node_id, .. } => Some(node_id),
+            _ => None,
+        }
+    }
+}
+
+/// A piece of a mapping template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart {
+    /// Literal text
+    Text(String),
+    /// A `{{...}}` reference
+    Reference(DataReference),
+}
+
+/// Split a mapping template into literal text and references
+pub fn parse_template(template: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            parts.push(TemplatePart::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("unclosed '{{{{' in '{}'", template))?;
+        parts.push(TemplatePart::Reference(DataReference::parse(&after[..end])?));
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Text(rest.to_string()));
+    }
+
+    Ok(parts)
+}
+
+/// All references in a mapping template, ignoring parse errors
+pub fn template_references(template: &str) -> Vec<DataReference> {
+    parse_template(template)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|part| match part {
+            TemplatePart::Reference(reference) => Some(reference),
+            TemplatePart::Text(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_output_reference() {
+        assert_eq!(
+            DataReference::parse("nodes.fetch-order.output.id"),
+            Ok(DataReference::NodeOutput {
+                node_id: "fetch-order".to_string(),
+                path: vec!["id".to_string()],
+            })
+        );
+        assert_eq!(
+            DataReference::parse("nodes.charge.error.message"),
+            Ok(DataReference::NodeError {
+                node_id: "charge".to_string(),
+                path: vec!["message".to_string()],
+            })
+        );
+        assert_eq!(
+            DataReference::parse("nodes.each-order.item"),
+            Ok(DataReference::MapItem {
+                node_id: "each-order".to_string(),
+                path: Vec::new(),
+            })
+        );
+        assert!(DataReference::parse("nodes.fetch-order.id").is_err());
+        assert!(DataReference::parse("secrets.token").is_err());
+    }
+
+    #[test]
+    fn test_parse_template_parts() {
+        let parts = parse_template("Order {{ input.orderId }} ready").unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                TemplatePart::Text("Order ".to_string()),
+                TemplatePart::Reference(DataReference::Input(vec!["orderId".to_string()])),
+                TemplatePart::Text(" ready".to_string()),
+            ]
+        );
+        assert!(parse_template("{{input.orderId").is_err());
+    }
+}
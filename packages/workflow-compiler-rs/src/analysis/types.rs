@@ -0,0 +1,565 @@
+//! Static type inference.
+//!
+//! Node outputs are typed from their component's port types, the workflow
+//! input and result from DataIn/DataOut fields, and state from the declared
+//! variables. Expressions and mapping templates are typed against these so
+//! mismatches surface during validation instead of at runtime.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::analysis::{parse_template, ControlFlowGraph, DataReference, TemplatePart};
+use crate::expression::{parse, BinaryOp, Expr, ExprKind, Span, UnaryOp};
+use crate::schema::{
+    failure_details_type, fields_type, LoopMode, MapConfig, NodeType, TypeDescriptor,
+    WorkflowDefinition, WorkflowNode, DEFAULT_ITEM_VARIABLE,
+};
+
+/// An expression whose operand has the wrong type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub expected: String,
+    pub found: String,
+    pub span: Span,
+}
+
+impl TypeError {
+    fn new(expected: impl ToString, found: &TypeDescriptor, span: Span) -> Self {
+        Self {
+            expected: expected.to_string(),
+            found: found.to_string(),
+            span,
+        }
+    }
+}
+
+/// Types of everything an expression can read
+#[derive(Debug, Clone)]
+pub struct TypeEnvironment {
+    input: TypeDescriptor,
+    result: Option<TypeDescriptor>,
+    state: BTreeMap<String, TypeDescriptor>,
+    workflow: TypeDescriptor,
+    nodes: HashMap<String, TypeDescriptor>,
+    /// Element type of the collection of each map node
+    items: HashMap<String, TypeDescriptor>,
+    locals: HashMap<String, TypeDescriptor>,
+}
+
+impl TypeEnvironment {
+    pub fn new(workflow: &WorkflowDefinition) -> Self {
+        let state: BTreeMap<_, _> = workflow
+            .variables
+            .iter()
+            .map(|v| (v.name.clone(), TypeDescriptor::from(&v.variable_type)))
+            .collect();
+
+        let mut env = Self {
+            input: data_type(workflow, NodeType::DataIn).unwrap_or_default(),
+            result: data_type(workflow, NodeType::DataOut),
+            workflow: TypeDescriptor::object([
+                ("workflowId", TypeDescriptor::String),
+                ("runId", TypeDescriptor::String),
+                ("workflowType", TypeDescriptor::String),
+                ("taskQueue", TypeDescriptor::String),
+                ("namespace", TypeDescriptor::String),
+                ("attempt", TypeDescriptor::Number),
+            ]),
+            nodes: HashMap::new(),
+            items: HashMap::new(),
+            locals: HashMap::new(),
+            state,
+        };
+
+        for node in workflow.nodes.iter().filter(|n| n.produces_result()) {
+            let output = env.component_output(node);
+            env.nodes.insert(node.id.clone(), output);
+        }
+
+        // Loop and map item variables take the element type of their collection
+        for node in workflow
+            .nodes
+            .iter()
+            .filter(|n| matches!(n.node_type, NodeType::Loop | NodeType::Map))
+        {
+            let mode = LoopMode::resolve(
+                node.data.loop_type.as_deref(),
+                node.data.condition.is_some(),
+                node.data.collection.is_some(),
+            );
+            if node.node_type == NodeType::Loop && mode != LoopMode::ForEach {
+                continue;
+            }
+            let item = match node.data.collection.as_deref().map(parse) {
+                Some(Ok(expr)) => match env.infer(&expr) {
+                    Ok(TypeDescriptor::Array(item)) => *item,
+                    _ => TypeDescriptor::Any,
+                },
+                _ => TypeDescriptor::Any,
+            };
+            if node.node_type == NodeType::Map {
+                env.items.insert(node.id.clone(), item.clone());
+            }
+            let name = node
+                .data
+                .item_variable
+                .as_deref()
+                .unwrap_or(DEFAULT_ITEM_VARIABLE);
+            let item = match env.locals.get(name) {
+                Some(existing) => existing.unify(&item),
+                None => item,
+            };
+            env.locals.insert(name.to_string(), item);
+        }
+
+        // Maps collect the result of the last step of their body for every
+        // item; inner maps come later in the flow, so they are typed first
+        let cfg = ControlFlowGraph::new(workflow);
+        for id in cfg.order().iter().rev() {
+            if cfg.node(id).is_some_and(|n| n.node_type == NodeType::Map) {
+                let item_result = cfg
+                    .item_result(id)
+                    .map(|n| env.node_output(&n.id))
+                    .unwrap_or_default();
+                env.nodes
+                    .insert(id.to_string(), MapConfig::output_type(item_result));
+            }
+        }
+
+        env
+    }
+
+    /// Environment in which `name` is also bound, to a value of type `ty`
+    pub fn with_local(&self, name: &str, ty: TypeDescriptor) -> Self {
+        let mut env = self.clone();
+        env.locals.insert(name.to_string(), ty);
+        env
+    }
+
+    /// Output type of a node that produces a result
+    fn component_output(&self, node: &WorkflowNode) -> TypeDescriptor {
+        if let Some(signal) = node.signal_config() {
+            return signal.output_type();
+        }
+        if let Some(update) = node.update_config() {
+            return update.output_type();
+        }
+        if let Some(child) = node.child_workflow_config() {
+            return child.output_type();
+        }
+        if let Some(timer) = node.timer_config() {
+            return timer.output_type();
+        }
+        if let Some(approval) = node.approval_config() {
+            return approval.output_type();
+        }
+        if let Some(agent) = node.agent_config() {
+            return agent.output_type();
+        }
+        let Some(mut output) = node.port_types().map(|types| types.output) else {
+            return TypeDescriptor::Any;
+        };
+
+        // Workflow-scoped reads return the declared variable's type
+        let workflow_scope = node
+            .data
+            .variable_scope
+            .as_deref()
+            .is_some_and(|s| !matches!(s.to_lowercase().as_str(), "service" | "project"));
+        if node.is_get_variable() && workflow_scope {
+            let declared = node
+                .data
+                .variable_name
+                .as_deref()
+                .and_then(|name| self.state.get(name));
+            if let (Some(declared), TypeDescriptor::Object(fields)) = (declared, &mut output) {
+                fields.insert("value".to_string(), declared.clone());
+            }
+        }
+        output
+    }
+
+    /// Type of the workflow result declared by DataOut nodes
+    pub fn result_type(&self) -> Option<&TypeDescriptor> {
+        self.result.as_ref()
+    }
+
+    /// Declared type of a state variable
+    pub fn variable_type(&self, name: &str) -> Option<&TypeDescriptor> {
+        self.state.get(name)
+    }
+
+    /// Type of the items a map node runs its body for, `Any` when unknown
+    pub fn map_item(&self, node_id: &str) -> TypeDescriptor {
+        self.items.get(node_id).cloned().unwrap_or_default()
+    }
+
+    /// Output type of a node, `Any` when it is unknown
+    pub fn node_output(&self, node_id: &str) -> TypeDescriptor {
+        self.nodes.get(node_id).cloned().unwrap_or_default()
+    }
+
+    /// Type of a `{{...}}` mapping reference
+    pub fn reference_type(&self, reference: &DataReference) -> TypeDescriptor {
+        let (root, path) = match reference {
+            DataReference::Input(path) => (self.input.clone(), path.as_slice()),
+            DataReference::State(path) => {
+                (TypeDescriptor::Object(self.state.clone()), path.as_slice())
+            }
+            DataReference::NodeOutput { node_id, path } => {
+                (self.node_output(node_id), path.as_slice())
+            }
+            DataReference::NodeError { path, .. } => (failure_details_type(), path.as_slice()),
+            DataReference::MapItem { node_id, path } => (self.map_item(node_id), path.as_slice()),
+        };
+        path.iter().fold(root, |ty, segment| match &ty {
+            TypeDescriptor::Object(_) => ty.field(segment).cloned().unwrap_or_default(),
+            TypeDescriptor::Array(_) | TypeDescriptor::String if segment == "length" => {
+                TypeDescriptor::Number
+            }
+            _ => TypeDescriptor::Any,
+        })
+    }
+
+    /// Type of a mapping template: a lone reference keeps its type, anything
+    /// else is interpolated into a string
+    pub fn template_type(&self, template: &str) -> TypeDescriptor {
+        match parse_template(template).as_deref() {
+            Ok([TemplatePart::Reference(reference)]) => self.reference_type(reference),
+            Ok(_) => TypeDescriptor::String,
+            Err(_) => TypeDescriptor::Any,
+        }
+    }
+
+    /// Infer the type of an expression, reporting operands of the wrong type
+    pub fn infer(&self, expr: &Expr) -> Result<TypeDescriptor, TypeError> {
+        match &expr.kind {
+            ExprKind::Null => Ok(TypeDescriptor::Null),
+            ExprKind::Bool(_) => Ok(TypeDescriptor::Boolean),
+            ExprKind::Number(_) => Ok(TypeDescriptor::Number),
+            ExprKind::String(_) => Ok(TypeDescriptor::String),
+            ExprKind::Identifier(name) => Ok(match name.as_str() {
+                "input" => self.input.clone(),
+                "state" => TypeDescriptor::Object(self.state.clone()),
+                "workflow" => self.workflow.clone(),
+                _ => self.locals.get(name).cloned().unwrap_or_default(),
+            }),
+            ExprKind::Member { object, property } => {
+                if let Some(node_id) = expr.as_node_output() {
+                    return Ok(self.node_output(node_id));
+                }
+                if object.as_node_selector().is_some() || object.is_identifier("nodes") {
+                    return Ok(TypeDescriptor::Any);
+                }
+                let ty = self.infer(object)?;
+                match &ty {
+                    TypeDescriptor::Object(_) => {
+                        Ok(ty.field(property).cloned().unwrap_or_default())
+                    }
+                    TypeDescriptor::Array(_) | TypeDescriptor::String if property == "length" => {
+                        Ok(TypeDescriptor::Number)
+                    }
+                    TypeDescriptor::Any | TypeDescriptor::Array(_) | TypeDescriptor::String => {
+                        Ok(TypeDescriptor::Any)
+                    }
+                    _ => Err(TypeError::new("object", &ty, object.span)),
+                }
+            }
+            ExprKind::Index { object, index } => {
+                if expr.as_node_selector().is_some() {
+                    return Ok(TypeDescriptor::Any);
+                }
+                let ty = self.infer(object)?;
+                let index_ty = self.infer(index)?;
+                match &ty {
+                    TypeDescriptor::Array(item) => {
+                        expect(&index_ty, &TypeDescriptor::Number, index.span)?;
+                        Ok((**item).clone())
+                    }
+                    TypeDescriptor::Object(_) => Ok(match &index.kind {
+                        ExprKind::String(key) => ty.field(key).cloned().unwrap_or_default(),
+                        _ => TypeDescriptor::Any,
+                    }),
+                    TypeDescriptor::String => {
+                        expect(&index_ty, &TypeDescriptor::Number, index.span)?;
+                        Ok(TypeDescriptor::String)
+                    }
+                    TypeDescriptor::Any => Ok(TypeDescriptor::Any),
+                    _ => Err(TypeError::new("object", &ty, object.span)),
+                }
+            }
+            ExprKind::Call { function, args } => self.infer_call(function, args),
+            ExprKind::Unary { op, operand } => {
+                let ty = self.infer(operand)?;
+                match op {
+                    UnaryOp::Not => Ok(TypeDescriptor::Boolean),
+                    UnaryOp::Negate => {
+                        expect(&ty, &TypeDescriptor::Number, operand.span)?;
+                        Ok(TypeDescriptor::Number)
+                    }
+                }
+            }
+            ExprKind::Binary { op, left, right } => {
+                let left_ty = self.infer(left)?;
+                let right_ty = self.infer(right)?;
+                self.infer_binary(*op, (&left_ty, left.span), (&right_ty, right.span))
+            }
+            ExprKind::Conditional {
+                test,
+                consequent,
+                alternate,
+            } => {
+                self.infer(test)?;
+                Ok(self.infer(consequent)?.unify(&self.infer(alternate)?))
+            }
+            ExprKind::Array(items) => {
+                let mut item = None::<TypeDescriptor>;
+                for expr in items {
+                    let ty = self.infer(expr)?;
+                    item = Some(match item {
+                        Some(item) => item.unify(&ty),
+                        None => ty,
+                    });
+                }
+                Ok(TypeDescriptor::array(item.unwrap_or_default()))
+            }
+            ExprKind::Object(entries) => {
+                let mut fields = BTreeMap::new();
+                for (key, value) in entries {
+                    fields.insert(key.clone(), self.infer(value)?);
+                }
+                Ok(TypeDescriptor::Object(fields))
+            }
+        }
+    }
+
+    fn infer_binary(
+        &self,
+        op: BinaryOp,
+        (left, left_span): (&TypeDescriptor, Span),
+        (right, right_span): (&TypeDescriptor, Span),
+    ) -> Result<TypeDescriptor, TypeError> {
+        let number = TypeDescriptor::Number;
+        match op {
+            BinaryOp::And | BinaryOp::Or => Ok(if left == right {
+                left.clone()
+            } else {
+                TypeDescriptor::Any
+            }),
+            BinaryOp::Coalesce => Ok(left.unify(right)),
+            BinaryOp::Eq | BinaryOp::NotEq => {
+                // Strict equality between different primitives is always false
+                let comparable = !left.is_primitive()
+                    || !right.is_primitive()
+                    || left == right
+                    || *left == TypeDescriptor::Null
+                    || *right == TypeDescriptor::Null;
+                if comparable {
+                    Ok(TypeDescriptor::Boolean)
+                } else {
+                    Err(TypeError::new(left, right, right_span))
+                }
+            }
+            BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => {
+                if *left == TypeDescriptor::String {
+                    expect(right, &TypeDescriptor::String, right_span)?;
+                } else {
+                    expect(left, &number, left_span)?;
+                    expect(right, &number, right_span)?;
+                }
+                Ok(TypeDescriptor::Boolean)
+            }
+            BinaryOp::Add => match (left, right) {
+                (TypeDescriptor::String, _) | (_, TypeDescriptor::String) => {
+                    Ok(TypeDescriptor::String)
+                }
+                (TypeDescriptor::Any, _) | (_, TypeDescriptor::Any) => Ok(TypeDescriptor::Any),
+                _ => {
+                    expect(left, &number, left_span)?;
+                    expect(right, &number, right_span)?;
+                    Ok(number)
+                }
+            },
+            BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                expect(left, &number, left_span)?;
+                expect(right, &number, right_span)?;
+                Ok(number)
+            }
+        }
+    }
+
+    fn infer_call(&self, function: &str, args: &[Expr]) -> Result<TypeDescriptor, TypeError> {
+        let types = args
+            .iter()
+            .map(|arg| Ok((self.infer(arg)?, arg.span)))
+            .collect::<Result<Vec<_>, TypeError>>()?;
+        let string = TypeDescriptor::String;
+        let number = TypeDescriptor::Number;
+
+        let expect_all = |expected: &TypeDescriptor| {
+            types
+                .iter()
+                .try_for_each(|(ty, span)| expect(ty, expected, *span))
+        };
+        let expect_sized = |(ty, span): &(TypeDescriptor, Span)| match ty {
+            TypeDescriptor::Any | TypeDescriptor::String | TypeDescriptor::Array(_) => Ok(()),
+            _ => Err(TypeError::new("string | unknown[]", ty, *span)),
+        };
+
+        match function {
+            "length" => {
+                types.iter().try_for_each(expect_sized)?;
+                Ok(number)
+            }
+            "contains" => {
+                types.iter().take(1).try_for_each(expect_sized)?;
+                Ok(TypeDescriptor::Boolean)
+            }
+            "lower" | "upper" | "trim" => {
+                expect_all(&string)?;
+                Ok(string)
+            }
+            "startsWith" | "endsWith" => {
+                expect_all(&string)?;
+                Ok(TypeDescriptor::Boolean)
+            }
+            "abs" | "floor" | "ceil" | "round" | "min" | "max" => {
+                expect_all(&number)?;
+                Ok(number)
+            }
+            "keys" => {
+                for (ty, span) in &types {
+                    if !matches!(ty, TypeDescriptor::Any | TypeDescriptor::Object(_)) {
+                        return Err(TypeError::new("object", ty, *span));
+                    }
+                }
+                Ok(TypeDescriptor::array(string))
+            }
+            "exists" => Ok(TypeDescriptor::Boolean),
+            "toNumber" => Ok(number),
+            "toString" | "toJson" | "now" => Ok(string),
+            _ => Ok(TypeDescriptor::Any),
+        }
+    }
+}
+
+/// Require `found` to be assignable to `expected`
+fn expect(found: &TypeDescriptor, expected: &TypeDescriptor, span: Span) -> Result<(), TypeError> {
+    if found.is_assignable_to(expected) {
+        Ok(())
+    } else {
+        Err(TypeError::new(expected, found, span))
+    }
+}
+
+/// Object type built from the fields declared on DataIn or DataOut nodes
+fn data_type(workflow: &WorkflowDefinition, node_type: NodeType) -> Option<TypeDescriptor> {
+    let nodes: Vec<_> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == node_type && n.data.fields.is_some())
+        .collect();
+    (!nodes.is_empty())
+        .then(|| fields_type(nodes.iter().flat_map(|n| n.data.fields.iter().flatten())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{DataField, NodeData, Position, VariableType, WorkflowVariable};
+
+    fn node(id: &str, node_type: NodeType, data: NodeData) -> WorkflowNode {
+        WorkflowNode {
+            id: id.to_string(),
+            node_type,
+            data,
+            position: Position::default(),
+            parent_id: None,
+        }
+    }
+
+    fn environment() -> TypeEnvironment {
+        let mut workflow = WorkflowDefinition::new();
+        workflow.variables = vec![WorkflowVariable::new("count", VariableType::Number)];
+        workflow.add_node(node(
+            "data-in",
+            NodeType::DataIn,
+            NodeData {
+                fields: Some(vec![
+                    DataField::new("name", VariableType::String),
+                    DataField::new("tags", VariableType::Array),
+                ]),
+                ..Default::default()
+            },
+        ));
+        workflow.add_node(node(
+            "read",
+            NodeType::StateVariable,
+            NodeData {
+                component_name: Some("GetVariable".to_string()),
+                variable_name: Some("count".to_string()),
+                variable_scope: Some("workflow".to_string()),
+                ..Default::default()
+            },
+        ));
+        TypeEnvironment::new(&workflow)
+    }
+
+    fn infer(env: &TypeEnvironment, source: &str) -> Result<TypeDescriptor, TypeError> {
+        env.infer(&parse(source).unwrap())
+    }
+
+    #[test]
+    fn test_infer_paths() {
+        let env = environment();
+        assert_eq!(infer(&env, "input.name"), Ok(TypeDescriptor::String));
+        assert_eq!(infer(&env, "state.count + 1"), Ok(TypeDescriptor::Number));
+        assert_eq!(
+            infer(&env, "nodes.read.output.value"),
+            Ok(TypeDescriptor::Number)
+        );
+        assert_eq!(
+            infer(&env, "nodes.read.output.exists"),
+            Ok(TypeDescriptor::Boolean)
+        );
+        assert_eq!(
+            infer(&env, "length(input.tags) > 0"),
+            Ok(TypeDescriptor::Boolean)
+        );
+        assert_eq!(
+            infer(&env, "{ label: upper(input.name) }"),
+            Ok(TypeDescriptor::object([("label", TypeDescriptor::String)]))
+        );
+    }
+
+    #[test]
+    fn test_infer_reports_mismatches() {
+        let env = environment();
+        let err = infer(&env, "input.name * 2").unwrap_err();
+        assert_eq!(err.expected, "number");
+        assert_eq!(err.found, "string");
+        assert_eq!(err.span, Span::new(0, 10));
+
+        let err = infer(&env, "state.count === 'ten'").unwrap_err();
+        assert_eq!(
+            (err.expected.as_str(), err.found.as_str()),
+            ("number", "string")
+        );
+
+        assert!(infer(&env, "abs(input.name)").is_err());
+        assert!(infer(&env, "length(state.count)").is_err());
+    }
+
+    #[test]
+    fn test_template_type() {
+        let env = environment();
+        assert_eq!(env.template_type("{{state.count}}"), TypeDescriptor::Number);
+        assert_eq!(
+            env.template_type("Count: {{state.count}}"),
+            TypeDescriptor::String
+        );
+        assert_eq!(
+            env.template_type("{{nodes.other.output}}"),
+            TypeDescriptor::Any
+        );
+    }
+}
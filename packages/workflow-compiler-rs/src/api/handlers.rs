@@ -8,7 +8,7 @@ use tempfile::tempdir;
 use super::{ApiError, ApiErrorResponse, ApiResponse};
 use crate::{
     codegen::{self, CodeGenOptions, GeneratedCode},
-    schema::WorkflowDefinition,
+    schema::{ParallelMode, WorkflowDefinition},
     validation::{self, ValidationResult},
     verification::{self, VerificationResult},
 };
@@ -36,6 +36,10 @@ pub struct CompileRequest {
     pub workflow: WorkflowDefinition,
     #[serde(default)]
     pub options: CompileOptions,
+    /// Other definitions compiled in the same request, which child workflow
+    /// nodes may start
+    #[serde(default)]
+    pub sibling_workflows: Vec<WorkflowDefinition>,
 }
 
 /// Compile options
@@ -46,6 +50,7 @@ pub struct CompileOptions {
     pub default_timeout: Option<String>,
     pub include_comments: Option<bool>,
     pub strict_mode: Option<bool>,
+    pub parallel_mode: Option<ParallelMode>,
     pub verify: Option<bool>,
     pub output_dir: Option<String>,
 }
@@ -177,7 +182,7 @@ pub async fn generate_typescript(
     }
 
     // Generate code
-    let options = to_codegen_options(&request.options);
+    let options = to_codegen_options(&request);
     let code = codegen::generate(&request.workflow, &options).map_err(|e| {
         ApiErrorResponse::internal_error(ApiError::internal(format!(
             "Code generation failed: {}",
@@ -226,7 +231,7 @@ pub async fn compile_workflow(
     }
 
     // Generate code
-    let options = to_codegen_options(&request.options);
+    let options = to_codegen_options(&request);
     let code = match codegen::generate(&request.workflow, &options) {
         Ok(code) => code,
         Err(e) => {
@@ -280,7 +285,7 @@ pub async fn full_compile(
     }
 
     // Generate code
-    let options = to_codegen_options(&request.options);
+    let options = to_codegen_options(&request);
     let code = codegen::generate(&request.workflow, &options).map_err(|e| {
         ApiErrorResponse::internal_error(ApiError::internal(format!(
             "Code generation failed: {}",
@@ -317,7 +322,8 @@ pub async fn full_compile(
 }
 
 /// Convert API options to codegen options
-fn to_codegen_options(options: &CompileOptions) -> CodeGenOptions {
+fn to_codegen_options(request: &CompileRequest) -> CodeGenOptions {
+    let options = &request.options;
     CodeGenOptions {
         workflow_name: options.workflow_name.clone(),
         default_timeout: options
@@ -326,5 +332,7 @@ fn to_codegen_options(options: &CompileOptions) -> CodeGenOptions {
             .unwrap_or_else(|| "1m".to_string()),
         include_comments: options.include_comments.unwrap_or(true),
         strict_mode: options.strict_mode.unwrap_or(true),
+        parallel_mode: options.parallel_mode.unwrap_or_default(),
+        sibling_workflows: request.sibling_workflows.clone(),
     }
 }
@@ -3,9 +3,13 @@
 //! This module provides code generation patterns for specific workflow components
 //! like Start, Stop, and Log activities.
 
+use super::expression::{
+    compile_interpolated, compile_source, member_access, string_array, string_literal,
+};
 use crate::schema::{
-    GetVariableConfig, LogConfig, LogLevel, ServiceVariableConfig, SetVariableConfig, StartConfig,
-    StopConfig, VariableScope,
+    ApprovalConfig, Duration, GetVariableConfig, LogConfig, LogLevel, ServiceVariableConfig,
+    SetVariableConfig, SignalConfig, StartConfig, StopConfig, TimerConfig, TimerDelay,
+    UpdateConfig, VariableScope,
 };
 use serde::Serialize;
 
@@ -69,11 +73,12 @@ pub fn generate_start_code(config: &StartConfig, node_id: &str) -> ComponentPatt
 
 /// Generate code for Stop component
 pub fn generate_stop_code(config: &StopConfig, node_id: &str) -> ComponentPattern {
-    let result_mapping = config
-        .result_mapping
-        .as_ref()
-        .map(|expr| format!("const finalResult = {};", expr))
-        .unwrap_or_else(|| "const finalResult = input;".to_string());
+    let result_mapping = match (&config.result_mapping, &config.result_type) {
+        (Some(expr), Some(ty)) => format!("const finalResult: {} = {};", ty, compile_source(expr)),
+        (Some(expr), None) => format!("const finalResult = {};", compile_source(expr)),
+        (None, Some(ty)) => format!("const finalResult = input as unknown as {};", ty),
+        (None, None) => "const finalResult = input;".to_string(),
+    };
 
     let metadata_code = if config.include_metadata {
         r#"
@@ -158,13 +163,8 @@ pub fn generate_log_code(config: &LogConfig, node_id: &str) -> ComponentPattern
         String::new()
     };
 
-    // Build the log call
-    let message_expr = if config.message.contains("${") {
-        // Template expression - evaluate at runtime
-        format!("`{}`", config.message)
-    } else {
-        format!("'{}'", config.message.replace('\'', "\\'"))
-    };
+    // Build the log call; `${...}` parts are evaluated at runtime
+    let message_expr = compile_interpolated(&config.message);
 
     let log_call = match config.level {
         LogLevel::Debug => "console.debug",
@@ -257,12 +257,22 @@ export async function log_{sanitized_id}(
 }
 
 /// Sanitize node ID for use in variable names
-fn sanitize_id(id: &str) -> String {
+pub(crate) fn sanitize_id(id: &str) -> String {
     id.chars()
         .map(|c| if c.is_alphanumeric() { c } else { '_' })
         .collect()
 }
 
+/// Name of the constant holding a node's result
+pub(crate) fn result_var(node_id: &str) -> String {
+    format!("{}_result", sanitize_id(node_id))
+}
+
+/// Name of the constant holding the failure of a node on its error branch
+pub(crate) fn error_var(node_id: &str) -> String {
+    format!("{}_error", sanitize_id(node_id))
+}
+
 // =============================================================================
 // Variable Component Code Generation
 // =============================================================================
@@ -317,10 +327,10 @@ pub fn generate_get_variable_code(config: &GetVariableConfig, node_id: &str) ->
     let throw_code = if config.throw_if_missing {
         format!(
             r#"
-  if (result_{id}.value === undefined) {{
+  if ({result}.value === undefined) {{
     throw new Error('[VAR:GET:ERROR] Variable {var_name} not found in {scope} scope');
   }}"#,
-            id = sanitize_id(node_id),
+            result = result_var(node_id),
             var_name = var_name,
             scope = scope_str
         )
@@ -331,39 +341,39 @@ pub fn generate_get_variable_code(config: &GetVariableConfig, node_id: &str) ->
     let code = match scope {
         VariableScope::Workflow => format!(
             r#"// GetVariable: {node_id} - {var_name} (workflow scope)
-  const result_{id} = {{
+  const {result} = {{
     value: state.{var_name} ?? {default_code},
     exists: state.{var_name} !== undefined,
   }};{throw_code}
-  console.log('[VAR:GET:WORKFLOW]', {{ name: '{var_name}', exists: result_{id}.exists, workflowId: workflowInfo().workflowId }});"#,
+  console.log('[VAR:GET:WORKFLOW]', {{ name: '{var_name}', exists: {result}.exists, workflowId: workflowInfo().workflowId }});"#,
             node_id = node_id,
-            id = sanitize_id(node_id),
+            result = result_var(node_id),
             var_name = var_name,
             default_code = default_code,
             throw_code = throw_code
         ),
         VariableScope::Service => format!(
             r#"// GetVariable: {node_id} - {var_name} (service scope)
-  const result_{id} = await acts.getServiceVariable({{
+  const {result} = await acts.getServiceVariable({{
     name: '{var_name}',
     defaultValue: {default_code},
   }});{throw_code}
-  console.log('[VAR:GET:SERVICE]', {{ name: '{var_name}', exists: result_{id}.exists, workflowId: workflowInfo().workflowId }});"#,
+  console.log('[VAR:GET:SERVICE]', {{ name: '{var_name}', exists: {result}.exists, workflowId: workflowInfo().workflowId }});"#,
             node_id = node_id,
-            id = sanitize_id(node_id),
+            result = result_var(node_id),
             var_name = var_name,
             default_code = default_code,
             throw_code = throw_code
         ),
         VariableScope::Project => format!(
             r#"// GetVariable: {node_id} - {var_name} (project scope)
-  const result_{id} = await acts.getProjectVariable({{
+  const {result} = await acts.getProjectVariable({{
     name: '{var_name}',
     defaultValue: {default_code},
   }});{throw_code}
-  console.log('[VAR:GET:PROJECT]', {{ name: '{var_name}', exists: result_{id}.exists, workflowId: workflowInfo().workflowId }});"#,
+  console.log('[VAR:GET:PROJECT]', {{ name: '{var_name}', exists: {result}.exists, workflowId: workflowInfo().workflowId }});"#,
             node_id = node_id,
-            id = sanitize_id(node_id),
+            result = result_var(node_id),
             var_name = var_name,
             default_code = default_code,
             throw_code = throw_code
@@ -382,11 +392,10 @@ pub fn generate_get_variable_code(config: &GetVariableConfig, node_id: &str) ->
 pub fn generate_set_variable_code(config: &SetVariableConfig, node_id: &str) -> ComponentPattern {
     let var_name = sanitize_id(&config.name);
     let scope = &config.scope;
-    let scope_str = scope.to_string();
 
     // Determine value source
     let value_expr = if let Some(expr) = &config.value_expression {
-        expr.clone()
+        compile_source(expr)
     } else if let Some(val) = &config.static_value {
         serde_json::to_string(val).unwrap_or_else(|_| "undefined".to_string())
     } else {
@@ -470,6 +479,274 @@ pub fn generate_set_variable_code(config: &SetVariableConfig, node_id: &str) ->
     }
 }
 
+/// Expression for the state the handler of a signal records
+pub fn signal_state(name: &str) -> String {
+    format!("signals{}", member_access(name))
+}
+
+/// Expression for the state the handler of an update records
+pub fn update_state(name: &str) -> String {
+    format!("updates{}", member_access(name))
+}
+
+/// Statement waiting for a handler to record `state`, and a description of
+/// the wait for the node comment
+fn handler_wait(state: &str, wait: bool, timeout: Option<&str>) -> (String, String) {
+    match (wait, timeout) {
+        (true, Some(timeout)) => {
+            let timeout = Duration::normalize(timeout);
+            (
+                format!(
+                    "\n  await condition(() => {}.received, {});",
+                    state,
+                    string_literal(&timeout)
+                ),
+                format!("wait up to {}", timeout),
+            )
+        }
+        (true, None) => (
+            format!("\n  await condition(() => {}.received);", state),
+            "wait".to_string(),
+        ),
+        (false, _) => (String::new(), "read".to_string()),
+    }
+}
+
+/// Generate code for Signal component.
+///
+/// The handler registered at the start of the workflow records the payload;
+/// the node snapshots it, first waiting for the signal if configured to.
+pub fn generate_signal_code(config: &SignalConfig, node_id: &str) -> ComponentPattern {
+    let state = signal_state(&config.name);
+    let (wait, description) = handler_wait(&state, config.wait, config.timeout.as_deref());
+
+    let code = format!(
+        r#"// Signal: {node_id} - {name} ({description}){wait}
+  const {result} = {{ ...{state} }};
+  console.log('[SIGNAL]', {{ name: {name_literal}, received: {result}.received, workflowId: workflowInfo().workflowId }});"#,
+        node_id = node_id,
+        name = config.name,
+        name_literal = string_literal(&config.name),
+        description = description,
+        wait = wait,
+        result = result_var(node_id),
+        state = state,
+    );
+
+    let mut required_imports = vec!["workflowInfo".to_string()];
+    if config.wait {
+        required_imports.push("condition".to_string());
+    }
+
+    ComponentPattern {
+        component_type: "signal".to_string(),
+        code,
+        is_activity: false,
+        required_imports,
+    }
+}
+
+/// Generate code for Update component.
+///
+/// Like a signal, the handler registered at the start of the workflow records
+/// the argument; the node snapshots it, first waiting for the update if
+/// configured to.
+pub fn generate_update_code(config: &UpdateConfig, node_id: &str) -> ComponentPattern {
+    let state = update_state(&config.name);
+    let (wait, description) = handler_wait(&state, config.wait, config.timeout.as_deref());
+
+    let code = format!(
+        r#"// Update: {node_id} - {name} ({description}){wait}
+  const {result} = {{ ...{state} }};
+  console.log('[UPDATE]', {{ name: {name_literal}, received: {result}.received, workflowId: workflowInfo().workflowId }});"#,
+        node_id = node_id,
+        name = config.name,
+        name_literal = string_literal(&config.name),
+        description = description,
+        wait = wait,
+        result = result_var(node_id),
+        state = state,
+    );
+
+    let mut required_imports = vec!["workflowInfo".to_string()];
+    if config.wait {
+        required_imports.push("condition".to_string());
+    }
+
+    ComponentPattern {
+        component_type: "update".to_string(),
+        code,
+        is_activity: false,
+        required_imports,
+    }
+}
+
+/// Generate code for Timer component.
+///
+/// The wait is a durable `sleep`; when the timer races a signal it is a
+/// `condition` with the delay as its timeout instead, which returns early
+/// once the signal's handler has recorded a delivery.
+pub fn generate_timer_code(config: &TimerConfig, node_id: &str) -> ComponentPattern {
+    let (delay, description) = match &config.delay {
+        TimerDelay::Duration(value) => {
+            let duration = Duration::normalize(value);
+            (string_literal(&duration), format!("wait {}", duration))
+        }
+        TimerDelay::DurationExpression(expr) => (
+            compile_source(expr),
+            "wait for a computed duration".to_string(),
+        ),
+        TimerDelay::Timestamp(value) => (
+            format!(
+                "Math.max(0, new Date({}).getTime() - Date.now())",
+                string_literal(value)
+            ),
+            "wait until a fixed time".to_string(),
+        ),
+        TimerDelay::UntilExpression(expr) => (
+            format!(
+                "Math.max(0, new Date({}).getTime() - Date.now())",
+                compile_source(expr)
+            ),
+            "wait until a computed time".to_string(),
+        ),
+    };
+
+    let (wait, signalled, description) = match &config.signal {
+        Some(signal) => {
+            let signalled = format!("{}_signalled", sanitize_id(node_id));
+            (
+                format!(
+                    "\n  const {} = await condition(() => {}.received, {});",
+                    signalled,
+                    signal_state(signal),
+                    delay
+                ),
+                signalled,
+                format!(
+                    "{}, or until signal {}",
+                    description,
+                    string_literal(signal)
+                ),
+            )
+        }
+        None => (
+            format!("\n  await sleep({});", delay),
+            "false".to_string(),
+            description,
+        ),
+    };
+
+    let code = format!(
+        "// Timer: {node_id} - {description}{wait}\n  \
+         const {result} = {{ signalled: {signalled}, completedAt: new Date().toISOString() }};",
+        node_id = node_id,
+        description = description,
+        wait = wait,
+        result = result_var(node_id),
+        signalled = signalled,
+    );
+
+    let required_imports = match config.signal {
+        Some(_) => vec!["condition".to_string()],
+        None => vec!["sleep".to_string()],
+    };
+
+    ComponentPattern {
+        component_type: "timer".to_string(),
+        code,
+        is_activity: false,
+        required_imports,
+    }
+}
+
+/// Generate code for Approval component.
+///
+/// The approval is listed by the `pendingApprovals` query while the node
+/// waits for its signal, and a decision only counts when it comes from one
+/// of the approvers. Without a decision before the timeout the outcome is
+/// `timed_out`.
+pub fn generate_approval_code(
+    config: &ApprovalConfig,
+    node_id: &str,
+    label: &str,
+) -> ComponentPattern {
+    let id = sanitize_id(node_id);
+    let id_literal = string_literal(node_id);
+    let state = signal_state(&config.signal);
+    let approvers = string_array(&config.approvers);
+    let timeout = config.timeout.as_deref().map(Duration::normalize);
+
+    let (expires, wait, description) = match &timeout {
+        Some(timeout) => (
+            Duration::parse(timeout)
+                .map(|d| {
+                    format!(
+                        "\n    expiresAt: new Date(Date.now() + {}).toISOString(),",
+                        d.as_millis()
+                    )
+                })
+                .unwrap_or_default(),
+            format!(
+                "await condition({}_decided, {});",
+                id,
+                string_literal(timeout)
+            ),
+            format!("wait up to {}", timeout),
+        ),
+        None => (
+            String::new(),
+            format!("await condition({}_decided);", id),
+            "wait".to_string(),
+        ),
+    };
+
+    let code = format!(
+        r#"// Approval: {node_id} - {approver_count} via signal {signal} ({description})
+  pendingApprovals.set({id_literal}, {{
+    id: {id_literal},
+    label: {label},
+    signal: {signal},
+    approvers: {approvers},
+    requestedAt: new Date().toISOString(),{expires}
+  }});
+  const {id}_decided = () =>
+    {state}.received && {approvers}.includes({state}.payload.approver);
+  {wait}
+  pendingApprovals.delete({id_literal});
+  const {id}_decision = {id}_decided() ? {state}.payload : undefined;
+  const {result}: ApprovalResult = {{
+    outcome: {id}_decision === undefined ? 'timed_out' : {id}_decision.approved ? 'approved' : 'rejected',
+    approver: {id}_decision?.approver,
+    comment: {id}_decision?.comment,
+    decidedAt: new Date().toISOString(),
+  }};
+  console.log('[APPROVAL]', {{ id: {id_literal}, outcome: {result}.outcome, workflowId: workflowInfo().workflowId }});"#,
+        node_id = node_id,
+        approver_count = match config.approvers.len() {
+            1 => "1 approver".to_string(),
+            n => format!("{} approvers", n),
+        },
+        signal = string_literal(&config.signal),
+        description = description,
+        id_literal = id_literal,
+        label = string_literal(label),
+        approvers = approvers,
+        expires = expires,
+        id = id,
+        state = state,
+        wait = wait,
+        result = result_var(node_id),
+    );
+
+    ComponentPattern {
+        component_type: "approval".to_string(),
+        code,
+        is_activity: false,
+        required_imports: vec!["condition".to_string(), "workflowInfo".to_string()],
+    }
+}
+
 /// Generate activity implementations for variable operations
 pub fn generate_variable_activities() -> String {
     r#"/**
@@ -586,11 +863,26 @@ mod tests {
         assert!(pattern.code.contains("success: true"));
     }
 
+    #[test]
+    fn test_generate_stop_code_with_result_type() {
+        let config = StopConfig {
+            result_mapping: Some("{ total: 1 }".to_string()),
+            result_type: Some("OrderWorkflowResult".to_string()),
+            ..Default::default()
+        };
+        let pattern = generate_stop_code(&config, "stop-typed");
+
+        assert!(pattern
+            .code
+            .contains("const finalResult: OrderWorkflowResult = { total: 1 };"));
+    }
+
     #[test]
     fn test_generate_stop_code_with_metadata() {
         let config = StopConfig {
             result_mapping: Some("{ processed: true }".to_string()),
             include_metadata: true,
+            ..Default::default()
         };
         let pattern = generate_stop_code(&config, "stop-meta");
 
@@ -745,6 +1037,62 @@ mod tests {
         assert!(pattern.code.contains("state.counter = state.counter + 1"));
     }
 
+    #[test]
+    fn test_generate_signal_code_waits_with_timeout() {
+        let config = SignalConfig {
+            name: "order-approved".to_string(),
+            wait: true,
+            timeout: Some("24h".to_string()),
+            ..Default::default()
+        };
+        let pattern = generate_signal_code(&config, "wait-approval");
+
+        assert!(pattern
+            .code
+            .contains("// Signal: wait-approval - order-approved (wait up to 24h)"));
+        assert!(pattern
+            .code
+            .contains("await condition(() => signals['order-approved'].received, '24h');"));
+        assert!(pattern
+            .code
+            .contains("const wait_approval_result = { ...signals['order-approved'] };"));
+        assert!(pattern.required_imports.contains(&"condition".to_string()));
+    }
+
+    #[test]
+    fn test_generate_signal_code_without_wait() {
+        let config = SignalConfig {
+            name: "cancel".to_string(),
+            ..Default::default()
+        };
+        let pattern = generate_signal_code(&config, "check-cancel");
+
+        assert!(!pattern.code.contains("condition("));
+        assert!(pattern
+            .code
+            .contains("const check_cancel_result = { ...signals.cancel };"));
+    }
+
+    #[test]
+    fn test_generate_update_code() {
+        let config = UpdateConfig {
+            name: "set-discount".to_string(),
+            wait: true,
+            ..Default::default()
+        };
+        let pattern = generate_update_code(&config, "discount");
+
+        assert!(pattern
+            .code
+            .contains("// Update: discount - set-discount (wait)"));
+        assert!(pattern
+            .code
+            .contains("await condition(() => updates['set-discount'].received);"));
+        assert!(pattern
+            .code
+            .contains("const discount_result = { ...updates['set-discount'] };"));
+    }
+
     #[test]
     fn test_generate_set_variable_with_merge() {
         let config = SetVariableConfig {
@@ -0,0 +1,258 @@
+//! TypeScript emission for workflow expressions.
+//!
+//! Expressions are compiled from their syntax tree, so generated code only
+//! ever contains literals, property paths, operators and the TypeScript
+//! equivalents of whitelisted functions.
+
+use crate::expression::{
+    parse, parse_interpolated, Expr, ExprKind, TemplateSegment, UnaryOp,
+};
+
+use super::components::result_var;
+
+/// Emitted in place of an expression that failed to parse
+const INVALID_EXPRESSION: &str = "undefined /* invalid expression */";
+
+/// Compile an expression to TypeScript
+pub fn compile_expression(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Null => "null".to_string(),
+        ExprKind::Bool(value) => value.to_string(),
+        ExprKind::Number(value) => value.clone(),
+        ExprKind::String(value) => string_literal(value),
+        ExprKind::Identifier(name) if name == "workflow" => "workflowInfo()".to_string(),
+        ExprKind::Identifier(name) => name.clone(),
+        ExprKind::Member { object, property } => match expr.as_node_output() {
+            Some(node_id) => result_var(node_id),
+            None => format!("{}{}", compile_expression(object), member_access(property)),
+        },
+        ExprKind::Index { object, index } => match expr.as_node_output() {
+            Some(node_id) => result_var(node_id),
+            None => format!("{}[{}]", compile_expression(object), compile_expression(index)),
+        },
+        ExprKind::Call { function, args } => compile_call(function, args),
+        ExprKind::Unary { op, operand } => {
+            let op = match op {
+                UnaryOp::Not => "!",
+                UnaryOp::Negate => "-",
+            };
+            format!("{}{}", op, compile_operand(operand))
+        }
+        ExprKind::Binary { op, left, right } => format!(
+            "{} {} {}",
+            compile_operand(left),
+            op.as_str(),
+            compile_operand(right)
+        ),
+        ExprKind::Conditional {
+            test,
+            consequent,
+            alternate,
+        } => format!(
+            "{} ? {} : {}",
+            compile_operand(test),
+            compile_operand(consequent),
+            compile_operand(alternate)
+        ),
+        ExprKind::Array(items) => format!("[{}]", compile_list(items)),
+        ExprKind::Object(entries) if entries.is_empty() => "{}".to_string(),
+        ExprKind::Object(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", property_key(key), compile_expression(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
+/// Parse and compile an expression, emitting `undefined` if it is invalid.
+///
+/// Validation reports invalid expressions, so this fallback only shows up
+/// when code is generated from a workflow that was never validated.
+pub fn compile_source(source: &str) -> String {
+    parse(source)
+        .map(|expr| compile_expression(&expr))
+        .unwrap_or_else(|_| INVALID_EXPRESSION.to_string())
+}
+
+/// Compile a `${...}` text template to a TypeScript string expression
+pub fn compile_interpolated(source: &str) -> String {
+    let Ok(segments) = parse_interpolated(source) else {
+        return INVALID_EXPRESSION.to_string();
+    };
+    if segments
+        .iter()
+        .all(|segment| matches!(segment, TemplateSegment::Text(_)))
+    {
+        return string_literal(source);
+    }
+
+    let mut literal = String::from("`");
+    for segment in segments {
+        match segment {
+            TemplateSegment::Text(text) => literal.push_str(
+                &text
+                    .replace('\\', "\\\\")
+                    .replace('`', "\\`")
+                    .replace("${", "\\${"),
+            ),
+            TemplateSegment::Expr(expr) => {
+                literal.push_str(&format!("${{{}}}", compile_expression(&expr)))
+            }
+        }
+    }
+    literal.push('`');
+    literal
+}
+
+/// Compile a sub-expression, parenthesizing anything with operators
+fn compile_operand(expr: &Expr) -> String {
+    let code = compile_expression(expr);
+    match expr.kind {
+        ExprKind::Binary { .. } | ExprKind::Conditional { .. } | ExprKind::Unary { .. } => {
+            format!("({})", code)
+        }
+        _ => code,
+    }
+}
+
+fn compile_list(items: &[Expr]) -> String {
+    items
+        .iter()
+        .map(compile_expression)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// TypeScript for a call to a whitelisted function
+fn compile_call(function: &str, args: &[Expr]) -> String {
+    let arg = |i: usize| args.get(i).map(compile_operand).unwrap_or_default();
+    match function {
+        "length" => format!("{}.length", arg(0)),
+        "lower" => format!("String({}).toLowerCase()", compile_list(args)),
+        "upper" => format!("String({}).toUpperCase()", compile_list(args)),
+        "trim" => format!("String({}).trim()", compile_list(args)),
+        "contains" => format!("{}.includes({})", arg(0), arg(1)),
+        "startsWith" => format!("String({}).startsWith({})", arg(0), arg(1)),
+        "endsWith" => format!("String({}).endsWith({})", arg(0), arg(1)),
+        "abs" | "floor" | "ceil" | "round" | "min" | "max" => {
+            format!("Math.{}({})", function, compile_list(args))
+        }
+        "toString" => format!("String({})", compile_list(args)),
+        "toNumber" => format!("Number({})", compile_list(args)),
+        "toJson" => format!("JSON.stringify({})", compile_list(args)),
+        "keys" => format!("Object.keys({})", compile_list(args)),
+        "exists" => format!("({} != null)", arg(0)),
+        "now" => "new Date().toISOString()".to_string(),
+        // The parser only produces whitelisted calls
+        _ => INVALID_EXPRESSION.to_string(),
+    }
+}
+
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Quote a string as a single-quoted JavaScript string literal
+pub(crate) fn string_literal(s: &str) -> String {
+    let mut literal = String::from("'");
+    for c in s.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '\'' => literal.push_str("\\'"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                literal.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('\'');
+    literal
+}
+
+/// Text for a single-line `/** ... */` doc comment: line breaks collapse to
+/// spaces and `*/` is escaped so the text cannot end the comment
+pub(crate) fn doc_comment_text(s: &str) -> String {
+    s.split(['\n', '\r', '\u{2028}', '\u{2029}'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("*/", "*\\/")
+}
+
+/// TypeScript array literal of strings
+pub(crate) fn string_array(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|v| string_literal(v)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Render an object literal key, quoting it when needed
+pub(crate) fn property_key(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        string_literal(name)
+    }
+}
+
+/// Render a property access for a path segment
+pub(crate) fn member_access(segment: &str) -> String {
+    if is_identifier(segment) {
+        format!(".{}", segment)
+    } else if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()) {
+        format!("[{}]", segment)
+    } else {
+        format!("[{}]", string_literal(segment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_condition() {
+        assert_eq!(
+            compile_source("state.iteration < state.maxIterations && !input.stop"),
+            "(state.iteration < state.maxIterations) && (!input.stop)"
+        );
+        assert_eq!(compile_source("input.status == 'approved'"), "input.status === 'approved'");
+    }
+
+    #[test]
+    fn test_compile_node_output_and_functions() {
+        assert_eq!(
+            compile_source("{ id: nodes['fetch-order'].output.id, at: now(), n: length(input.items) }"),
+            "{ id: fetch_order_result.id, at: new Date().toISOString(), n: input.items.length }"
+        );
+        assert_eq!(compile_source("workflow.workflowId"), "workflowInfo().workflowId");
+    }
+
+    #[test]
+    fn test_compile_invalid_source() {
+        assert_eq!(compile_source("process.exit(1)"), INVALID_EXPRESSION);
+    }
+
+    #[test]
+    fn test_compile_interpolated() {
+        assert_eq!(
+            compile_interpolated("Order `${input.id}` done"),
+            "`Order \\`${input.id}\\` done`"
+        );
+        assert_eq!(compile_interpolated("It's done"), "'It\\'s done'");
+    }
+
+    #[test]
+    fn test_member_access() {
+        assert_eq!(member_access("id"), ".id");
+        assert_eq!(member_access("0"), "[0]");
+        assert_eq!(member_access("order-id"), "['order-id']");
+    }
+}
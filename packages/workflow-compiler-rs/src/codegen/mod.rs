@@ -3,16 +3,19 @@
 //! This module generates type-safe TypeScript code from validated workflow definitions.
 
 pub mod components;
+mod expression;
 mod typescript;
 
 pub use components::*;
+pub(crate) use expression::is_identifier;
+pub use expression::{compile_expression, compile_interpolated, compile_source};
 pub use typescript::*;
 
 use handlebars::Handlebars;
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 
-use crate::schema::WorkflowDefinition;
+use crate::schema::{ParallelMode, WorkflowDefinition};
 
 /// Generated code output
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +43,11 @@ pub struct CodeGenOptions {
     pub workflow_name: Option<String>,
     /// Default timeout for activities
     pub default_timeout: String,
+    /// How parallel branches are awaited unless a Parallel node overrides it
+    pub parallel_mode: ParallelMode,
+    /// Other definitions compiled in the same request, which Child Workflow
+    /// nodes can start with their generated types
+    pub sibling_workflows: Vec<WorkflowDefinition>,
 }
 
 impl CodeGenOptions {
@@ -49,6 +57,8 @@ impl CodeGenOptions {
             strict_mode: true,
             workflow_name: None,
             default_timeout: "1m".to_string(),
+            parallel_mode: ParallelMode::All,
+            sibling_workflows: Vec::new(),
         }
     }
 }
@@ -1,15 +1,32 @@
 //! TypeScript code generator.
 
 use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
 
+use crate::analysis::{
+    parse_template, template_references, ControlFlowGraph, DataReference, TemplatePart,
+    TypeEnvironment,
+};
 use crate::schema::{
-    GetVariableConfig, LogConfig, LogLevel, NodeType, ServiceVariableConfig, SetVariableConfig,
-    StartConfig, StopConfig, VariableScope, WorkflowDefinition, WorkflowNode,
+    AgentConfig, ChildMode, ChildWorkflowConfig, DataField, Duration, GetVariableConfig, LogConfig,
+    LogLevel, LoopConfig, LoopMode, MapConfig, NodeType, ParallelMode, QuerySource, RetryConfig,
+    RetryPolicy, RetryStrategy, ServiceVariableConfig, SetVariableConfig, SignalConfig,
+    StartConfig, StopConfig, TypeDescriptor, VariableScope, WorkflowDefinition, WorkflowEdge,
+    WorkflowNode, WorkflowQuery, CANCELLED_MESSAGE, DEFAULT_ITEM_VARIABLE, ERROR_HANDLE,
+    PENDING_APPROVALS_QUERY, UPDATE_ARGUMENT,
 };
+use crate::expression::parse;
 
 use super::components::{
-    generate_get_variable_code, generate_log_code, generate_service_variable_code,
-    generate_set_variable_code, generate_start_code, generate_stop_code,
+    error_var, generate_approval_code, generate_get_variable_code, generate_log_code,
+    generate_service_variable_code, generate_set_variable_code, generate_signal_code,
+    generate_start_code, generate_stop_code, generate_timer_code, generate_update_code, result_var,
+    sanitize_id, signal_state, update_state,
+};
+use crate::validation::{validate_expressions, ValidationError};
+
+use super::expression::{
+    compile_source, doc_comment_text, member_access, property_key, string_array, string_literal,
 };
 use super::{get_handlebars, CodeGenOptions, GeneratedCode};
 
@@ -24,16 +41,51 @@ pub struct WorkflowTemplateData {
     pub default_timeout: String,
     pub has_signals: bool,
     pub has_queries: bool,
+    pub has_updates: bool,
     pub is_long_running: bool,
-    pub has_retry_policy: bool,
     pub has_variables: bool,
     pub input_type: String,
     pub output_type: String,
+    pub has_input_schema: bool,
+    pub has_output_schema: bool,
+    pub input_fields: Vec<FieldInfo>,
+    pub output_fields: Vec<FieldInfo>,
     pub activities: Vec<ActivityInfo>,
+    /// Activity proxies: `acts` with the workflow's retry policy, then one per
+    /// distinct node override
+    pub proxies: Vec<ActivityProxy>,
+    /// Additional `@temporalio/workflow` imports used by node code
+    pub workflow_imports: Vec<String>,
+    /// Whether Retry nodes match error types, which needs the `failureType` helper
+    pub uses_failure_type: bool,
+    /// Whether nodes route failures to an error branch, which needs `settle`
+    pub catches_errors: bool,
+    /// Whether activities register compensations, undone when the workflow fails
+    pub has_compensations: bool,
+    /// Whether Map nodes run their bodies through `mapInBatches`
+    pub has_maps: bool,
+    /// Whether Approval nodes wait for decisions, listed by `pendingApprovals`
+    pub has_approvals: bool,
+    /// Whether Agent nodes run `runAgent`, which needs the `callModel` activity
+    pub has_agents: bool,
+    /// Whether compensations run concurrently instead of newest first
+    pub parallel_compensation: bool,
+    /// Whether a cleanup branch is declared, so cancellation ends the workflow
+    /// with a cancelled output
+    pub handles_cancellation: bool,
+    /// The trigger's cleanup branch, run when the workflow is cancelled
+    pub cancel_cleanup: Option<String>,
+    /// Error reported in the cancelled output
+    pub cancelled_message: String,
+    pub child_workflow_imports: Vec<ChildWorkflowImport>,
     pub signals: Vec<SignalInfo>,
+    pub queries: Vec<QueryInfo>,
+    pub updates: Vec<UpdateInfo>,
+    pub tracks_phase: bool,
+    pub tracks_progress: bool,
+    pub total_steps: usize,
     pub variables: Vec<VariableInfo>,
     pub code_blocks: Vec<String>,
-    pub retry_policy: Option<RetryPolicyInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -44,12 +96,75 @@ pub struct ActivityInfo {
     pub timeout: Option<String>,
 }
 
+/// A sibling workflow whose type a Child Workflow node uses
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChildWorkflowImport {
+    pub function_name: String,
+    pub module: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SignalInfo {
     pub name: String,
+    /// Name of the `defineSignal` constant
+    pub const_name: String,
+    /// Property key of the signal in the `signals` state object
+    pub key: String,
+    /// Expression for the signal's state
+    pub state: String,
     pub param_types: String,
+    /// Payload interface fields, empty when the payload is untyped
+    pub payload_fields: Vec<FieldInfo>,
+    /// State variables assigned the payload on delivery
+    pub state_variables: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateInfo {
+    pub name: String,
+    /// Name of the `defineUpdate` constant
+    pub const_name: String,
+    /// Property key of the update in the `updates` state object
+    pub key: String,
+    /// Expression for the update's state
+    pub state: String,
+    pub arg_type: String,
+    /// Argument interface fields, empty when the argument is untyped
+    pub arg_fields: Vec<FieldInfo>,
+    pub return_type: String,
+    /// Result interface fields, empty when the result is untyped
+    pub return_fields: Vec<FieldInfo>,
+    /// Compiled expression returned by the handler
+    pub return_value: Option<String>,
+    /// Compiled validator condition
+    pub validator: Option<String>,
+    /// Validator parameter name, prefixed with `_` when the condition ignores it
+    pub validator_param: String,
+    /// Error message when the validator rejects an update
+    pub rejection: String,
+    /// State variable assigned the argument on delivery
+    pub state_variable: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryInfo {
+    pub name: String,
+    /// Name of the `defineQuery` constant
+    pub const_name: String,
+    pub return_type: String,
+    /// Expression returned by the query handler
+    pub handler: String,
+    pub description: Option<String>,
 }
 
+/// Handler of the progress query
+const PROGRESS_QUERY_HANDLER: &str = "({
+    totalSteps: progress.totalSteps,
+    completedSteps: progress.completed.size,
+    currentStep: progress.currentStep,
+    percentComplete: Math.round((progress.completed.size / progress.totalSteps) * 100),
+  })";
+
 #[derive(Debug, Serialize)]
 pub struct VariableInfo {
     pub name: String,
@@ -58,12 +173,71 @@ pub struct VariableInfo {
     pub default_value: Option<String>,
 }
 
+/// A field of a generated interface: the workflow input or result, a signal
+/// payload, or an update argument or result
+#[derive(Debug, Serialize)]
+pub struct FieldInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub required: bool,
+    pub description: Option<String>,
+}
+
+impl From<&DataField> for FieldInfo {
+    fn from(field: &DataField) -> Self {
+        Self {
+            name: field.name.clone(),
+            field_type: field.field_type.to_typescript_type().to_string(),
+            required: field.required,
+            description: field.description.as_deref().map(doc_comment_text),
+        }
+    }
+}
+
+/// An activity proxy and the retry policy of the activities it runs
+#[derive(Debug, Serialize)]
+pub struct ActivityProxy {
+    pub name: String,
+    pub retry_policy: Option<RetryPolicyInfo>,
+}
+
+/// Temporal retry options of a retry policy; unset options keep Temporal's
+/// defaults
 #[derive(Debug, Serialize)]
 pub struct RetryPolicyInfo {
-    pub max_attempts: u32,
-    pub initial_interval: String,
-    pub max_interval: String,
-    pub backoff_coefficient: f64,
+    /// Strategy summary emitted as a comment
+    pub description: String,
+    pub maximum_attempts: Option<u32>,
+    pub initial_interval: Option<String>,
+    pub maximum_interval: Option<String>,
+    pub backoff_coefficient: Option<f64>,
+    /// TypeScript array of the error types that are not retried
+    pub non_retryable_error_types: Option<String>,
+}
+
+impl From<&RetryPolicy> for RetryPolicyInfo {
+    fn from(policy: &RetryPolicy) -> Self {
+        let description = match policy.strategy {
+            RetryStrategy::None => "no retries".to_string(),
+            RetryStrategy::KeepTrying => "retrying until the activity succeeds".to_string(),
+            RetryStrategy::FailAfterX => format!(
+                "failing after {} attempts",
+                policy.max_attempts.unwrap_or_default()
+            ),
+            RetryStrategy::ExponentialBackoff => "retrying with exponential backoff".to_string(),
+        };
+        Self {
+            description,
+            maximum_attempts: policy.maximum_attempts(),
+            initial_interval: policy.initial_interval().map(Duration::normalize),
+            maximum_interval: policy.maximum_interval().map(Duration::normalize),
+            backoff_coefficient: policy.backoff_coefficient(),
+            non_retryable_error_types: Some(policy.non_retryable_errors())
+                .filter(|types| !types.is_empty())
+                .map(string_array),
+        }
+    }
 }
 
 /// TypeScript code generator
@@ -78,6 +252,17 @@ impl<'a> TypeScriptGenerator<'a> {
     }
 
     pub fn generate(&self) -> anyhow::Result<GeneratedCode> {
+        // Expressions are compiled from their syntax tree; refuse to emit
+        // code for any that fail to parse or read names out of scope
+        let mut errors = Vec::new();
+        validate_expressions(self.workflow, &mut errors, &mut Vec::new());
+        if let Some(error) = errors
+            .iter()
+            .find(|e| matches!(e, ValidationError::InvalidExpression { .. }))
+        {
+            anyhow::bail!("{}", error);
+        }
+
         let hbs = get_handlebars();
 
         // Prepare template data
@@ -107,15 +292,452 @@ impl<'a> TypeScriptGenerator<'a> {
         })
     }
 
-    fn prepare_template_data(&self) -> WorkflowTemplateData {
-        let workflow_name = self
-            .options
+    fn workflow_name(&self) -> String {
+        self.options
             .workflow_name
             .clone()
             .or(self.workflow.name.clone())
-            .unwrap_or_else(|| "workflow".to_string());
+            .unwrap_or_else(|| "workflow".to_string())
+    }
 
-        let function_name = to_camel_case(&workflow_name) + "Workflow";
+    fn function_name(&self) -> String {
+        to_camel_case(&self.workflow_name()) + "Workflow"
+    }
+
+    /// Sibling definition with the given id, compiled in the same request
+    fn sibling(&self, id: &str) -> Option<&WorkflowDefinition> {
+        self.options
+            .sibling_workflows
+            .iter()
+            .find(|w| w.id.as_deref() == Some(id))
+    }
+
+    /// Imports from `@temporalio/workflow` needed by node code
+    fn workflow_imports(&self) -> Vec<String> {
+        let mut imports = BTreeSet::new();
+        if self
+            .workflow
+            .nodes
+            .iter()
+            .any(|n| n.retry_config().is_some())
+        {
+            imports.insert("isCancellation");
+        }
+        if self.catches_errors() || self.handles_cancellation() {
+            imports.insert("isCancellation");
+        }
+        // Only imported where it is used, for compensations and cleanup
+        if self.has_compensations() || self.handles_cancellation() {
+            imports.insert("CancellationScope");
+        }
+        if self.uses_failure_type() || self.catches_errors() {
+            imports.extend(["ApplicationFailure", "TemporalFailure"]);
+        }
+        // Maps with more items than maxItems fail with an application failure
+        if self
+            .workflow
+            .nodes
+            .iter()
+            .filter_map(|n| n.map_config())
+            .any(|config| config.max_items.is_some())
+        {
+            imports.insert("ApplicationFailure");
+        }
+        // Agents that use up their turns fail with an application failure
+        if self.has_agents() {
+            imports.insert("ApplicationFailure");
+        }
+        for config in self.workflow.nodes.iter().filter_map(|n| n.child_workflow_config()) {
+            imports.insert(config.mode.function());
+            if config.parent_close_policy.is_some() {
+                imports.insert("ParentClosePolicy");
+            }
+        }
+        imports.into_iter().map(str::to_string).collect()
+    }
+
+    /// Whether a Retry node lists non-retryable error types
+    fn uses_failure_type(&self) -> bool {
+        self.workflow
+            .nodes
+            .iter()
+            .filter_map(|n| n.retry_config())
+            .any(|config| !config.non_retryable_errors.is_empty())
+    }
+
+    /// Whether the workflow has Agent nodes that generate code
+    fn has_agents(&self) -> bool {
+        self.workflow
+            .nodes
+            .iter()
+            .any(|n| n.agent_config().is_some())
+    }
+
+    /// Whether a node with generated code routes its failures to an error edge
+    fn catches_errors(&self) -> bool {
+        self.workflow.nodes.iter().any(|n| {
+            n.handles_errors()
+                && self
+                    .workflow
+                    .get_outgoing_edges(&n.id)
+                    .iter()
+                    .any(|e| e.is_error())
+                && self.generate_node_code(n).is_some()
+        })
+    }
+
+    /// Whether the workflow waits on signals, loops or runs through phases
+    fn is_long_running(&self) -> bool {
+        self.workflow.nodes.iter().any(|n| {
+            matches!(
+                n.node_type,
+                NodeType::Signal
+                    | NodeType::Loop
+                    | NodeType::Phase
+                    | NodeType::Timer
+                    | NodeType::Approval
+            )
+        })
+    }
+
+    /// Whether an activity declares a compensation
+    fn has_compensations(&self) -> bool {
+        self.workflow
+            .nodes
+            .iter()
+            .any(|n| n.node_type == NodeType::Activity && n.data.compensation_activity.is_some())
+    }
+
+    /// Whether the trigger or a phase declares a cleanup branch
+    fn handles_cancellation(&self) -> bool {
+        self.workflow.edges.iter().any(|e| e.is_cancel())
+    }
+
+    /// Whether a mapping reads `nodes.<id>.error` for the given node
+    fn reads_error(&self, node_id: &str) -> bool {
+        self.workflow
+            .nodes
+            .iter()
+            .flat_map(|n| n.data.input_mapping.iter().flat_map(|m| m.values()))
+            .flat_map(|value| template_references(value))
+            .any(|r| matches!(r, DataReference::NodeError { node_id: id, .. } if id == node_id))
+    }
+
+    /// Sibling workflows started by Child Workflow nodes, imported for their types
+    fn child_workflow_imports(&self) -> Vec<ChildWorkflowImport> {
+        let imports: BTreeSet<_> = self
+            .workflow
+            .nodes
+            .iter()
+            .filter_map(|n| n.child_workflow_config())
+            .filter_map(|config| {
+                let sibling = self.sibling(&config.workflow_type)?;
+                Some(ChildWorkflowImport {
+                    function_name: sibling_function_name(sibling),
+                    module: format!("./{}", config.workflow_type),
+                })
+            })
+            .collect();
+        imports.into_iter().collect()
+    }
+
+    /// Fields declared by all nodes of the given DataIn/DataOut type, in node order
+    fn data_fields(&self, node_type: NodeType) -> Vec<FieldInfo> {
+        let mut seen = HashSet::new();
+        self.workflow
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == node_type)
+            .flat_map(|n| n.data.fields.iter().flatten())
+            .filter(|field| seen.insert(field.name.as_str()))
+            .map(FieldInfo::from)
+            .collect()
+    }
+
+    /// Signals declared by Signal nodes, one entry per signal name
+    fn signals(&self) -> Vec<SignalInfo> {
+        let mut signals: Vec<SignalInfo> = Vec::new();
+        // Approvals receive their decision through a typed signal
+        let approval_signals = self
+            .workflow
+            .nodes
+            .iter()
+            .filter_map(|n| Some(n.approval_config()?.signal_config()));
+        // Signals raced by timers have a handler too, typed by their Signal
+        // node if there is one
+        let timer_signals = self.workflow.nodes.iter().filter_map(|n| {
+            n.timer_config()?.signal.map(|name| SignalConfig {
+                name,
+                ..Default::default()
+            })
+        });
+        for config in self
+            .workflow
+            .nodes
+            .iter()
+            .filter_map(|n| n.signal_config())
+            .chain(timer_signals)
+            .chain(approval_signals)
+        {
+            let index = match signals.iter().position(|s| s.name == config.name) {
+                Some(index) => index,
+                None => {
+                    let const_name = to_camel_case(&config.name) + "Signal";
+                    signals.push(SignalInfo {
+                        key: property_key(&config.name),
+                        state: signal_state(&config.name),
+                        param_types: "unknown".to_string(),
+                        payload_fields: Vec::new(),
+                        state_variables: Vec::new(),
+                        name: config.name.clone(),
+                        const_name,
+                    });
+                    signals.len() - 1
+                }
+            };
+
+            let signal = &mut signals[index];
+            if signal.payload_fields.is_empty() && !config.payload.is_empty() {
+                signal.param_types = to_pascal_case(&signal.const_name) + "Payload";
+                signal.payload_fields = config
+                    .payload
+                    .iter()
+                    .map(FieldInfo::from)
+                    .collect();
+            }
+            if let Some(variable) = config.state_variable {
+                if !signal.state_variables.contains(&variable) {
+                    signal.state_variables.push(variable);
+                }
+            }
+        }
+        signals
+    }
+
+    /// Updates declared by Update nodes
+    fn updates(&self) -> Vec<UpdateInfo> {
+        self.workflow
+            .nodes
+            .iter()
+            .filter_map(|n| n.update_config())
+            .map(|config| {
+                let const_name = to_camel_case(&config.name) + "Update";
+                let type_prefix = to_pascal_case(&const_name);
+                let arg_type = if config.argument.is_empty() {
+                    "unknown".to_string()
+                } else {
+                    format!("{}Args", type_prefix)
+                };
+                let return_type = match (config.returns.is_empty(), &config.return_value) {
+                    (false, _) => format!("{}Result", type_prefix),
+                    (true, Some(_)) => "unknown".to_string(),
+                    (true, None) => "void".to_string(),
+                };
+                let reads_argument = config.validator.as_deref().is_some_and(|source| {
+                    parse(source).is_ok_and(|expr| {
+                        let mut found = false;
+                        expr.walk(&mut |e| found |= e.is_identifier(UPDATE_ARGUMENT));
+                        found
+                    })
+                });
+                let validator_param = if reads_argument {
+                    UPDATE_ARGUMENT.to_string()
+                } else {
+                    format!("_{}", UPDATE_ARGUMENT)
+                };
+
+                UpdateInfo {
+                    key: property_key(&config.name),
+                    state: update_state(&config.name),
+                    arg_fields: config.argument.iter().map(FieldInfo::from).collect(),
+                    return_fields: config.returns.iter().map(FieldInfo::from).collect(),
+                    return_value: config.return_value.as_deref().map(compile_source),
+                    validator: config.validator.as_deref().map(compile_source),
+                    rejection: string_literal(&format!(
+                        "Update '{}' rejected: {}",
+                        config.name,
+                        config.validator.as_deref().unwrap_or_default()
+                    )),
+                    validator_param,
+                    state_variable: config.state_variable.clone(),
+                    name: config.name,
+                    const_name,
+                    arg_type,
+                    return_type,
+                }
+            })
+            .collect()
+    }
+
+    fn metadata_queries(&self) -> &[WorkflowQuery] {
+        self.workflow
+            .metadata
+            .as_ref()
+            .map_or(&[], |m| m.queries.as_slice())
+    }
+
+    /// Whether the current phase is tracked, for a query or the phase logs
+    fn tracks_phase(&self) -> bool {
+        self.metadata_queries()
+            .iter()
+            .any(|q| q.source == QuerySource::CurrentPhase)
+            || self
+                .workflow
+                .nodes
+                .iter()
+                .any(|n| n.node_type == NodeType::Phase)
+    }
+
+    /// Whether step progress is tracked for a query
+    fn tracks_progress(&self) -> bool {
+        self.metadata_queries()
+            .iter()
+            .any(|q| q.source == QuerySource::Progress)
+    }
+
+    /// Number of steps reported by the progress query
+    fn total_steps(&self) -> usize {
+        if !self.tracks_progress() {
+            return 0;
+        }
+        self.workflow
+            .nodes
+            .iter()
+            .filter(|n| self.generate_node_code(n).is_some())
+            .count()
+    }
+
+    /// Whether the workflow has Approval nodes that generate code
+    fn has_approvals(&self) -> bool {
+        self.workflow
+            .nodes
+            .iter()
+            .any(|n| n.approval_config().is_some())
+    }
+
+    /// Queries declared in the workflow metadata, and the pending approvals
+    /// query when the workflow has approvals
+    fn queries(&self) -> Vec<QueryInfo> {
+        let pending_approvals = self.has_approvals().then(|| QueryInfo {
+            name: PENDING_APPROVALS_QUERY.to_string(),
+            const_name: to_camel_case(PENDING_APPROVALS_QUERY) + "Query",
+            return_type: "PendingApproval[]".to_string(),
+            handler: "[...pendingApprovals.values()]".to_string(),
+            description: Some("Approvals waiting for a decision".to_string()),
+        });
+        self.metadata_queries()
+            .iter()
+            .map(|query| {
+                let (return_type, handler) = match &query.source {
+                    QuerySource::Variables { variables } => self.variables_query(variables),
+                    QuerySource::CurrentPhase => {
+                        ("string | undefined".to_string(), "currentPhase".to_string())
+                    }
+                    QuerySource::Progress => (
+                        "WorkflowProgress".to_string(),
+                        PROGRESS_QUERY_HANDLER.to_string(),
+                    ),
+                };
+                QueryInfo {
+                    const_name: to_camel_case(&query.name) + "Query",
+                    name: query.name.clone(),
+                    return_type,
+                    handler,
+                    description: query.description.as_deref().map(doc_comment_text),
+                }
+            })
+            .chain(pending_approvals)
+            .collect()
+    }
+
+    /// Return type and handler of a query exposing the named variables
+    /// (all of them when `names` is empty)
+    fn variables_query(&self, names: &[String]) -> (String, String) {
+        let selected: Vec<_> = self
+            .workflow
+            .variables
+            .iter()
+            .filter(|v| names.is_empty() || names.contains(&v.name))
+            .collect();
+        let fields: Vec<String> = selected
+            .iter()
+            .map(|v| format!("{}: {}", property_key(&v.name), v.to_typescript_type()))
+            .collect();
+        let values: Vec<String> = selected
+            .iter()
+            .map(|v| format!("{}: state{}", property_key(&v.name), member_access(&v.name)))
+            .collect();
+        (
+            format!("{{ {} }}", fields.join("; ")),
+            format!("({{ {} }})", values.join(", ")),
+        )
+    }
+
+    /// Name of the result interface, when DataOut nodes declare one
+    fn result_type_name(&self) -> Option<String> {
+        let declared = self
+            .workflow
+            .nodes
+            .iter()
+            .any(|n| n.node_type == NodeType::DataOut && n.data.fields.is_some());
+        declared.then(|| format!("{}Result", self.function_name()))
+    }
+
+    /// Retry policy of activities whose node does not override it
+    fn workflow_retry_policy(&self) -> Option<&'a RetryPolicy> {
+        self.workflow
+            .metadata
+            .as_ref()
+            .and_then(|m| m.retry_policy.as_ref())
+    }
+
+    /// Distinct node retry policies that differ from the workflow's, in node order
+    fn retry_overrides(&self) -> Vec<&'a RetryPolicy> {
+        let default = self.workflow_retry_policy();
+        let mut policies: Vec<&RetryPolicy> = Vec::new();
+        for policy in self
+            .workflow
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == NodeType::Activity)
+            .filter_map(|n| n.data.retry_policy.as_ref())
+        {
+            if Some(policy) != default && !policies.contains(&policy) {
+                policies.push(policy);
+            }
+        }
+        policies
+    }
+
+    fn activity_proxies(&self) -> Vec<ActivityProxy> {
+        let default = ActivityProxy {
+            name: "acts".to_string(),
+            retry_policy: self.workflow_retry_policy().map(RetryPolicyInfo::from),
+        };
+        let overrides = self
+            .retry_overrides()
+            .into_iter()
+            .enumerate()
+            .map(|(i, policy)| ActivityProxy {
+                name: format!("acts_{}", i + 1),
+                retry_policy: Some(policy.into()),
+            });
+        std::iter::once(default).chain(overrides).collect()
+    }
+
+    /// Proxy that runs a node's activity with its effective retry policy
+    fn activity_proxy(&self, node: &WorkflowNode) -> String {
+        node.data
+            .retry_policy
+            .as_ref()
+            .and_then(|policy| self.retry_overrides().iter().position(|p| *p == policy))
+            .map(|i| format!("acts_{}", i + 1))
+            .unwrap_or_else(|| "acts".to_string())
+    }
+
+    fn prepare_template_data(&self) -> WorkflowTemplateData {
+        let workflow_name = self.workflow_name();
+        let function_name = self.function_name();
 
         // Extract activities from nodes
         let activities: Vec<ActivityInfo> = self
@@ -124,26 +746,16 @@ impl<'a> TypeScriptGenerator<'a> {
             .iter()
             .filter(|n| n.node_type == NodeType::Activity)
             .map(|n| ActivityInfo {
-                name: n.data.activity_name.clone().unwrap_or_else(|| n.data.label.clone()),
+                name: activity_method(n),
                 component_id: n.data.component_id.clone(),
                 component_name: n.data.component_name.clone(),
                 timeout: n.data.timeout.clone(),
             })
             .collect();
 
-        // Extract signals from nodes
-        let signals: Vec<SignalInfo> = self
-            .workflow
-            .nodes
-            .iter()
-            .filter(|n| n.node_type == NodeType::Signal)
-            .filter_map(|n| {
-                n.data.signal_name.clone().map(|name| SignalInfo {
-                    name,
-                    param_types: "unknown".to_string(), // Default to unknown
-                })
-            })
-            .collect();
+        let signals = self.signals();
+        let queries = self.queries();
+        let updates = self.updates();
 
         // Extract variables
         let variables: Vec<VariableInfo> = self
@@ -157,13 +769,12 @@ impl<'a> TypeScriptGenerator<'a> {
             })
             .collect();
 
-        // Check for long-running indicators
-        let is_long_running = self.workflow.nodes.iter().any(|n| {
-            matches!(
-                n.node_type,
-                NodeType::Signal | NodeType::Loop | NodeType::Phase
-            )
-        });
+        // Typed input/result from DataIn/DataOut field schemas
+        let input_fields = self.data_fields(NodeType::DataIn);
+        let output_fields = self.data_fields(NodeType::DataOut);
+        let output_type = self
+            .result_type_name()
+            .unwrap_or_else(|| "unknown".to_string());
 
         // Generate code blocks
         let code_blocks = self.generate_code_blocks();
@@ -173,65 +784,1075 @@ impl<'a> TypeScriptGenerator<'a> {
             generated_at: chrono::Utc::now().to_rfc3339(),
             workflow_id: self.workflow.id.clone().unwrap_or_default(),
             workflow_name: workflow_name.clone(),
+            input_type: format!("{}Input", function_name),
             function_name,
-            default_timeout: self.options.default_timeout.clone(),
+            default_timeout: Duration::normalize(&self.options.default_timeout),
             has_signals: !signals.is_empty(),
-            has_queries: false, // TODO: Add query support
-            is_long_running,
-            has_retry_policy: self.workflow.metadata.as_ref().and_then(|m| m.retry_policy.as_ref()).is_some(),
+            has_queries: !queries.is_empty(),
+            has_updates: !updates.is_empty(),
+            is_long_running: self.is_long_running(),
             has_variables: !variables.is_empty(),
-            input_type: "Record<string, unknown>".to_string(),
-            output_type: "unknown".to_string(),
+            output_type,
+            has_input_schema: !input_fields.is_empty(),
+            has_output_schema: self.result_type_name().is_some(),
+            input_fields,
+            output_fields,
             activities,
+            proxies: self.activity_proxies(),
+            workflow_imports: self.workflow_imports(),
+            uses_failure_type: self.uses_failure_type(),
+            catches_errors: self.catches_errors(),
+            has_compensations: self.has_compensations(),
+            has_maps: self
+                .workflow
+                .nodes
+                .iter()
+                .any(|n| n.node_type == NodeType::Map),
+            has_approvals: self.has_approvals(),
+            has_agents: self.has_agents(),
+            handles_cancellation: self.handles_cancellation(),
+            cancel_cleanup: self
+                .workflow
+                .nodes
+                .iter()
+                .find(|n| n.node_type == NodeType::Trigger)
+                .and_then(|trigger| self.generate_cleanup_code(trigger))
+                .map(|block| {
+                    // Indented to sit inside the `catch` of the workflow body
+                    let mut code = String::new();
+                    push_blocks(&mut code, &[block], 2);
+                    code.trim_start().to_string()
+                }),
+            cancelled_message: CANCELLED_MESSAGE.to_string(),
+            parallel_compensation: self
+                .workflow
+                .metadata
+                .as_ref()
+                .and_then(|m| m.parallel_compensation)
+                .unwrap_or(false),
+            child_workflow_imports: self.child_workflow_imports(),
             signals,
+            queries,
+            updates,
+            tracks_phase: self.tracks_phase(),
+            tracks_progress: self.tracks_progress(),
+            total_steps: self.total_steps(),
             variables,
             code_blocks,
-            retry_policy: None,
         }
     }
 
     fn generate_code_blocks(&self) -> Vec<String> {
         let mut blocks = Vec::new();
 
-        // Sort nodes topologically (simple approach for now)
-        let ordered_nodes = self.topological_sort();
+        // Walk the graph from the trigger so statements follow the edges
+        let cfg = ControlFlowGraph::new(self.workflow);
+        if let Some(entry) = cfg.entry() {
+            let mut emitted = HashSet::new();
+            self.emit_region(&cfg, entry, None, &mut emitted, &mut blocks);
+        }
+
+        if blocks.is_empty() {
+            blocks.push("return input;".to_string());
+        }
+
+        blocks
+    }
+
+    /// Emit code for the nodes from `start` up to (but excluding) `stop`.
+    ///
+    /// When a node has several outgoing edges, each branch is emitted in turn
+    /// and the walk resumes at the node where the branches meet again.
+    fn emit_region(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        start: &'a str,
+        stop: Option<&'a str>,
+        emitted: &mut HashSet<&'a str>,
+        blocks: &mut Vec<String>,
+    ) {
+        let mut current = Some(start);
+
+        while let Some(id) = current {
+            if Some(id) == stop || emitted.contains(id) {
+                break;
+            }
+            // A join is emitted once, after the last of its predecessors
+            let ready = cfg
+                .predecessors(id)
+                .iter()
+                .all(|e| emitted.contains(e.source.as_str()));
+            if !ready {
+                break;
+            }
+            emitted.insert(id);
+
+            let Some(node) = cfg.node(id) else {
+                break;
+            };
+
+            if matches!(node.node_type, NodeType::Conditional | NodeType::Condition) {
+                let merge = cfg.immediate_post_dominator(id);
+                blocks.push(self.generate_branch_code(cfg, node, merge, emitted));
+                current = merge;
+                continue;
+            }
+
+            if node.node_type == NodeType::Loop {
+                let (body, exit) = cfg.loop_edges(id);
+                let after = exit.first().map(|e| e.target.as_str());
+                blocks.push(self.generate_loop_code(cfg, node, &body, after, emitted));
+                for edge in exit.iter().skip(1) {
+                    self.emit_region(cfg, &edge.target, None, emitted, blocks);
+                }
+                current = after;
+                continue;
+            }
+
+            if node.node_type == NodeType::Phase && self.workflow.has_members(id) {
+                let exit = self.phase_exit(cfg, node);
+                let mut region = Vec::new();
+                for edge in cfg.successors(id) {
+                    self.emit_region(cfg, &edge.target, exit, emitted, &mut region);
+                }
+                blocks.push(self.generate_phase_code(cfg, node, exit, &region));
+                current = exit;
+                continue;
+            }
+
+            if node.node_type == NodeType::Map {
+                let (body, exit) = cfg.region_edges(id);
+                let after = exit.first().map(|e| e.target.as_str());
+                let mut region = Vec::new();
+                for edge in &body {
+                    self.emit_region(cfg, &edge.target, after, emitted, &mut region);
+                }
+                match node.map_config() {
+                    Some(config) => {
+                        let block = self.generate_map_code(cfg, node, &config, &region);
+                        blocks.push(self.track_progress(node, block));
+                    }
+                    None => blocks.extend(region),
+                }
+                current = after;
+                continue;
+            }
+
+            if node.node_type == NodeType::Retry {
+                let (body, exit) = cfg.region_edges(id);
+                let after = exit.first().map(|e| e.target.as_str());
+                let mut region = Vec::new();
+                for edge in &body {
+                    self.emit_region(cfg, &edge.target, after, emitted, &mut region);
+                }
+                match node.retry_config() {
+                    Some(config) if !region.is_empty() => {
+                        blocks.push(self.generate_retry_code(cfg, node, &config, &region))
+                    }
+                    _ => blocks.extend(region),
+                }
+                current = after;
+                continue;
+            }
+
+            // An approval's outcome selects its branch, as a switch does
+            if node.node_type == NodeType::Approval
+                && cfg.successors(id).iter().any(|e| e.source_handle.is_some())
+            {
+                if let Some(block) = self.generate_node_code(node) {
+                    blocks.push(self.track_progress(node, block));
+                    let merge = cfg.immediate_post_dominator(id);
+                    blocks.push(self.generate_branch_code(cfg, node, merge, emitted));
+                    current = merge;
+                    continue;
+                }
+            }
+
+            let error_edge = cfg.successors(id).iter().find(|e| e.is_error());
+            if let (Some(error_edge), true) = (error_edge, node.handles_errors()) {
+                if let Some(block) = self.generate_node_code(node) {
+                    let merge = cfg.immediate_post_dominator(id);
+                    let on_error = self.emit_nested(cfg, &error_edge.target, merge, emitted);
+                    let mut on_success = Vec::new();
+                    for edge in cfg.successors(id).iter().filter(|e| !e.is_error()) {
+                        self.emit_region(cfg, &edge.target, merge, emitted, &mut on_success);
+                    }
+                    let block = self.track_progress(node, block);
+                    blocks.push(self.generate_error_handling(node, &block, &on_success, &on_error));
+                    current = merge;
+                    continue;
+                }
+            }
 
-        for node in ordered_nodes {
             if let Some(block) = self.generate_node_code(node) {
-                blocks.push(block);
+                blocks.push(self.track_progress(node, block));
+            }
+
+            current = match cfg.successors(id) {
+                [] => None,
+                [edge] if node.node_type != NodeType::Parallel => Some(edge.target.as_str()),
+                edges => {
+                    // Independent branches that re-join run concurrently
+                    let merge = cfg.immediate_post_dominator(id);
+                    let branches: Vec<(Vec<String>, Vec<String>)> = edges
+                        .iter()
+                        .zip(cfg.branch_results(id))
+                        .map(|(edge, results)| {
+                            let results = results.iter().map(|n| result_var(&n.id)).collect();
+                            (self.emit_nested(cfg, &edge.target, merge, emitted), results)
+                        })
+                        .filter(|(branch, _)| !branch.is_empty())
+                        .collect();
+                    if merge.is_some() && branches.len() > 1 {
+                        blocks.push(self.generate_parallel_code(node, &branches));
+                    } else {
+                        blocks.extend(branches.into_iter().flat_map(|(branch, _)| branch));
+                    }
+                    merge
+                }
+            };
+        }
+    }
+
+    /// Emit a region into its own list of blocks
+    fn emit_nested(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        start: &'a str,
+        stop: Option<&'a str>,
+        emitted: &mut HashSet<&'a str>,
+    ) -> Vec<String> {
+        let mut blocks = Vec::new();
+        self.emit_region(cfg, start, stop, emitted, &mut blocks);
+        blocks
+    }
+
+    /// Generate `if/else` or `switch` code for a conditional node.
+    ///
+    /// Branches are selected by the `sourceHandle` of each outgoing edge:
+    /// `true`/`false` handles produce an `if/else`, any other handle names
+    /// produce a `switch` on the condition value. Each branch body runs up to
+    /// the node where the branches re-join. Approvals switch on their outcome.
+    fn generate_branch_code(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        node: &'a WorkflowNode,
+        merge: Option<&'a str>,
+        emitted: &mut HashSet<&'a str>,
+    ) -> String {
+        let condition = if node.node_type == NodeType::Approval {
+            format!("{}.outcome", result_var(&node.id))
+        } else {
+            node.data
+                .condition
+                .as_deref()
+                .map(compile_source)
+                .unwrap_or_else(|| "true".to_string())
+        };
+        let edges = cfg.successors(&node.id);
+
+        let is_boolean = edges.iter().all(|e| {
+            matches!(e.source_handle.as_deref(), None | Some("true") | Some("false"))
+        });
+
+        let kind = match node.node_type {
+            NodeType::Approval => "Outcome",
+            _ => "Condition",
+        };
+        let mut code = format!("// {}: {}", kind, node.id);
+
+        if is_boolean {
+            let true_edge = edges
+                .iter()
+                .find(|e| e.source_handle.as_deref() != Some("false"));
+            let false_edge = edges
+                .iter()
+                .find(|e| e.source_handle.as_deref() == Some("false"));
+
+            let mut branch = |edge: Option<&&'a WorkflowEdge>| {
+                edge.map(|e| self.emit_nested(cfg, &e.target, merge, emitted))
+                    .unwrap_or_default()
+            };
+            let then_blocks = branch(true_edge);
+            let else_blocks = branch(false_edge);
+
+            if then_blocks.is_empty() && !else_blocks.is_empty() {
+                code.push_str(&format!("\n  if (!({})) {{", condition));
+                push_blocks(&mut code, &else_blocks, 1);
+                code.push_str("\n  }");
+            } else {
+                code.push_str(&format!("\n  if ({}) {{", condition));
+                push_blocks(&mut code, &then_blocks, 1);
+                if !else_blocks.is_empty() {
+                    code.push_str("\n  } else {");
+                    push_blocks(&mut code, &else_blocks, 1);
+                }
+                code.push_str("\n  }");
+            }
+        } else {
+            code.push_str(&format!("\n  switch ({}) {{", condition));
+            for edge in edges {
+                let handle = edge.source_handle.as_deref().unwrap_or("default");
+                let label = if handle == "default" {
+                    "default:".to_string()
+                } else {
+                    format!("case {}:", string_literal(handle))
+                };
+                let body = self.emit_nested(cfg, &edge.target, merge, emitted);
+                code.push_str(&format!("\n    {} {{", label));
+                push_blocks(&mut code, &body, 2);
+                code.push_str("\n      break;\n    }");
+            }
+            code.push_str("\n  }");
+        }
+
+        code
+    }
+
+    /// Generate a bounded `while`/`for` loop around the loop body.
+    ///
+    /// The body runs from the loop's body edges until it returns to the loop
+    /// node through a back-edge; `after` is the first node following the loop.
+    fn generate_loop_code(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        node: &'a WorkflowNode,
+        body: &[&'a WorkflowEdge],
+        after: Option<&'a str>,
+        emitted: &mut HashSet<&'a str>,
+    ) -> String {
+        let config = self.extract_loop_config(node);
+        let limit = config.iteration_limit();
+        let counter = format!("{}_iteration", sanitize_id(&node.id));
+
+        let mut body_blocks = Vec::new();
+        for edge in body {
+            self.emit_region(cfg, &edge.target, after, emitted, &mut body_blocks);
+        }
+
+        let mut code = format!(
+            "// Loop: {} ({}, max {} iterations)",
+            node.id,
+            config.mode.as_str(),
+            limit
+        );
+        match config.mode {
+            LoopMode::While => {
+                let condition = config
+                    .condition
+                    .as_deref()
+                    .map(compile_source)
+                    .unwrap_or_else(|| "true".to_string());
+                code.push_str(&format!(
+                    "\n  let {counter} = 0;\n  while (({condition}) && {counter} < {limit}) {{\n    {counter}++;",
+                    counter = counter,
+                    condition = condition,
+                    limit = limit
+                ));
+            }
+            LoopMode::Count => {
+                code.push_str(&format!(
+                    "\n  for (let {counter} = 0; {counter} < {limit}; {counter}++) {{",
+                    counter = counter,
+                    limit = limit
+                ));
+            }
+            LoopMode::ForEach => {
+                let collection = config
+                    .collection
+                    .as_deref()
+                    .map(compile_source)
+                    .unwrap_or_else(|| "[]".to_string());
+                let item = sanitize_id(config.item_variable.as_deref().unwrap_or("item"));
+                code.push_str(&format!(
+                    "\n  const {id}_items = ({collection}) as unknown[];\n  for (const {item} of {id}_items.slice(0, {limit})) {{",
+                    id = sanitize_id(&node.id),
+                    collection = collection,
+                    item = item,
+                    limit = limit
+                ));
+            }
+        }
+        push_blocks(&mut code, &body_blocks, 1);
+        code.push_str("\n  }");
+
+        code
+    }
+
+    /// The node that follows a phase, reached from the nodes inside it; `None`
+    /// when the workflow ends inside the phase
+    fn phase_exit(&self, cfg: &ControlFlowGraph<'a>, phase: &WorkflowNode) -> Option<&'a str> {
+        let inside = |id: &str| self.workflow.is_in_phase(id, &phase.id);
+        self.workflow
+            .nodes
+            .iter()
+            .filter(|n| inside(&n.id))
+            .flat_map(|n| cfg.successors(&n.id))
+            .map(|edge| edge.target.as_str())
+            .find(|target| !inside(target))
+    }
+
+    /// Generate a phase as a named async function that runs the nodes inside
+    /// it, followed by the call.
+    ///
+    /// `currentPhase` holds the phase label while it runs. Results of nodes
+    /// that run on every path through the phase are returned, so later nodes
+    /// can keep referencing them. A phase the workflow ends in returns the
+    /// workflow output instead. A phase with a cleanup branch runs it when it
+    /// is cancelled, then lets the cancellation end the workflow.
+    fn generate_phase_code(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        phase: &WorkflowNode,
+        exit: Option<&'a str>,
+        region: &[String],
+    ) -> String {
+        let function = format!("{}_phase", sanitize_id(&phase.id));
+        let label = string_literal(&phase.data.label);
+
+        let mut code = match exit {
+            Some(_) => format!("// Phase: {}\n  async function {}() {{", phase.id, function),
+            None => format!(
+                "// Phase: {}\n  async function {}(): Promise<{}Output> {{",
+                phase.id,
+                function,
+                self.function_name()
+            ),
+        };
+        code.push_str(&format!(
+            "\n    currentPhase = {};\n    \
+             console.log('[PHASE:START]', {{ phase: currentPhase }});",
+            label
+        ));
+
+        let mut body = region.to_vec();
+        let call = match exit {
+            None => format!("return await {}();", function),
+            Some(exit) => {
+                // Restore the enclosing phase once this one completes
+                let enclosing = phase
+                    .parent_id
+                    .as_deref()
+                    .and_then(|id| self.workflow.get_node(id))
+                    .map(|parent| string_literal(&parent.data.label))
+                    .unwrap_or_else(|| "undefined".to_string());
+                let results: Vec<String> = self
+                    .workflow
+                    .nodes
+                    .iter()
+                    .filter(|n| n.produces_result() && self.workflow.is_in_phase(&n.id, &phase.id))
+                    .filter(|n| cfg.dominates(&n.id, exit))
+                    .map(|n| result_var(&n.id))
+                    .collect();
+                body.push(format!(
+                    "console.log('[PHASE:END]', {{ phase: currentPhase }});\n  \
+                     currentPhase = {};",
+                    enclosing
+                ));
+                if results.is_empty() {
+                    format!("await {}();", function)
+                } else {
+                    let results = results.join(", ");
+                    body.push(format!("return {{ {} }};", results));
+                    format!("const {{ {} }} = await {}();", results, function)
+                }
+            }
+        };
+
+        match self.generate_cleanup_code(phase) {
+            Some(cleanup) => {
+                code.push_str("\n    try {");
+                push_blocks(&mut code, &body, 2);
+                code.push_str("\n    } catch (err) {\n      if (isCancellation(err)) {");
+                push_blocks(&mut code, &[cleanup], 3);
+                code.push_str("\n      }\n      throw err;\n    }");
+            }
+            None => push_blocks(&mut code, &body, 1),
+        }
+        code.push_str(&format!("\n  }}\n  {}", call));
+
+        code
+    }
+
+    /// Generate a Map node: its body becomes a function of the item, run for
+    /// every element of the collection through `mapInBatches`.
+    ///
+    /// The items are typed from the collection and the result array from the
+    /// last step of the body that runs for every item. A collection larger
+    /// than `maxItems` fails the map before any item runs.
+    fn generate_map_code(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        node: &WorkflowNode,
+        config: &MapConfig,
+        region: &[String],
+    ) -> String {
+        let id = sanitize_id(&node.id);
+        let items = format!("{}_items", id);
+        // Untyped items are `any`, so the body can read their fields
+        let item_type = match TypeEnvironment::new(self.workflow).map_item(&node.id) {
+            TypeDescriptor::Any => "any".to_string(),
+            item_type => item_type.to_string(),
+        };
+
+        let mut code = format!(
+            "// Map: {} (each item, {} at a time)\n  const {} = ({}) as {}[];",
+            node.id,
+            config.max_concurrency,
+            items,
+            compile_source(&config.collection),
+            item_type
+        );
+        if let Some(max_items) = config.max_items {
+            let message = format!("{} allows at most {} items, got ", node.id, max_items);
+            code.push_str(&format!(
+                "\n  if ({items}.length > {max}) {{\n    \
+                 throw ApplicationFailure.nonRetryable({message} + {items}.length, 'MapTooLarge');\
+                 \n  }}",
+                items = items,
+                max = max_items,
+                message = string_literal(&message)
+            ));
+        }
+        code.push_str(&format!(
+            "\n  const {} = await mapInBatches({}, {}, async ({}) => {{",
+            result_var(&node.id),
+            items,
+            config.max_concurrency,
+            sanitize_id(&config.item_variable)
+        ));
+        let mut body = region.to_vec();
+        if let Some(item_result) = cfg.item_result(&node.id) {
+            body.push(format!("return {};", result_var(&item_result.id)));
+        }
+        push_blocks(&mut code, &body, 1);
+        code.push_str("\n  });");
+
+        code
+    }
+
+    /// Generate a workflow-side retry loop around the region of a Retry node.
+    ///
+    /// A failed attempt sleeps for the backoff delay and runs the whole region
+    /// again, unless the workflow is being cancelled, the attempts are used up
+    /// or the error type is non-retryable. Results of nodes that run on every
+    /// path through the region are returned from the loop, so later nodes can
+    /// reference them.
+    fn generate_retry_code(
+        &self,
+        cfg: &ControlFlowGraph<'a>,
+        node: &WorkflowNode,
+        config: &RetryConfig,
+        region: &[String],
+    ) -> String {
+        let attempt = format!("{}_attempt", sanitize_id(&node.id));
+
+        let mut give_up = vec!["isCancellation(err)".to_string()];
+        if let Some(max) = config.max_attempts {
+            give_up.push(format!("{} >= {}", attempt, max));
+        }
+        if !config.non_retryable_errors.is_empty() {
+            give_up.push(format!(
+                "{}.includes(failureType(err) ?? '')",
+                string_array(&config.non_retryable_errors)
+            ));
+        }
+
+        let initial = config.initial_interval.as_millis();
+        let delay = if config.backoff_coefficient == 1.0 {
+            initial.min(config.max_interval.as_millis()).to_string()
+        } else {
+            format!(
+                "Math.min({} * {} ** ({} - 1), {})",
+                initial,
+                config.backoff_coefficient,
+                attempt,
+                config.max_interval.as_millis()
+            )
+        };
+
+        let limit = match config.max_attempts {
+            Some(1) => "1 attempt".to_string(),
+            Some(max) => format!("up to {} attempts", max),
+            None => "until the region succeeds".to_string(),
+        };
+        let results: Vec<String> = cfg
+            .retry_results(&node.id)
+            .iter()
+            .map(|n| result_var(&n.id))
+            .collect();
+        let mut body = region.to_vec();
+        if results.is_empty() {
+            body.push("break;".to_string());
+        } else {
+            body.push(format!("return {{ {} }};", results.join(", ")));
+        }
+
+        let mut attempts = format!(
+            "for (let {attempt} = 1; ; {attempt}++) {{\n    try {{",
+            attempt = attempt
+        );
+        push_blocks(&mut attempts, &body, 2);
+        attempts.push_str(&format!(
+            "\n    }} catch (err) {{\n      \
+             if ({give_up}) {{\n        throw err;\n      }}\n      \
+             console.warn('[RETRY_ATTEMPT_FAILED]', \
+             {{ nodeId: {node_id}, attempt: {attempt}, error: err }});\n      \
+             await sleep({delay});\n    }}\n  }}",
+            give_up = give_up.join(" || "),
+            node_id = string_literal(&node.id),
+            attempt = attempt,
+            delay = delay
+        ));
+
+        let mut code = format!("// Retry: {} ({})", node.id, limit);
+        if results.is_empty() {
+            push_blocks(&mut code, &[attempts], 0);
+        } else {
+            // The attempts run in a function that returns the region's results
+            code.push_str(&format!(
+                "\n  const {{ {} }} = await (async () => {{",
+                results.join(", ")
+            ));
+            push_blocks(&mut code, &[attempts], 1);
+            code.push_str("\n  })();");
+        }
+
+        code
+    }
+
+    /// Generate a node whose failures take its error edge.
+    ///
+    /// The node runs inside `settle`, which catches its failure (but not a
+    /// cancellation); the outcome then selects the error or success branch.
+    /// The result is declared ahead of the branches, so code after them can
+    /// read it, and the failure details are bound for mappings that read them.
+    fn generate_error_handling(
+        &self,
+        node: &WorkflowNode,
+        block: &str,
+        on_success: &[String],
+        on_error: &[String],
+    ) -> String {
+        let id = sanitize_id(&node.id);
+        let outcome = format!("{}_outcome", id);
+        let result = result_var(&node.id);
+
+        let mut code = format!(
+            "// {}: failures take the '{}' branch\n  const {} = await settle(async () => {{",
+            node.id, ERROR_HANDLE, outcome
+        );
+        push_blocks(
+            &mut code,
+            &[block.to_string(), format!("return {};", result)],
+            1,
+        );
+        code.push_str(&format!(
+            "\n  }});\n  let {}!: SettledValue<typeof {}>;\n  if ({}.failed) {{",
+            result, outcome, outcome
+        ));
+
+        let mut failure = vec![format!(
+            "console.warn('[NODE_FAILED]', {{ nodeId: {}, error: {}.error }});",
+            string_literal(&node.id),
+            outcome
+        )];
+        if self.reads_error(&node.id) {
+            failure.push(format!(
+                "const {} = {}.error;",
+                error_var(&node.id),
+                outcome
+            ));
+        }
+        failure.extend(on_error.iter().cloned());
+        push_blocks(&mut code, &failure, 1);
+
+        code.push_str("\n  } else {");
+        let mut success = vec![format!("{} = {}.value;", result, outcome)];
+        success.extend(on_success.iter().cloned());
+        push_blocks(&mut code, &success, 1);
+        code.push_str("\n  }");
+
+        code
+    }
+
+    /// Generate a `Promise.all`/`Promise.allSettled` block running each
+    /// branch in its own async closure.
+    ///
+    /// Each closure returns the results of its branch, which are bound once
+    /// the branches have joined; with `Promise.allSettled` a failed branch
+    /// leaves its results undefined.
+    fn generate_parallel_code(
+        &self,
+        node: &WorkflowNode,
+        branches: &[(Vec<String>, Vec<String>)],
+    ) -> String {
+        let mode = node
+            .data
+            .parallel_mode
+            .as_deref()
+            .and_then(ParallelMode::parse)
+            .unwrap_or(self.options.parallel_mode);
+        let id = sanitize_id(&node.id);
+
+        let mut code = format!(
+            "// Parallel: {} ({} branches, {})",
+            node.id,
+            branches.len(),
+            mode.combinator()
+        );
+        // Destructuring pattern of each branch's results, without the
+        // trailing branches that have none
+        let mut patterns: Vec<String> = branches
+            .iter()
+            .map(|(_, results)| {
+                if results.is_empty() {
+                    String::new()
+                } else {
+                    format!("{{ {} }}", results.join(", "))
+                }
+            })
+            .collect();
+        while patterns.last().is_some_and(String::is_empty) {
+            patterns.pop();
+        }
+
+        match mode {
+            ParallelMode::All if patterns.is_empty() => {
+                code.push_str(&format!("\n  await {}([", mode.combinator()))
             }
+            ParallelMode::All => code.push_str(&format!(
+                "\n  const [{}] = await {}([",
+                patterns.join(", "),
+                mode.combinator()
+            )),
+            ParallelMode::AllSettled => code.push_str(&format!(
+                "\n  const {}_settled = await {}([",
+                id,
+                mode.combinator()
+            )),
         }
+        for (branch, results) in branches {
+            code.push_str("\n    (async () => {");
+            let mut branch = branch.clone();
+            if !results.is_empty() {
+                branch.push(format!("return {{ {} }};", results.join(", ")));
+            }
+            push_blocks(&mut code, &branch, 2);
+            code.push_str("\n    })(),");
+        }
+        code.push_str("\n  ]);");
 
+        if mode == ParallelMode::AllSettled {
+            code.push_str(&format!(
+                "\n  for (const outcome of {id}_settled) {{\n    \
+                 if (outcome.status === 'rejected') {{\n      \
+                 console.warn('[PARALLEL_BRANCH_FAILED]', \
+                 {{ nodeId: {node_id}, reason: outcome.reason }});\n    }}\n  }}",
+                id = id,
+                node_id = string_literal(&node.id)
+            ));
+            if !patterns.is_empty() {
+                let outcomes: Vec<String> = patterns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pattern)| {
+                        if pattern.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{}_branch_{}", id, i + 1)
+                        }
+                    })
+                    .collect();
+                code.push_str(&format!(
+                    "\n  const [{}] = {}_settled;",
+                    outcomes.join(", "),
+                    id
+                ));
+                for ((outcome, pattern), (_, results)) in
+                    outcomes.iter().zip(&patterns).zip(branches)
+                {
+                    if pattern.is_empty() {
+                        continue;
+                    }
+                    let missing: Vec<String> = results
+                        .iter()
+                        .map(|r| format!("{}: undefined", r))
+                        .collect();
+                    code.push_str(&format!(
+                        "\n  const {pattern} =\n    \
+                         {outcome}.status === 'fulfilled' ? {outcome}.value : {{ {missing} }};",
+                        pattern = pattern,
+                        outcome = outcome,
+                        missing = missing.join(", ")
+                    ));
+                }
+            }
+        }
+
+        code
+    }
+
+    /// Generate the cleanup branch a node's `cancel` edge leads to, run in a
+    /// non-cancellable scope so its activities still execute.
+    ///
+    /// The branch is emitted from a graph of its own and stops at End nodes:
+    /// the workflow returns its cancelled output once cleanup is done.
+    fn generate_cleanup_code(&self, node: &WorkflowNode) -> Option<String> {
+        let edge = self
+            .workflow
+            .get_outgoing_edges(&node.id)
+            .into_iter()
+            .find(|e| e.is_cancel())?;
+        let cfg = ControlFlowGraph::rooted_at(self.workflow, &edge.target);
+        let mut emitted: HashSet<&'a str> = self
+            .workflow
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == NodeType::End)
+            .map(|n| n.id.as_str())
+            .collect();
+        let blocks = self.emit_nested(&cfg, &edge.target, None, &mut emitted);
         if blocks.is_empty() {
-            blocks.push("return input;".to_string());
+            return None;
         }
 
-        blocks
+        let mut code = format!(
+            "// Cleanup: {} (on cancel)\n  \
+             await CancellationScope.nonCancellable(async () => {{",
+            node.id
+        );
+        push_blocks(&mut code, &blocks, 1);
+        code.push_str("\n  });");
+        Some(code)
+    }
+
+    /// Generate an Agent node: a `runAgent` loop given the node's input as
+    /// the prompt.
+    ///
+    /// Each tool is bound to the proxy of the activity it names, so tool
+    /// calls keep that activity's retry policy; the result is typed by the
+    /// output schema.
+    fn generate_agent_code(&self, node: &WorkflowNode, config: &AgentConfig) -> String {
+        let mut fields = vec![
+            format!("id: {}", string_literal(&node.id)),
+            format!("model: {}", string_literal(&config.model)),
+        ];
+        if let Some(system) = &config.system_prompt {
+            fields.push(format!("system: {}", string_literal(system)));
+        }
+        let tools: Vec<String> = config
+            .tools
+            .iter()
+            .filter_map(|tool| {
+                let activity =
+                    self.workflow.nodes.iter().find(|n| {
+                        n.node_type == NodeType::Activity && activity_method(n) == *tool
+                    })?;
+                Some(format!(
+                    "\n        {}: {{ description: {}, run: (args) => {}.{}(args) }},",
+                    property_key(tool),
+                    string_literal(&activity.data.label),
+                    self.activity_proxy(activity),
+                    tool
+                ))
+            })
+            .collect();
+        if tools.is_empty() {
+            fields.push("tools: {}".to_string());
+        } else {
+            fields.push(format!("tools: {{{}\n      }}", tools.concat()));
+        }
+        fields.push(format!("maxTurns: {}", config.max_turns));
+        if let Some(schema) = config.output_json_schema() {
+            fields.push(format!("outputSchema: {}", schema));
+        }
+
+        let tool_count = match config.tools.len() {
+            1 => "1 tool".to_string(),
+            n => format!("{} tools", n),
+        };
+        let mut code = format!(
+            "// Agent: {} - {} (max {} turns)\n  \
+             const {} = await runAgent<{}>(\n    {{",
+            node.id,
+            tool_count,
+            config.max_turns,
+            result_var(&node.id),
+            config.output_type()
+        );
+        for field in fields {
+            code.push_str(&format!("\n      {},", field));
+        }
+        code.push_str(&format!(
+            "\n    }},\n    {}\n  );",
+            self.activity_args(node)
+        ));
+
+        code
+    }
+
+    /// Generate a Child Workflow node: start the child and bind its result,
+    /// or its handle in start mode
+    fn generate_child_workflow(&self, node: &WorkflowNode, config: &ChildWorkflowConfig) -> String {
+        let mut options = vec![format!("args: [{}]", self.activity_args(node))];
+        if let Some(workflow_id) = &config.workflow_id {
+            options.push(format!("workflowId: {}", self.compile_template(workflow_id)));
+        }
+        if let Some(task_queue) = &config.task_queue {
+            options.push(format!("taskQueue: {}", string_literal(task_queue)));
+        }
+        if let Some(policy) = config.parent_close_policy {
+            options.push(format!("parentClosePolicy: {}", policy.typescript()));
+        }
+        if let Some(timeout) = &config.execution_timeout {
+            options.push(format!(
+                "workflowExecutionTimeout: {}",
+                string_literal(&Duration::normalize(timeout))
+            ));
+        }
+        if let Some(timeout) = &config.run_timeout {
+            options.push(format!(
+                "workflowRunTimeout: {}",
+                string_literal(&Duration::normalize(timeout))
+            ));
+        }
+
+        // Siblings compiled in the same request are started by their generated
+        // function name and typed against it
+        let (workflow_type, type_argument) = match self.sibling(&config.workflow_type) {
+            Some(sibling) => {
+                let function_name = sibling_function_name(sibling);
+                (string_literal(&function_name), format!("<typeof {}>", function_name))
+            }
+            None => (string_literal(&config.workflow_type), String::new()),
+        };
+        let options: String = options.iter().map(|o| format!("\n    {},", o)).collect();
+        let call = format!(
+            "await {}{}({}, {{{}\n  }})",
+            config.mode.function(),
+            type_argument,
+            workflow_type,
+            options
+        );
+
+        let result = result_var(&node.id);
+        let workflow = string_literal(&config.workflow_type);
+        match config.mode {
+            ChildMode::Execute => format!(
+                "// Child workflow: {} - {} (execute)\n  const {} = {};",
+                node.id, workflow, result, call
+            ),
+            ChildMode::Start => {
+                let handle = format!("{}_handle", sanitize_id(&node.id));
+                format!(
+                    "// Child workflow: {id} - {workflow} (start)\n  const {handle} = {call};\n  \
+                     const {result} = {{ workflowId: {handle}.workflowId, \
+                     runId: {handle}.firstExecutionRunId }};",
+                    id = node.id,
+                    workflow = workflow,
+                    handle = handle,
+                    call = call,
+                    result = result
+                )
+            }
+        }
     }
 
-    fn topological_sort(&self) -> Vec<&WorkflowNode> {
-        // Simple implementation: put trigger first, end last, others in between
-        let mut trigger: Option<&WorkflowNode> = None;
-        let mut end: Option<&WorkflowNode> = None;
-        let mut others: Vec<&WorkflowNode> = Vec::new();
+    /// Build the argument passed to an activity or child workflow from its
+    /// input mapping
+    fn activity_args(&self, node: &WorkflowNode) -> String {
+        match &node.data.input_mapping {
+            Some(mapping) if !mapping.is_empty() => {
+                let entries: Vec<String> = mapping
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("{}: {}", property_key(name), self.compile_template(value))
+                    })
+                    .collect();
+                format!("{{ {} }}", entries.join(", "))
+            }
+            // Typed input interfaces lack an index signature, so pass a copy
+            _ if !self.data_fields(NodeType::DataIn).is_empty() => "{ ...input }".to_string(),
+            _ => "input".to_string(),
+        }
+    }
 
-        for node in &self.workflow.nodes {
-            match node.node_type {
-                NodeType::Trigger => trigger = Some(node),
-                NodeType::End => end = Some(node),
-                _ => others.push(node),
+    /// Compile a mapping template into a TypeScript expression.
+    ///
+    /// A value that is a single reference keeps the referenced value's type;
+    /// text mixed with references becomes a template literal.
+    fn compile_template(&self, template: &str) -> String {
+        let Ok(parts) = parse_template(template) else {
+            return string_literal(template);
+        };
+        match parts.as_slice() {
+            [] => string_literal(""),
+            [TemplatePart::Reference(reference)] => self.compile_reference(reference),
+            parts if parts.iter().all(|p| matches!(p, TemplatePart::Text(_))) => {
+                string_literal(template)
+            }
+            parts => {
+                let mut literal = String::from("`");
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(text) => literal.push_str(
+                            &text
+                                .replace('\\', "\\\\")
+                                .replace('`', "\\`")
+                                .replace("${", "\\${"),
+                        ),
+                        TemplatePart::Reference(reference) => {
+                            literal.push_str(&format!("${{{}}}", self.compile_reference(reference)))
+                        }
+                    }
+                }
+                literal.push('`');
+                literal
             }
         }
+    }
+
+    /// Compile a data reference into a TypeScript expression
+    fn compile_reference(&self, reference: &DataReference) -> String {
+        let (root, path) = match reference {
+            DataReference::Input(path) => ("input".to_string(), path),
+            DataReference::State(path) => ("state".to_string(), path),
+            DataReference::NodeOutput { node_id, path } => (result_var(node_id), path),
+            DataReference::NodeError { node_id, path } => (error_var(node_id), path),
+            DataReference::MapItem { node_id, path } => {
+                let item = self
+                    .workflow
+                    .get_node(node_id)
+                    .and_then(|n| n.map_config())
+                    .map(|config| config.item_variable)
+                    .unwrap_or_else(|| DEFAULT_ITEM_VARIABLE.to_string());
+                (item, path)
+            }
+        };
+        path.iter().fold(root, |expr, segment| expr + &member_access(segment))
+    }
 
-        let mut result = Vec::new();
-        if let Some(t) = trigger {
-            result.push(t);
+    /// Record the step in the progress reported by the progress query
+    fn track_progress(&self, node: &WorkflowNode, block: String) -> String {
+        if !self.tracks_progress() {
+            return block;
         }
-        result.extend(others);
-        if let Some(e) = end {
-            result.push(e);
+        let id = string_literal(&node.id);
+        if node.node_type == NodeType::End {
+            // End nodes return, so they count as completed once reached
+            format!(
+                "progress.currentStep = {id};\n  progress.completed.add({id});\n  {block}",
+                id = id,
+                block = block
+            )
+        } else {
+            format!(
+                "progress.currentStep = {id};\n  {block}\n  progress.completed.add({id});",
+                id = id,
+                block = block
+            )
         }
-        result
     }
 
     fn generate_node_code(&self, node: &WorkflowNode) -> Option<String> {
@@ -250,24 +1871,34 @@ impl<'a> TypeScriptGenerator<'a> {
             }
             NodeType::Activity => {
                 // Check if this is a Log activity
-                if node.data.component_name.as_deref() == Some("Log")
-                    || node.data.activity_name.as_deref() == Some("log")
-                {
+                if node.is_log() {
                     let config = self.extract_log_config(node);
                     let pattern = generate_log_code(&config, &node.id);
                     Some(pattern.code)
                 } else {
                     // Generic activity
-                    let activity_name = node
-                        .data
-                        .activity_name
-                        .clone()
-                        .unwrap_or_else(|| to_camel_case(&node.data.label));
-                    Some(format!(
-                        "const {}_result = await acts.{}(input);",
-                        to_snake_case(&node.data.label),
-                        activity_name
-                    ))
+                    let activity_name = activity_method(node);
+                    let result = result_var(&node.id);
+                    let proxy = self.activity_proxy(node);
+                    let args = self.activity_args(node);
+                    let mut code = format!(
+                        "const {} = await {}.{}({});",
+                        result, proxy, activity_name, args
+                    );
+                    // Registered once the activity succeeds, so only completed
+                    // steps are undone
+                    if let Some(compensation) = &node.data.compensation_activity {
+                        let input = if args == "input" {
+                            args
+                        } else {
+                            format!("input: {}", args)
+                        };
+                        code.push_str(&format!(
+                            "\n  compensations.push(() => {}.{}({{ {}, result: {} }}));",
+                            proxy, compensation, input, result
+                        ));
+                    }
+                    Some(code)
                 }
             }
             NodeType::KongLogging => {
@@ -276,32 +1907,31 @@ impl<'a> TypeScriptGenerator<'a> {
                 let pattern = generate_log_code(&config, &node.id);
                 Some(pattern.code)
             }
-            NodeType::Signal => {
-                node.data.signal_name.as_ref().map(|signal_name| {
-                    format!(
-                        "setHandler({}Signal, (payload) => {{ /* Handle {} signal */ }});",
-                        to_camel_case(signal_name),
-                        signal_name
-                    )
-                })
-            }
-            NodeType::Conditional | NodeType::Condition => {
-                let condition = node
-                    .data
-                    .condition
-                    .clone()
-                    .unwrap_or_else(|| "true".to_string());
-                Some(format!("if ({}) {{\n  // TODO: condition body\n}}", condition))
-            }
-            NodeType::Loop => Some("// TODO: loop implementation".to_string()),
-            NodeType::ChildWorkflow => {
-                node.data.workflow_id.as_ref().map(|wf_id| {
-                    format!(
-                        "const child_result = await executeChild('{}', {{ args: [input] }});",
-                        wf_id
-                    )
-                })
-            }
+            NodeType::Signal => node
+                .signal_config()
+                .map(|config| generate_signal_code(&config, &node.id).code),
+            NodeType::Update => node
+                .update_config()
+                .map(|config| generate_update_code(&config, &node.id).code),
+            NodeType::Timer => node
+                .timer_config()
+                .map(|config| generate_timer_code(&config, &node.id).code),
+            NodeType::Approval => node
+                .approval_config()
+                .map(|config| generate_approval_code(&config, &node.id, &node.data.label).code),
+            // Phases containing nodes are emitted as functions by emit_region
+            NodeType::Phase if !self.workflow.has_members(&node.id) => Some(format!(
+                "// Phase: {}\n  currentPhase = {};\n  \
+                 console.log('[PHASE:START]', {{ phase: currentPhase }});",
+                node.id,
+                string_literal(&node.data.label)
+            )),
+            NodeType::ChildWorkflow => node
+                .child_workflow_config()
+                .map(|config| self.generate_child_workflow(node, &config)),
+            NodeType::Agent => node
+                .agent_config()
+                .map(|config| self.generate_agent_code(node, &config)),
             NodeType::StateVariable => {
                 // Handle variable operations based on component name
                 let component_name = node.data.component_name.as_deref().unwrap_or("ServiceVariable");
@@ -348,6 +1978,22 @@ impl<'a> TypeScriptGenerator<'a> {
         StopConfig {
             result_mapping: node.data.result_mapping.clone(),
             include_metadata: node.data.include_metadata.unwrap_or(false),
+            result_type: self.result_type_name(),
+        }
+    }
+
+    /// Extract LoopConfig from node data
+    fn extract_loop_config(&self, node: &WorkflowNode) -> LoopConfig {
+        LoopConfig {
+            mode: LoopMode::resolve(
+                node.data.loop_type.as_deref(),
+                node.data.condition.is_some(),
+                node.data.collection.is_some(),
+            ),
+            condition: node.data.condition.clone(),
+            max_iterations: node.data.max_iterations,
+            collection: node.data.collection.clone(),
+            item_variable: node.data.item_variable.clone(),
         }
     }
 
@@ -466,6 +2112,40 @@ impl<'a> TypeScriptGenerator<'a> {
     }
 }
 
+/// Append generated blocks to an enclosing block, `depth` levels deeper.
+///
+/// Blocks start unindented and indent continuation lines by two spaces (the
+/// workflow body level), so every line is shifted to match the nesting depth.
+fn push_blocks(code: &mut String, blocks: &[String], depth: usize) {
+    let indent = "  ".repeat(depth);
+    for block in blocks {
+        let mut lines = block.lines();
+        code.push_str(&format!("\n  {}{}", indent, lines.next().unwrap_or_default()));
+        for line in lines {
+            code.push('\n');
+            if !line.is_empty() {
+                code.push_str(&indent);
+            }
+            code.push_str(line);
+        }
+    }
+}
+
+/// Function name generated for a sibling definition, which is compiled
+/// without a workflow name override
+fn sibling_function_name(workflow: &WorkflowDefinition) -> String {
+    to_camel_case(workflow.name.as_deref().unwrap_or("workflow")) + "Workflow"
+}
+
+/// Name of an activity node's method in the generated `Activities`
+/// interface: its activity name, or its label in camelCase
+pub(crate) fn activity_method(node: &WorkflowNode) -> String {
+    node.data
+        .activity_name
+        .clone()
+        .unwrap_or_else(|| to_camel_case(&node.data.label))
+}
+
 /// Convert string to camelCase
 fn to_camel_case(s: &str) -> String {
     let mut result = String::new();
@@ -487,28 +2167,26 @@ fn to_camel_case(s: &str) -> String {
     result
 }
 
-/// Convert string to snake_case
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-
-    for (i, c) in s.chars().enumerate() {
-        if c.is_uppercase() && i > 0 {
-            result.push('_');
-            result.push(c.to_lowercase().next().unwrap_or(c));
-        } else if c == ' ' || c == '-' {
-            result.push('_');
-        } else {
-            result.push(c.to_lowercase().next().unwrap_or(c));
-        }
+/// Convert string to PascalCase
+fn to_pascal_case(s: &str) -> String {
+    let camel = to_camel_case(s);
+    let mut chars = camel.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => camel,
     }
-
-    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_pascal_case() {
+        assert_eq!(to_pascal_case("order-approved"), "OrderApproved");
+        assert_eq!(to_pascal_case("approveSignal"), "ApproveSignal");
+    }
+
     #[test]
     fn test_to_camel_case() {
         assert_eq!(to_camel_case("hello_world"), "helloWorld");
@@ -516,12 +2194,4 @@ mod tests {
         assert_eq!(to_camel_case("hello world"), "helloWorld");
         assert_eq!(to_camel_case("HelloWorld"), "helloWorld");
     }
-
-    #[test]
-    fn test_to_snake_case() {
-        assert_eq!(to_snake_case("helloWorld"), "hello_world");
-        assert_eq!(to_snake_case("HelloWorld"), "hello_world");
-        assert_eq!(to_snake_case("hello-world"), "hello_world");
-        assert_eq!(to_snake_case("hello world"), "hello_world");
-    }
 }
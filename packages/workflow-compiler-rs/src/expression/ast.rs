@@ -0,0 +1,218 @@
+//! Expression syntax tree.
+
+use super::Span;
+
+/// An expression with its location in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+/// Expression variants
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Null,
+    Bool(bool),
+    /// Numeric literal, kept as written
+    Number(String),
+    String(String),
+    /// A root name such as `input`, `state` or a loop item variable
+    Identifier(String),
+    /// `object.property`
+    Member {
+        object: Box<Expr>,
+        property: String,
+    },
+    /// `object[index]`
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// Call of a whitelisted function
+    Call {
+        function: String,
+        args: Vec<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `test ? consequent : alternate`
+    Conditional {
+        test: Box<Expr>,
+        consequent: Box<Expr>,
+        alternate: Box<Expr>,
+    },
+    Array(Vec<Expr>),
+    /// Object literal entries in source order
+    Object(Vec<(String, Expr)>),
+}
+
+/// Unary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Negate,
+}
+
+/// Binary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Coalesce,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    /// TypeScript spelling of the operator
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Coalesce => "??",
+            BinaryOp::Eq => "===",
+            BinaryOp::NotEq => "!==",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        }
+    }
+
+    /// Whether the operator produces a boolean from comparing its operands
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Eq
+                | BinaryOp::NotEq
+                | BinaryOp::Lt
+                | BinaryOp::LtEq
+                | BinaryOp::Gt
+                | BinaryOp::GtEq
+        )
+    }
+}
+
+/// A reference to another node's output: `nodes.<id>.output`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeReference {
+    pub node_id: String,
+    pub span: Span,
+}
+
+impl Expr {
+    /// Visit this expression and every sub-expression, parents first
+    pub fn walk<'e>(&'e self, visit: &mut dyn FnMut(&'e Expr)) {
+        visit(self);
+        match &self.kind {
+            ExprKind::Member { object, .. } => object.walk(visit),
+            ExprKind::Index { object, index } => {
+                object.walk(visit);
+                index.walk(visit);
+            }
+            ExprKind::Call { args, .. } | ExprKind::Array(args) => {
+                args.iter().for_each(|arg| arg.walk(visit))
+            }
+            ExprKind::Unary { operand, .. } => operand.walk(visit),
+            ExprKind::Binary { left, right, .. } => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            ExprKind::Conditional {
+                test,
+                consequent,
+                alternate,
+            } => {
+                test.walk(visit);
+                consequent.walk(visit);
+                alternate.walk(visit);
+            }
+            ExprKind::Object(entries) => entries.iter().for_each(|(_, value)| value.walk(visit)),
+            ExprKind::Null
+            | ExprKind::Bool(_)
+            | ExprKind::Number(_)
+            | ExprKind::String(_)
+            | ExprKind::Identifier(_) => {}
+        }
+    }
+
+    /// If this expression is `nodes.<id>` or `nodes['<id>']`, the node id
+    pub fn as_node_selector(&self) -> Option<&str> {
+        match &self.kind {
+            ExprKind::Member { object, property } if object.is_identifier("nodes") => {
+                Some(property)
+            }
+            ExprKind::Index { object, index } if object.is_identifier("nodes") => {
+                match &index.kind {
+                    ExprKind::String(id) => Some(id),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// If this expression is `nodes.<id>.output`, the node id
+    pub fn as_node_output(&self) -> Option<&str> {
+        match &self.kind {
+            ExprKind::Member { object, property } if property == "output" => {
+                object.as_node_selector()
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this expression is the given bare identifier
+    pub fn is_identifier(&self, name: &str) -> bool {
+        matches!(&self.kind, ExprKind::Identifier(id) if id == name)
+    }
+
+    /// All `nodes.<id>.output` references in this expression
+    pub fn node_references(&self) -> Vec<NodeReference> {
+        let mut references = Vec::new();
+        self.walk(&mut |expr| {
+            if let Some(node_id) = expr.as_node_output() {
+                references.push(NodeReference {
+                    node_id: node_id.to_string(),
+                    span: expr.span,
+                });
+            }
+        });
+        references
+    }
+
+    /// Names of the state variables read through `state.<name>`
+    pub fn state_references(&self) -> Vec<(String, Span)> {
+        let mut references = Vec::new();
+        self.walk(&mut |expr| {
+            if let ExprKind::Member { object, property } = &expr.kind {
+                if object.is_identifier("state") {
+                    references.push((property.clone(), expr.span));
+                }
+            }
+        });
+        references
+    }
+}
@@ -0,0 +1,56 @@
+//! Functions callable from expressions.
+//!
+//! Only the functions listed here may be called; everything else is
+//! rejected by the parser.
+
+/// A whitelisted function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Function {
+    pub name: &'static str,
+    pub min_args: usize,
+    /// Maximum number of arguments, `None` for variadic functions
+    pub max_args: Option<usize>,
+}
+
+const fn function(name: &'static str, min_args: usize, max_args: Option<usize>) -> Function {
+    Function {
+        name,
+        min_args,
+        max_args,
+    }
+}
+
+/// All functions available to expressions
+pub const FUNCTIONS: &[Function] = &[
+    function("length", 1, Some(1)),
+    function("lower", 1, Some(1)),
+    function("upper", 1, Some(1)),
+    function("trim", 1, Some(1)),
+    function("contains", 2, Some(2)),
+    function("startsWith", 2, Some(2)),
+    function("endsWith", 2, Some(2)),
+    function("abs", 1, Some(1)),
+    function("floor", 1, Some(1)),
+    function("ceil", 1, Some(1)),
+    function("round", 1, Some(1)),
+    function("min", 1, None),
+    function("max", 1, None),
+    function("toString", 1, Some(1)),
+    function("toNumber", 1, Some(1)),
+    function("toJson", 1, Some(1)),
+    function("keys", 1, Some(1)),
+    function("exists", 1, Some(1)),
+    function("now", 0, Some(0)),
+];
+
+/// Look up a whitelisted function by name
+pub fn lookup(name: &str) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|f| f.name == name)
+}
+
+impl Function {
+    /// Whether the function accepts `count` arguments
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min_args && self.max_args.map_or(true, |max| count <= max)
+    }
+}
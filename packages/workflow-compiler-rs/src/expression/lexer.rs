@@ -0,0 +1,273 @@
+//! Expression tokenizer.
+
+use super::{ExpressionError, Span};
+
+/// Token kinds produced by the lexer
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(String),
+    String(String),
+    Identifier(String),
+    True,
+    False,
+    Null,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    Dot,
+    Question,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Bang,
+    AndAnd,
+    OrOr,
+    Coalesce,
+    EqEq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Eof,
+}
+
+/// A token with its location in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Tokenize `source` starting at byte offset `start`.
+///
+/// When `stop_at_brace` is set, lexing ends at the first unbalanced `}` so
+/// an expression embedded in a `${...}` template can be read in place.
+/// Returns the tokens (ending with `Eof`) and the offset where lexing stopped.
+pub fn tokenize(
+    source: &str,
+    start: usize,
+    stop_at_brace: bool,
+) -> Result<(Vec<Token>, usize), ExpressionError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = start;
+    let mut depth = 0usize;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let begin = pos;
+
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        let kind = match c {
+            b'0'..=b'9' => {
+                while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                    pos += 1;
+                }
+                let text = &source[begin..pos];
+                if text.parse::<f64>().is_err() || text.ends_with('.') {
+                    return Err(ExpressionError::new(
+                        format!("invalid number '{}'", text),
+                        Span::new(begin, pos),
+                    ));
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Number(text.to_string()),
+                    span: Span::new(begin, pos),
+                });
+                continue;
+            }
+            b'\'' | b'"' => {
+                let (value, end) = lex_string(source, begin)?;
+                pos = end;
+                tokens.push(Token {
+                    kind: TokenKind::String(value),
+                    span: Span::new(begin, end),
+                });
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b'$' => {
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_' || bytes[pos] == b'$')
+                {
+                    pos += 1;
+                }
+                let kind = match &source[begin..pos] {
+                    "true" => TokenKind::True,
+                    "false" => TokenKind::False,
+                    "null" => TokenKind::Null,
+                    word => TokenKind::Identifier(word.to_string()),
+                };
+                tokens.push(Token {
+                    kind,
+                    span: Span::new(begin, pos),
+                });
+                continue;
+            }
+            b'(' => TokenKind::LParen,
+            b')' => TokenKind::RParen,
+            b'[' => TokenKind::LBracket,
+            b']' => TokenKind::RBracket,
+            b'{' => {
+                depth += 1;
+                TokenKind::LBrace
+            }
+            b'}' => {
+                if depth == 0 && stop_at_brace {
+                    break;
+                }
+                depth = depth.saturating_sub(1);
+                TokenKind::RBrace
+            }
+            b',' => TokenKind::Comma,
+            b':' => TokenKind::Colon,
+            b'.' => TokenKind::Dot,
+            b'+' => TokenKind::Plus,
+            b'-' => TokenKind::Minus,
+            b'*' => TokenKind::Star,
+            b'/' => TokenKind::Slash,
+            b'%' => TokenKind::Percent,
+            b'?' if bytes.get(pos + 1) == Some(&b'?') => {
+                pos += 1;
+                TokenKind::Coalesce
+            }
+            b'?' => TokenKind::Question,
+            b'&' if bytes.get(pos + 1) == Some(&b'&') => {
+                pos += 1;
+                TokenKind::AndAnd
+            }
+            b'|' if bytes.get(pos + 1) == Some(&b'|') => {
+                pos += 1;
+                TokenKind::OrOr
+            }
+            b'=' | b'!' if bytes.get(pos + 1) == Some(&b'=') => {
+                // Accept both `==` and `===`; all equality is strict
+                pos += 1;
+                if bytes.get(pos + 1) == Some(&b'=') {
+                    pos += 1;
+                }
+                if c == b'=' {
+                    TokenKind::EqEq
+                } else {
+                    TokenKind::NotEq
+                }
+            }
+            b'!' => TokenKind::Bang,
+            b'<' if bytes.get(pos + 1) == Some(&b'=') => {
+                pos += 1;
+                TokenKind::LtEq
+            }
+            b'<' => TokenKind::Lt,
+            b'>' if bytes.get(pos + 1) == Some(&b'=') => {
+                pos += 1;
+                TokenKind::GtEq
+            }
+            b'>' => TokenKind::Gt,
+            _ => {
+                let ch = source[begin..].chars().next().unwrap_or_default();
+                let message = match ch {
+                    '=' => "assignment is not allowed in expressions".to_string(),
+                    ';' => "statements are not allowed in expressions".to_string(),
+                    '`' => "template literals are not allowed in expressions".to_string(),
+                    _ => format!("unexpected character '{}'", ch),
+                };
+                return Err(ExpressionError::new(
+                    message,
+                    Span::new(begin, begin + ch.len_utf8()),
+                ));
+            }
+        };
+
+        pos += 1;
+        tokens.push(Token {
+            kind,
+            span: Span::new(begin, pos),
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: Span::new(pos, pos),
+    });
+    Ok((tokens, pos))
+}
+
+/// Read a quoted string literal starting at `start`, returning its value and end offset
+fn lex_string(source: &str, start: usize) -> Result<(String, usize), ExpressionError> {
+    let mut chars = source[start..].char_indices();
+    let (_, quote) = chars.next().unwrap_or((0, '"'));
+    let mut value = String::new();
+
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            c if c == quote => return Ok((value, start + offset + 1)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+
+    Err(ExpressionError::new(
+        "unterminated string literal",
+        Span::new(start, source.len()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source, 0, false)
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_operators() {
+        assert_eq!(
+            kinds("a >= 1 && b === 'x'"),
+            vec![
+                TokenKind::Identifier("a".to_string()),
+                TokenKind::GtEq,
+                TokenKind::Number("1".to_string()),
+                TokenKind::AndAnd,
+                TokenKind::Identifier("b".to_string()),
+                TokenKind::EqEq,
+                TokenKind::String("x".to_string()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_rejects_assignment() {
+        let err = tokenize("state.x = 1", 0, false).unwrap_err();
+        assert_eq!(err.span, Span::new(8, 9));
+    }
+
+    #[test]
+    fn test_tokenize_stops_at_closing_brace() {
+        let (tokens, end) = tokenize("${ { a: 1 }.a } rest", 2, true).unwrap();
+        assert_eq!(end, 14);
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Eof);
+    }
+}
@@ -0,0 +1,338 @@
+//! Workflow expression language.
+//!
+//! Conditions, result mappings, variable values and `${...}` log templates
+//! are written in a small, side-effect free expression language: literals,
+//! property paths, comparisons, boolean and arithmetic operators, object and
+//! array literals, and calls to a fixed set of functions. Expressions are
+//! parsed here and compiled to TypeScript by the code generator, so no user
+//! text is ever spliced into generated code.
+
+mod ast;
+mod functions;
+mod lexer;
+mod parser;
+
+pub use ast::*;
+pub use functions::{lookup as lookup_function, Function, FUNCTIONS};
+
+use serde::Serialize;
+
+use crate::schema::{is_timestamp_literal, NodeType, WorkflowNode};
+
+/// Names every expression may start from
+pub const ROOT_IDENTIFIERS: &[&str] = &["input", "state", "nodes", "workflow"];
+
+/// Byte range of an expression or token in its source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Span covering this span and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A syntax or scoping error in an expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ExpressionError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl std::fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// Parse a single expression
+pub fn parse(source: &str) -> Result<Expr, ExpressionError> {
+    let (tokens, _) = lexer::tokenize(source, 0, false)?;
+    parser::Parser::new(tokens).parse_complete()
+}
+
+/// A piece of an interpolated `${...}` template
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSegment {
+    Text(String),
+    Expr(Expr),
+}
+
+/// Parse a text template with embedded `${...}` expressions
+pub fn parse_interpolated(source: &str) -> Result<Vec<TemplateSegment>, ExpressionError> {
+    let mut segments = Vec::new();
+    let mut offset = 0;
+
+    while let Some(found) = source[offset..].find("${") {
+        let open = offset + found;
+        if open > offset {
+            segments.push(TemplateSegment::Text(source[offset..open].to_string()));
+        }
+        let (tokens, end) = lexer::tokenize(source, open + 2, true)?;
+        if end >= source.len() {
+            return Err(ExpressionError::new(
+                "unclosed '${' in template",
+                Span::new(open, source.len()),
+            ));
+        }
+        segments.push(TemplateSegment::Expr(
+            parser::Parser::new(tokens).parse_complete()?,
+        ));
+        offset = end + 1;
+    }
+    if offset < source.len() {
+        segments.push(TemplateSegment::Text(source[offset..].to_string()));
+    }
+
+    Ok(segments)
+}
+
+/// Check that an expression only reads names that are in scope.
+///
+/// `locals` are extra names bound by the surrounding node, such as loop
+/// item variables. `nodes` may only be used as `nodes.<id>.output`.
+pub fn check_scope(expr: &Expr, locals: &[&str]) -> Result<(), ExpressionError> {
+    let mut error = None;
+    let mut node_selectors = Vec::new();
+
+    expr.walk(&mut |e| {
+        if error.is_some() {
+            return;
+        }
+        if let Some(id) = e.as_node_output() {
+            node_selectors.push((id.to_string(), e.span));
+        }
+        if let ExprKind::Identifier(name) = &e.kind {
+            if !ROOT_IDENTIFIERS.contains(&name.as_str()) && !locals.contains(&name.as_str()) {
+                error = Some(ExpressionError::new(
+                    format!("unknown name '{}'", name),
+                    e.span,
+                ));
+            }
+        }
+    });
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    // Every use of `nodes` must be part of a `nodes.<id>.output` path
+    let mut bare_nodes = None;
+    expr.walk(&mut |e| {
+        if bare_nodes.is_none() && e.is_identifier("nodes") {
+            let covered = node_selectors
+                .iter()
+                .any(|(_, span)| span.start == e.span.start);
+            if !covered {
+                bare_nodes = Some(e.span);
+            }
+        }
+    });
+    match bare_nodes {
+        Some(span) => Err(ExpressionError::new(
+            "node data must be read as nodes.<id>.output",
+            span,
+        )),
+        None => Ok(()),
+    }
+}
+
+/// How the source of a node expression is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpressionKind {
+    /// A single expression
+    Plain,
+    /// Text with embedded `${...}` expressions
+    Interpolated,
+}
+
+/// An expression-valued field of a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeExpression<'a> {
+    /// Field name as written in the workflow JSON
+    pub field: &'static str,
+    pub source: &'a str,
+    pub kind: ExpressionKind,
+}
+
+impl NodeExpression<'_> {
+    /// Parse the field, returning every embedded expression
+    pub fn parse(&self) -> Result<Vec<Expr>, ExpressionError> {
+        match self.kind {
+            ExpressionKind::Plain => parse(self.source).map(|expr| vec![expr]),
+            ExpressionKind::Interpolated => Ok(parse_interpolated(self.source)?
+                .into_iter()
+                .filter_map(|segment| match segment {
+                    TemplateSegment::Expr(expr) => Some(expr),
+                    TemplateSegment::Text(_) => None,
+                })
+                .collect()),
+        }
+    }
+}
+
+/// The expression-valued fields of a node
+pub fn node_expressions(node: &WorkflowNode) -> Vec<NodeExpression<'_>> {
+    let data = &node.data;
+    let fields: Vec<(&'static str, &Option<String>, ExpressionKind)> = match node.node_type {
+        NodeType::Conditional | NodeType::Condition => {
+            vec![("condition", &data.condition, ExpressionKind::Plain)]
+        }
+        NodeType::Loop => vec![
+            ("condition", &data.condition, ExpressionKind::Plain),
+            ("collection", &data.collection, ExpressionKind::Plain),
+        ],
+        NodeType::Map => vec![("collection", &data.collection, ExpressionKind::Plain)],
+        NodeType::End => vec![("resultMapping", &data.result_mapping, ExpressionKind::Plain)],
+        NodeType::Update => vec![
+            ("validator", &data.validator, ExpressionKind::Plain),
+            ("returnValue", &data.return_value, ExpressionKind::Plain),
+        ],
+        NodeType::Timer => vec![
+            (
+                "durationExpression",
+                &data.duration_expression,
+                ExpressionKind::Plain,
+            ),
+            ("until", &data.until, ExpressionKind::Plain),
+        ],
+        NodeType::StateVariable => vec![(
+            "valueExpression",
+            &data.value_expression,
+            ExpressionKind::Plain,
+        )],
+        NodeType::KongLogging => vec![(
+            "logMessage",
+            &data.log_message,
+            ExpressionKind::Interpolated,
+        )],
+        NodeType::Activity if node.is_log() => vec![(
+            "logMessage",
+            &data.log_message,
+            ExpressionKind::Interpolated,
+        )],
+        _ => Vec::new(),
+    };
+
+    fields
+        .into_iter()
+        .filter_map(|(field, source, kind)| {
+            let source = source.as_deref()?;
+            // A timer may wait until a timestamp written as a literal
+            if field == "until" && is_timestamp_literal(source) {
+                return None;
+            }
+            Some(NodeExpression {
+                field,
+                source,
+                kind,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_precedence() {
+        let expr = parse("state.a < 3 && !input.done || false").unwrap();
+        let ExprKind::Binary { op, left, .. } = expr.kind else {
+            panic!("expected binary expression");
+        };
+        assert_eq!(op, BinaryOp::Or);
+        assert!(matches!(left.kind, ExprKind::Binary { op: BinaryOp::And, .. }));
+    }
+
+    #[test]
+    fn test_parse_object_and_calls() {
+        let expr = parse("{ total: length(input.items), input, 'ok': true }").unwrap();
+        let ExprKind::Object(entries) = expr.kind else {
+            panic!("expected object literal");
+        };
+        let keys: Vec<_> = entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["total", "input", "ok"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_function() {
+        let err = parse("input.x && eval('1')").unwrap_err();
+        assert_eq!(err.message, "unknown function 'eval'");
+        assert_eq!(err.span, Span::new(11, 15));
+    }
+
+    #[test]
+    fn test_parse_rejects_method_calls() {
+        let err = parse("input.name.toUpperCase()").unwrap_err();
+        assert!(err.message.contains("whitelisted"));
+    }
+
+    #[test]
+    fn test_parse_rejects_prototype_access() {
+        assert!(parse("input.constructor").is_err());
+        assert!(parse("input['__proto__']").is_err());
+    }
+
+    #[test]
+    fn test_parse_reports_trailing_tokens() {
+        let err = parse("state.a state.b").unwrap_err();
+        assert_eq!(err.span, Span::new(8, 13));
+    }
+
+    #[test]
+    fn test_parse_interpolated() {
+        let segments = parse_interpolated("Order ${input.id} has ${length(input.items)} items")
+            .unwrap();
+        assert_eq!(segments.len(), 5);
+        assert!(matches!(&segments[0], TemplateSegment::Text(t) if t == "Order "));
+        assert!(matches!(&segments[4], TemplateSegment::Text(t) if t == " items"));
+
+        let err = parse_interpolated("Value ${input.x").unwrap_err();
+        assert_eq!(err.span.start, 6);
+    }
+
+    #[test]
+    fn test_check_scope() {
+        assert!(check_scope(&parse("input.a + state.b").unwrap(), &[]).is_ok());
+        assert!(check_scope(&parse("nodes['fetch-order'].output.id").unwrap(), &[]).is_ok());
+        assert!(check_scope(&parse("item.price").unwrap(), &["item"]).is_ok());
+
+        let err = check_scope(&parse("process.env").unwrap(), &[]).unwrap_err();
+        assert_eq!(err.message, "unknown name 'process'");
+
+        let err = check_scope(&parse("nodes.fetch").unwrap(), &[]).unwrap_err();
+        assert!(err.message.contains("nodes.<id>.output"));
+    }
+
+    #[test]
+    fn test_node_references() {
+        let expr = parse("nodes.fetch.output.id + nodes['b-2'].output.total").unwrap();
+        let ids: Vec<_> = expr.node_references().into_iter().map(|r| r.node_id).collect();
+        assert_eq!(ids, vec!["fetch", "b-2"]);
+    }
+}
@@ -0,0 +1,390 @@
+//! Recursive-descent expression parser.
+//!
+//! Precedence, lowest first: `?:`, `??`, `||`, `&&`, equality, comparison,
+//! additive, multiplicative, unary, member access / call.
+
+use super::ast::{BinaryOp, Expr, ExprKind, UnaryOp};
+use super::functions;
+use super::lexer::{Token, TokenKind};
+use super::{ExpressionError, Span};
+
+/// Property names that could reach the prototype chain
+const FORBIDDEN_PROPERTIES: &[&str] = &["constructor", "prototype", "__proto__"];
+
+/// Nesting limit to keep pathological input from exhausting the stack
+const MAX_DEPTH: usize = 64;
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    /// Parse a complete expression, requiring all tokens to be consumed
+    pub fn parse_complete(mut self) -> Result<Expr, ExpressionError> {
+        let expr = self.parse_expression()?;
+        match &self.peek().kind {
+            TokenKind::Eof => Ok(expr),
+            _ => Err(self.unexpected("end of expression")),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind, what: &str) -> Result<Token, ExpressionError> {
+        if &self.peek().kind == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.unexpected(what))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ExpressionError {
+        let token = self.peek();
+        let found = match &token.kind {
+            TokenKind::Eof => "end of expression".to_string(),
+            TokenKind::Identifier(name) => format!("'{}'", name),
+            TokenKind::Number(n) => n.clone(),
+            TokenKind::String(_) => "string".to_string(),
+            other => format!("{:?}", other),
+        };
+        ExpressionError::new(format!("expected {}, found {}", expected, found), token.span)
+    }
+
+    fn parse_expression(&mut self) -> Result<Expr, ExpressionError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExpressionError::new(
+                "expression is nested too deeply",
+                self.peek().span,
+            ));
+        }
+        let expr = self.parse_conditional();
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expr, ExpressionError> {
+        let test = self.parse_binary(0)?;
+        if !self.eat(&TokenKind::Question) {
+            return Ok(test);
+        }
+        let consequent = self.parse_expression()?;
+        self.expect(&TokenKind::Colon, "':'")?;
+        let alternate = self.parse_expression()?;
+        let span = test.span.to(alternate.span);
+        Ok(Expr {
+            kind: ExprKind::Conditional {
+                test: Box::new(test),
+                consequent: Box::new(consequent),
+                alternate: Box::new(alternate),
+            },
+            span,
+        })
+    }
+
+    /// Binary operator precedence levels, lowest first
+    fn binary_op(kind: &TokenKind, level: usize) -> Option<BinaryOp> {
+        let op = match kind {
+            TokenKind::Coalesce => BinaryOp::Coalesce,
+            TokenKind::OrOr => BinaryOp::Or,
+            TokenKind::AndAnd => BinaryOp::And,
+            TokenKind::EqEq => BinaryOp::Eq,
+            TokenKind::NotEq => BinaryOp::NotEq,
+            TokenKind::Lt => BinaryOp::Lt,
+            TokenKind::LtEq => BinaryOp::LtEq,
+            TokenKind::Gt => BinaryOp::Gt,
+            TokenKind::GtEq => BinaryOp::GtEq,
+            TokenKind::Plus => BinaryOp::Add,
+            TokenKind::Minus => BinaryOp::Sub,
+            TokenKind::Star => BinaryOp::Mul,
+            TokenKind::Slash => BinaryOp::Div,
+            TokenKind::Percent => BinaryOp::Rem,
+            _ => return None,
+        };
+        let op_level = match op {
+            BinaryOp::Coalesce => 0,
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::NotEq => 3,
+            BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        };
+        (op_level == level).then_some(op)
+    }
+
+    fn binary_op_any(kind: &TokenKind) -> bool {
+        (0..=6).any(|level| Self::binary_op(kind, level).is_some())
+            && !matches!(kind, TokenKind::Minus)
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, ExpressionError> {
+        if level > 6 {
+            return self.parse_unary();
+        }
+        let mut left = self.parse_binary(level + 1)?;
+        while let Some(op) = Self::binary_op(&self.peek().kind, level) {
+            self.advance();
+            let right = self.parse_binary(level + 1)?;
+            let span = left.span.to(right.span);
+            left = Expr {
+                kind: ExprKind::Binary {
+                    op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                span,
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExpressionError> {
+        let op = match self.peek().kind {
+            TokenKind::Bang => UnaryOp::Not,
+            TokenKind::Minus => UnaryOp::Negate,
+            _ => return self.parse_postfix(),
+        };
+        let start = self.advance().span;
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExpressionError::new("expression is nested too deeply", start));
+        }
+        let operand = self.parse_unary()?;
+        self.depth -= 1;
+        let span = start.to(operand.span);
+        Ok(Expr {
+            kind: ExprKind::Unary {
+                op,
+                operand: Box::new(operand),
+            },
+            span,
+        })
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            match self.peek().kind {
+                TokenKind::Dot => {
+                    self.advance();
+                    let token = self.advance();
+                    let property = match token.kind {
+                        TokenKind::Identifier(name) => name,
+                        // Keywords are valid property names
+                        TokenKind::True => "true".to_string(),
+                        TokenKind::False => "false".to_string(),
+                        TokenKind::Null => "null".to_string(),
+                        _ => {
+                            return Err(ExpressionError::new(
+                                "expected property name after '.'",
+                                token.span,
+                            ))
+                        }
+                    };
+                    check_property(&property, token.span)?;
+                    let span = expr.span.to(token.span);
+                    expr = Expr {
+                        kind: ExprKind::Member {
+                            object: Box::new(expr),
+                            property,
+                        },
+                        span,
+                    };
+                }
+                TokenKind::LBracket => {
+                    self.advance();
+                    let index = self.parse_expression()?;
+                    if let ExprKind::String(property) = &index.kind {
+                        check_property(property, index.span)?;
+                    }
+                    let end = self.expect(&TokenKind::RBracket, "']'")?;
+                    let span = expr.span.to(end.span);
+                    expr = Expr {
+                        kind: ExprKind::Index {
+                            object: Box::new(expr),
+                            index: Box::new(index),
+                        },
+                        span,
+                    };
+                }
+                TokenKind::LParen => {
+                    return Err(ExpressionError::new(
+                        "only whitelisted functions can be called",
+                        expr.span,
+                    ));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ExpressionError> {
+        if matches!(
+            self.peek().kind,
+            TokenKind::Eof
+                | TokenKind::RParen
+                | TokenKind::RBracket
+                | TokenKind::RBrace
+                | TokenKind::Comma
+                | TokenKind::Colon
+                | TokenKind::Dot
+                | TokenKind::Question
+        ) || Self::binary_op_any(&self.peek().kind)
+        {
+            return Err(self.unexpected("a value"));
+        }
+        let token = self.advance();
+        let kind = match token.kind {
+            TokenKind::Null => ExprKind::Null,
+            TokenKind::True => ExprKind::Bool(true),
+            TokenKind::False => ExprKind::Bool(false),
+            TokenKind::Number(n) => ExprKind::Number(n),
+            TokenKind::String(s) => ExprKind::String(s),
+            TokenKind::Identifier(name) if self.peek().kind == TokenKind::LParen => {
+                return self.parse_call(name, token.span);
+            }
+            TokenKind::Identifier(name) => ExprKind::Identifier(name),
+            TokenKind::LParen => {
+                let inner = self.parse_expression()?;
+                let end = self.expect(&TokenKind::RParen, "')'")?;
+                return Ok(Expr {
+                    kind: inner.kind,
+                    span: token.span.to(end.span),
+                });
+            }
+            TokenKind::LBracket => {
+                let items = self.parse_list(&TokenKind::RBracket, "']'")?;
+                let end = self.advance();
+                return Ok(Expr {
+                    kind: ExprKind::Array(items),
+                    span: token.span.to(end.span),
+                });
+            }
+            TokenKind::LBrace => return self.parse_object(token.span),
+            _ => return Err(ExpressionError::new("expected a value", token.span)),
+        };
+        Ok(Expr {
+            kind,
+            span: token.span,
+        })
+    }
+
+    fn parse_call(&mut self, name: String, span: Span) -> Result<Expr, ExpressionError> {
+        let Some(function) = functions::lookup(&name) else {
+            return Err(ExpressionError::new(
+                format!("unknown function '{}'", name),
+                span,
+            ));
+        };
+        self.advance();
+        let args = self.parse_list(&TokenKind::RParen, "')'")?;
+        let end = self.advance();
+        let span = span.to(end.span);
+        if !function.accepts(args.len()) {
+            return Err(ExpressionError::new(
+                format!(
+                    "function '{}' does not take {} argument(s)",
+                    name,
+                    args.len()
+                ),
+                span,
+            ));
+        }
+        Ok(Expr {
+            kind: ExprKind::Call {
+                function: name,
+                args,
+            },
+            span,
+        })
+    }
+
+    /// Parse comma-separated expressions up to (not including) `close`
+    fn parse_list(&mut self, close: &TokenKind, what: &str) -> Result<Vec<Expr>, ExpressionError> {
+        let mut items = Vec::new();
+        while &self.peek().kind != close {
+            items.push(self.parse_expression()?);
+            if !self.eat(&TokenKind::Comma) && &self.peek().kind != close {
+                return Err(self.unexpected(&format!("',' or {}", what)));
+            }
+        }
+        Ok(items)
+    }
+
+    fn parse_object(&mut self, start: Span) -> Result<Expr, ExpressionError> {
+        let mut entries = Vec::new();
+        while self.peek().kind != TokenKind::RBrace {
+            let (key, shorthand) = match &self.peek().kind {
+                TokenKind::Identifier(name) => (name.clone(), true),
+                TokenKind::String(name) => (name.clone(), false),
+                TokenKind::Number(n) => (n.clone(), false),
+                _ => return Err(self.unexpected("property name")),
+            };
+            let key_token = self.advance();
+            check_property(&key, key_token.span)?;
+
+            let value = if self.eat(&TokenKind::Colon) {
+                self.parse_expression()?
+            } else if shorthand {
+                // `{ input }` is short for `{ input: input }`
+                Expr {
+                    kind: ExprKind::Identifier(key.clone()),
+                    span: key_token.span,
+                }
+            } else {
+                return Err(self.unexpected("':'"));
+            };
+            entries.push((key, value));
+
+            if !self.eat(&TokenKind::Comma) && self.peek().kind != TokenKind::RBrace {
+                return Err(self.unexpected("',' or '}'"));
+            }
+        }
+        let end = self.advance();
+        Ok(Expr {
+            kind: ExprKind::Object(entries),
+            span: start.to(end.span),
+        })
+    }
+}
+
+fn check_property(name: &str, span: Span) -> Result<(), ExpressionError> {
+    if FORBIDDEN_PROPERTIES.contains(&name) {
+        Err(ExpressionError::new(
+            format!("property '{}' is not accessible", name),
+            span,
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -3,9 +3,11 @@
 //! This library provides workflow validation, TypeScript code generation,
 //! and verification functionality.
 
+pub mod analysis;
 pub mod api;
 pub mod codegen;
 pub mod config;
+pub mod expression;
 pub mod schema;
 pub mod validation;
 pub mod verification;
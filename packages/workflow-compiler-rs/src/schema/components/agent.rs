@@ -0,0 +1,127 @@
+//! Agent component schema.
+//!
+//! An Agent node runs a tool-calling loop with a language model. Each model
+//! call is a `callModel` activity, so the conversation is durable, and each
+//! tool the model calls is one of the workflow's activities. The loop ends
+//! when the model answers without calling a tool, with an output shaped by
+//! the output schema, or fails once it has used up its turns.
+
+use serde_json::{json, Map, Value};
+
+use crate::schema::{fields_type, DataField, TypeDescriptor, VariableType};
+
+/// Model calls an agent may make when it does not declare `maxTurns`
+pub const DEFAULT_MAX_TURNS: u32 = 10;
+
+/// Activity that makes an agent's model calls
+pub const CALL_MODEL_ACTIVITY: &str = "callModel";
+
+/// Agent component configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentConfig {
+    /// Model identifier, passed to the model provider as is
+    pub model: String,
+
+    /// System prompt
+    pub system_prompt: Option<String>,
+
+    /// Activities the model may call, by name
+    pub tools: Vec<String>,
+
+    /// Upper bound on the model calls
+    pub max_turns: u32,
+
+    /// Fields of the final output; an empty schema leaves it untyped
+    pub output_schema: Vec<DataField>,
+}
+
+impl AgentConfig {
+    /// Type of the node result, the agent's final output
+    pub fn output_type(&self) -> TypeDescriptor {
+        if self.output_schema.is_empty() {
+            TypeDescriptor::Any
+        } else {
+            fields_type(&self.output_schema)
+        }
+    }
+
+    /// JSON Schema of the final output, which providers use to constrain
+    /// the answer
+    pub fn output_json_schema(&self) -> Option<Value> {
+        if self.output_schema.is_empty() {
+            return None;
+        }
+        let mut properties = Map::new();
+        for field in &self.output_schema {
+            let mut property = Map::new();
+            if let Some(json_type) = json_schema_type(&field.field_type) {
+                property.insert("type".to_string(), json!(json_type));
+            }
+            if let Some(description) = &field.description {
+                property.insert("description".to_string(), json!(description));
+            }
+            properties.insert(field.name.clone(), Value::Object(property));
+        }
+        let required: Vec<&str> = self
+            .output_schema
+            .iter()
+            .filter(|f| f.required)
+            .map(|f| f.name.as_str())
+            .collect();
+        Some(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }))
+    }
+}
+
+/// JSON Schema type of a field; `None` accepts any value
+fn json_schema_type(field_type: &VariableType) -> Option<&'static str> {
+    match field_type {
+        VariableType::String => Some("string"),
+        VariableType::Number => Some("number"),
+        VariableType::Boolean => Some("boolean"),
+        VariableType::Object => Some("object"),
+        VariableType::Array => Some("array"),
+        VariableType::Any => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_output_schema() {
+        let config = AgentConfig {
+            model: "stub".to_string(),
+            system_prompt: None,
+            tools: Vec::new(),
+            max_turns: DEFAULT_MAX_TURNS,
+            output_schema: vec![
+                DataField::new("category", VariableType::String).required(),
+                DataField::new("notes", VariableType::Any),
+            ],
+        };
+        assert_eq!(
+            config.output_type().to_string(),
+            "{ category: string; notes: unknown }"
+        );
+        assert_eq!(
+            config.output_json_schema().unwrap(),
+            json!({
+                "type": "object",
+                "properties": { "category": { "type": "string" }, "notes": {} },
+                "required": ["category"],
+            })
+        );
+
+        let untyped = AgentConfig {
+            output_schema: Vec::new(),
+            ..config
+        };
+        assert_eq!(untyped.output_type(), TypeDescriptor::Any);
+        assert!(untyped.output_json_schema().is_none());
+    }
+}
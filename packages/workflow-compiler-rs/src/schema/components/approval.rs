@@ -0,0 +1,88 @@
+//! Approval component schema.
+//!
+//! An Approval node waits for one of its approvers to approve or reject,
+//! through a signal of its own that carries the decision. While it waits, the
+//! approval is listed by the workflow's `pendingApprovals` query. The outcome
+//! selects the node's `approved`, `rejected` or `timed_out` branch; the
+//! `timed_out` branch is where a workflow escalates an approval nobody
+//! decided on in time.
+
+use crate::schema::{DataField, SignalConfig, TypeDescriptor, VariableType};
+
+/// Source handle of the branch taken when an approver approves
+pub const APPROVED_HANDLE: &str = "approved";
+
+/// Source handle of the branch taken when an approver rejects
+pub const REJECTED_HANDLE: &str = "rejected";
+
+/// Source handle of the branch taken when the timeout passes without a
+/// decision
+pub const TIMED_OUT_HANDLE: &str = "timed_out";
+
+/// Outcomes of an approval, which are also its branch handles
+pub const APPROVAL_OUTCOMES: [&str; 3] = [APPROVED_HANDLE, REJECTED_HANDLE, TIMED_OUT_HANDLE];
+
+/// Query listing the approvals a workflow is waiting on
+pub const PENDING_APPROVALS_QUERY: &str = "pendingApprovals";
+
+/// Approval component configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalConfig {
+    /// Signal that delivers the decision
+    pub signal: String,
+
+    /// Users or groups whose decision is accepted
+    pub approvers: Vec<String>,
+
+    /// How long to wait before taking the `timed_out` branch
+    pub timeout: Option<String>,
+}
+
+impl ApprovalConfig {
+    /// The decision signal: who decided, whether they approved, and why
+    pub fn signal_config(&self) -> SignalConfig {
+        SignalConfig {
+            name: self.signal.clone(),
+            payload: vec![
+                DataField::new("approver", VariableType::String)
+                    .required()
+                    .with_description("Approver making the decision"),
+                DataField::new("approved", VariableType::Boolean).required(),
+                DataField::new("comment", VariableType::String),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Type of the node result: the outcome, the decision if there was one,
+    /// and when the approval completed
+    pub fn output_type(&self) -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("outcome", TypeDescriptor::String),
+            ("approver", TypeDescriptor::String),
+            ("comment", TypeDescriptor::String),
+            ("decidedAt", TypeDescriptor::String),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approval_signal_payload() {
+        let config = ApprovalConfig {
+            signal: "approve-order".to_string(),
+            approvers: vec!["finance".to_string()],
+            timeout: None,
+        };
+        let signal = config.signal_config();
+        assert_eq!(signal.name, "approve-order");
+        assert!(!signal.wait);
+        assert_eq!(
+            signal.payload_type().to_string(),
+            "{ approved: boolean; approver: string; comment: string }"
+        );
+    }
+}
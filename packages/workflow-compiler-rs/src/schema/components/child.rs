@@ -0,0 +1,154 @@
+//! Child workflow component schema.
+//!
+//! A Child Workflow node starts another workflow. By default the parent waits
+//! for the child's result (`executeChild`); in start mode it only waits for
+//! the child to be started (`startChild`) and continues with its handle.
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::TypeDescriptor;
+
+/// Whether the parent waits for the child to complete
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChildMode {
+    /// `executeChild` - wait for the child's result
+    #[default]
+    Execute,
+    /// `startChild` - fire and forget once the child has started
+    Start,
+}
+
+impl ChildMode {
+    /// Parse a mode from a node field, accepting common spellings
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "execute" | "wait" => Some(ChildMode::Execute),
+            "start" | "fire-and-forget" | "fire_and_forget" => Some(ChildMode::Start),
+            _ => None,
+        }
+    }
+
+    /// The `@temporalio/workflow` function that starts the child
+    pub fn function(&self) -> &'static str {
+        match self {
+            ChildMode::Execute => "executeChild",
+            ChildMode::Start => "startChild",
+        }
+    }
+}
+
+/// What happens to the child when the parent closes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParentClosePolicy {
+    Terminate,
+    Abandon,
+    RequestCancel,
+}
+
+impl ParentClosePolicy {
+    /// Parse a policy from a node field, accepting common spellings
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().replace('_', "-").as_str() {
+            "terminate" => Some(ParentClosePolicy::Terminate),
+            "abandon" => Some(ParentClosePolicy::Abandon),
+            "request-cancel" | "cancel" => Some(ParentClosePolicy::RequestCancel),
+            _ => None,
+        }
+    }
+
+    /// The `ParentClosePolicy` member in TypeScript
+    pub fn typescript(&self) -> &'static str {
+        match self {
+            ParentClosePolicy::Terminate => "ParentClosePolicy.TERMINATE",
+            ParentClosePolicy::Abandon => "ParentClosePolicy.ABANDON",
+            ParentClosePolicy::RequestCancel => "ParentClosePolicy.REQUEST_CANCEL",
+        }
+    }
+}
+
+/// Child workflow component configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChildWorkflowConfig {
+    /// Id of the child workflow definition, or the workflow type to start
+    pub workflow_type: String,
+
+    /// Workflow ID of the child execution; a template with `{{...}}` references
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+
+    /// Task queue of the child, when it differs from the parent's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_queue: Option<String>,
+
+    /// What happens to the child when the parent closes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_close_policy: Option<ParentClosePolicy>,
+
+    /// Timeout of the whole child execution, including retries and continue-as-new
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_timeout: Option<String>,
+
+    /// Timeout of a single child run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_timeout: Option<String>,
+
+    /// Whether the parent waits for the child to complete
+    #[serde(default)]
+    pub mode: ChildMode,
+}
+
+impl ChildWorkflowConfig {
+    /// Type of the node result: the child's output, or its handle in start mode
+    pub fn output_type(&self) -> TypeDescriptor {
+        match self.mode {
+            ChildMode::Execute => TypeDescriptor::object([
+                ("success", TypeDescriptor::Boolean),
+                ("result", TypeDescriptor::Any),
+                ("error", TypeDescriptor::String),
+                ("completedAt", TypeDescriptor::String),
+            ]),
+            ChildMode::Start => TypeDescriptor::object([
+                ("workflowId", TypeDescriptor::String),
+                ("runId", TypeDescriptor::String),
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_child_options() {
+        assert_eq!(ChildMode::parse("fire-and-forget"), Some(ChildMode::Start));
+        assert_eq!(ChildMode::parse("Execute"), Some(ChildMode::Execute));
+        assert_eq!(ChildMode::parse("later"), None);
+
+        assert_eq!(
+            ParentClosePolicy::parse("REQUEST_CANCEL"),
+            Some(ParentClosePolicy::RequestCancel)
+        );
+        assert_eq!(
+            ParentClosePolicy::parse("abandon"),
+            Some(ParentClosePolicy::Abandon)
+        );
+        assert_eq!(ParentClosePolicy::parse("keep"), None);
+    }
+
+    #[test]
+    fn test_child_output_type() {
+        let config = ChildWorkflowConfig {
+            workflow_type: "fulfilment".to_string(),
+            mode: ChildMode::Start,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.output_type().to_string(),
+            "{ runId: string; workflowId: string }"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! DataIn/DataOut component schema.
+//!
+//! DataIn and DataOut nodes declare the fields of the workflow input and
+//! result. The code generator turns them into named TypeScript interfaces.
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{TypeDescriptor, VariableType};
+
+/// A single field of the workflow input or result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DataField {
+    /// Property name in the generated interface
+    pub name: String,
+
+    /// Field type
+    #[serde(rename = "type", default)]
+    pub field_type: VariableType,
+
+    /// Whether callers must provide the field
+    #[serde(default)]
+    pub required: bool,
+
+    /// Description emitted as a doc comment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl DataField {
+    /// Create a new optional field with the given name and type
+    pub fn new(name: impl Into<String>, field_type: VariableType) -> Self {
+        Self {
+            name: name.into(),
+            field_type,
+            required: false,
+            description: None,
+        }
+    }
+
+    /// Mark this field as required
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Set the field description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Whether the name can be used as a TypeScript property without quoting
+    pub fn has_valid_name(&self) -> bool {
+        let mut chars = self.name.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the description can be emitted in a doc comment without
+    /// ending it
+    pub fn has_valid_description(&self) -> bool {
+        !self
+            .description
+            .as_deref()
+            .is_some_and(|d| d.contains("*/"))
+    }
+}
+
+/// Object type with the given fields; the first declaration of a name wins
+pub fn fields_type<'a>(fields: impl IntoIterator<Item = &'a DataField>) -> TypeDescriptor {
+    let mut object = std::collections::BTreeMap::new();
+    for field in fields {
+        object
+            .entry(field.name.clone())
+            .or_insert_with(|| TypeDescriptor::from(&field.field_type));
+    }
+    TypeDescriptor::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_field_deserialization() {
+        let field: DataField = serde_json::from_value(serde_json::json!({
+            "name": "orderId",
+            "type": "string",
+            "required": true,
+            "description": "Order to process"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            field,
+            DataField::new("orderId", VariableType::String)
+                .required()
+                .with_description("Order to process")
+        );
+    }
+
+    #[test]
+    fn test_data_field_name_validation() {
+        assert!(DataField::new("orderId", VariableType::String).has_valid_name());
+        assert!(DataField::new("_private", VariableType::Any).has_valid_name());
+        assert!(!DataField::new("order-id", VariableType::String).has_valid_name());
+        assert!(!DataField::new("1st", VariableType::Number).has_valid_name());
+    }
+}
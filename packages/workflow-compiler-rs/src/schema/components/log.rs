@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use validator::Validate;
 
+use crate::schema::{TypeDescriptor, Typed};
+
 /// Log level enumeration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -136,6 +138,26 @@ impl LogOutput {
     }
 }
 
+impl Typed for LogInput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("message", TypeDescriptor::String),
+            ("level", TypeDescriptor::String),
+            ("metadata", TypeDescriptor::object([])),
+        ])
+    }
+}
+
+impl Typed for LogOutput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("logged", TypeDescriptor::Boolean),
+            ("timestamp", TypeDescriptor::String),
+            ("logId", TypeDescriptor::String),
+        ])
+    }
+}
+
 /// Log component configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -289,6 +311,16 @@ mod tests {
         assert!(output.log_id.is_none());
     }
 
+    #[test]
+    fn test_log_type_descriptors() {
+        let input = LogInput::type_descriptor();
+        assert_eq!(input.field("message"), Some(&TypeDescriptor::String));
+
+        let output = LogOutput::type_descriptor();
+        assert_eq!(output.field("logged"), Some(&TypeDescriptor::Boolean));
+        assert_eq!(output.field("logId"), Some(&TypeDescriptor::String));
+    }
+
     #[test]
     fn test_log_entry_serialization() {
         let entry = LogEntry {
@@ -0,0 +1,120 @@
+//! Loop component schema.
+//!
+//! The Loop component repeats a body subgraph. The body is connected to the
+//! loop node through a back-edge and always runs under an iteration limit.
+
+use serde::{Deserialize, Serialize};
+
+/// Iteration limit applied when a loop does not declare `maxIterations`
+pub const DEFAULT_MAX_LOOP_ITERATIONS: u32 = 1000;
+
+/// How a loop decides whether to run another iteration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopMode {
+    /// Repeat while a condition holds
+    #[default]
+    While,
+    /// Repeat a fixed number of times
+    Count,
+    /// Repeat once per element of a collection
+    ForEach,
+}
+
+impl LoopMode {
+    /// Resolve the loop mode from node fields.
+    ///
+    /// An explicit `loopType` wins; otherwise a collection implies for-each,
+    /// a condition implies while, and anything else is a counted loop.
+    pub fn resolve(loop_type: Option<&str>, condition: bool, collection: bool) -> Self {
+        match loop_type.map(|t| t.to_lowercase()).as_deref() {
+            Some("while") => LoopMode::While,
+            Some("count") | Some("times") => LoopMode::Count,
+            Some("for-each") | Some("foreach") | Some("for_each") => LoopMode::ForEach,
+            _ if collection => LoopMode::ForEach,
+            _ if condition => LoopMode::While,
+            _ => LoopMode::Count,
+        }
+    }
+
+    /// Convert to string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoopMode::While => "while",
+            LoopMode::Count => "count",
+            LoopMode::ForEach => "for-each",
+        }
+    }
+}
+
+/// Loop component configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LoopConfig {
+    /// Loop mode
+    #[serde(default)]
+    pub mode: LoopMode,
+
+    /// Condition evaluated before each iteration (while loops)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+
+    /// Upper bound on the number of iterations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+
+    /// Collection expression to iterate over (for-each loops)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+
+    /// Name bound to the current element (for-each loops)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_variable: Option<String>,
+}
+
+impl LoopConfig {
+    /// The iteration limit, falling back to the default bound
+    pub fn iteration_limit(&self) -> u32 {
+        self.max_iterations.unwrap_or(DEFAULT_MAX_LOOP_ITERATIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_mode_resolve() {
+        assert_eq!(
+            LoopMode::resolve(Some("for-each"), false, false),
+            LoopMode::ForEach
+        );
+        assert_eq!(LoopMode::resolve(None, false, true), LoopMode::ForEach);
+        assert_eq!(LoopMode::resolve(None, true, false), LoopMode::While);
+        assert_eq!(LoopMode::resolve(None, false, false), LoopMode::Count);
+        assert_eq!(
+            LoopMode::resolve(Some("count"), true, false),
+            LoopMode::Count
+        );
+    }
+
+    #[test]
+    fn test_loop_config_iteration_limit() {
+        let config = LoopConfig::default();
+        assert_eq!(config.iteration_limit(), DEFAULT_MAX_LOOP_ITERATIONS);
+
+        let config = LoopConfig {
+            max_iterations: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(config.iteration_limit(), 5);
+    }
+
+    #[test]
+    fn test_loop_mode_serialization() {
+        assert_eq!(
+            serde_json::to_string(&LoopMode::ForEach).unwrap(),
+            "\"for-each\""
+        );
+    }
+}
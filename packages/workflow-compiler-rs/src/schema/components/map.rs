@@ -0,0 +1,50 @@
+//! Map component schema.
+//!
+//! A Map node runs a region of the graph once per element of a collection:
+//! the nodes reached through its `body` edge, up to the node its other edge
+//! leads to. Items run concurrently in batches of at most `maxConcurrency`,
+//! and the node's result collects what the body produced for each item, in
+//! collection order.
+
+use crate::schema::TypeDescriptor;
+
+/// Items run at once when a map does not declare `maxConcurrency`
+pub const DEFAULT_MAP_CONCURRENCY: u32 = 10;
+
+/// Name bound to the current element when a map does not declare one
+pub const DEFAULT_ITEM_VARIABLE: &str = "item";
+
+/// Map component configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapConfig {
+    /// Collection expression to map over
+    pub collection: String,
+
+    /// Name bound to the current element inside the body
+    pub item_variable: String,
+
+    /// Upper bound on the items running at once
+    pub max_concurrency: u32,
+
+    /// Upper bound on the collection size; larger collections fail the map
+    pub max_items: Option<u32>,
+}
+
+impl MapConfig {
+    /// Type of the node result, given the type of each item's result
+    pub fn output_type(item_result: TypeDescriptor) -> TypeDescriptor {
+        TypeDescriptor::array(item_result)
+    }
+
+    /// Whether the item variable is an identifier that can name the
+    /// parameter of the generated body function
+    pub fn has_valid_item_variable(&self) -> bool {
+        let mut chars = self.item_variable.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            }
+            _ => false,
+        }
+    }
+}
@@ -2,12 +2,53 @@
 //!
 //! This module defines the input/output schemas for all workflow components.
 
+mod agent;
+mod approval;
+mod child;
+mod data;
 mod log;
+mod loops;
+mod map;
+mod parallel;
+mod retry;
+mod signal;
 mod start;
 mod stop;
+mod timer;
+mod update;
 mod variable;
 
+pub use agent::*;
+pub use approval::*;
+pub use child::*;
+pub use data::*;
 pub use log::*;
+pub use loops::*;
+pub use map::*;
+pub use parallel::*;
+pub use retry::*;
+pub use signal::*;
 pub use start::*;
 pub use stop::*;
+pub use timer::*;
+pub use update::*;
 pub use variable::*;
+
+use crate::schema::PortTypes;
+
+/// Input and output types of a built-in component, by component name
+pub fn component_port_types(component_name: &str) -> Option<PortTypes> {
+    let types = match component_name {
+        "Log" | "log" => PortTypes::of::<LogInput, LogOutput>(),
+        "ServiceVariable" | "service-variable" => {
+            PortTypes::of::<ServiceVariableInput, ServiceVariableOutput>()
+        }
+        "ProjectVariable" | "project-variable" => {
+            PortTypes::of::<ProjectVariableInput, ProjectVariableOutput>()
+        }
+        "GetVariable" | "get-variable" => PortTypes::of::<GetVariableInput, GetVariableOutput>(),
+        "SetVariable" | "set-variable" => PortTypes::of::<SetVariableInput, SetVariableOutput>(),
+        _ => return None,
+    };
+    Some(types)
+}
@@ -0,0 +1,68 @@
+//! Parallel component schema.
+//!
+//! A Parallel node fans out to every outgoing edge at once; the branches
+//! re-join at the node where they meet (optionally marked with a Join node).
+
+use serde::{Deserialize, Serialize};
+
+/// How the branches of a parallel block are awaited
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParallelMode {
+    /// `Promise.all` - the first failing branch fails the block
+    #[default]
+    All,
+    /// `Promise.allSettled` - every branch runs to completion, failures are logged
+    AllSettled,
+}
+
+impl ParallelMode {
+    /// Parse a mode from a node field, accepting common spellings
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "all" | "fail-fast" => Some(ParallelMode::All),
+            "all-settled" | "allsettled" | "all_settled" | "settled" => {
+                Some(ParallelMode::AllSettled)
+            }
+            _ => None,
+        }
+    }
+
+    /// The `Promise` combinator used for this mode
+    pub fn combinator(&self) -> &'static str {
+        match self {
+            ParallelMode::All => "Promise.all",
+            ParallelMode::AllSettled => "Promise.allSettled",
+        }
+    }
+}
+
+/// Parallel component configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParallelConfig {
+    /// How the branches are awaited
+    #[serde(default)]
+    pub mode: ParallelMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_mode_parse() {
+        assert_eq!(ParallelMode::parse("all"), Some(ParallelMode::All));
+        assert_eq!(
+            ParallelMode::parse("allSettled"),
+            Some(ParallelMode::AllSettled)
+        );
+        assert_eq!(ParallelMode::parse("race"), None);
+    }
+
+    #[test]
+    fn test_parallel_mode_combinator() {
+        assert_eq!(ParallelMode::default().combinator(), "Promise.all");
+        assert_eq!(ParallelMode::AllSettled.combinator(), "Promise.allSettled");
+    }
+}
@@ -0,0 +1,110 @@
+//! Retry component schema.
+//!
+//! A Retry node wraps a region of the graph: the nodes reached through its
+//! `body` edge, up to the node its other edge leads to. When any step in the
+//! region fails, the whole region runs again after a backoff, so several
+//! activities can be retried together instead of one by one. The retries run
+//! in the workflow, with durable timers between attempts.
+
+use crate::schema::{Duration, RetryPolicy};
+
+/// Interval before the first retry when the policy does not set one, as in
+/// Temporal's activity retries
+const DEFAULT_INITIAL_INTERVAL: Duration = Duration::from_millis(1_000);
+
+/// Backoff coefficient when the policy does not set one
+const DEFAULT_BACKOFF_COEFFICIENT: f64 = 2.0;
+
+/// Retry component configuration, resolved from the node's retry policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Attempt limit, or `None` to retry until the region succeeds
+    pub max_attempts: Option<u32>,
+
+    /// Delay before the first retry
+    pub initial_interval: Duration,
+
+    /// Factor by which the delay grows after each retry
+    pub backoff_coefficient: f64,
+
+    /// Upper bound on the delay between attempts
+    pub max_interval: Duration,
+
+    /// Error types that fail the region without another attempt
+    pub non_retryable_errors: Vec<String>,
+}
+
+impl RetryConfig {
+    /// Delay before the attempt following `attempt` (counting from 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let growth = self
+            .backoff_coefficient
+            .powi(attempt.saturating_sub(1) as i32);
+        let millis = (self.initial_interval.as_millis() as f64 * growth).round() as u64;
+        Duration::from_millis(millis.min(self.max_interval.as_millis()))
+    }
+}
+
+/// Unset options take Temporal's defaults: a one second initial interval,
+/// a coefficient of 2 and a maximum interval of 100 times the initial one.
+/// Intervals that do not parse are reported by validation and fall back to
+/// the defaults here.
+impl From<&RetryPolicy> for RetryConfig {
+    fn from(policy: &RetryPolicy) -> Self {
+        let parse = |value: Option<&str>| value.and_then(|v| Duration::parse(v).ok());
+        let initial_interval =
+            parse(policy.initial_interval()).unwrap_or(DEFAULT_INITIAL_INTERVAL);
+        let max_interval = parse(policy.maximum_interval()).unwrap_or_else(|| {
+            Duration::from_millis(initial_interval.as_millis().saturating_mul(100))
+        });
+        Self {
+            max_attempts: policy.maximum_attempts(),
+            initial_interval,
+            backoff_coefficient: policy
+                .backoff_coefficient()
+                .unwrap_or(DEFAULT_BACKOFF_COEFFICIENT),
+            max_interval,
+            non_retryable_errors: policy.non_retryable_errors().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::RetryStrategy;
+
+    #[test]
+    fn test_retry_config_from_policy() {
+        let config = RetryConfig::from(&RetryPolicy {
+            strategy: RetryStrategy::FailAfterX,
+            max_attempts: Some(3),
+            non_retryable_errors: Some(vec!["CardDeclined".to_string()]),
+            ..Default::default()
+        });
+        assert_eq!(config.max_attempts, Some(3));
+        assert_eq!(config.initial_interval, DEFAULT_INITIAL_INTERVAL);
+        assert_eq!(config.backoff_coefficient, 2.0);
+        assert_eq!(config.max_interval, Duration::from_millis(100_000));
+        assert_eq!(config.non_retryable_errors, vec!["CardDeclined"]);
+
+        let once = RetryConfig::from(&RetryPolicy {
+            non_retryable_errors: Some(vec!["CardDeclined".to_string()]),
+            ..Default::default()
+        });
+        assert_eq!(once.max_attempts, Some(1));
+        assert!(once.non_retryable_errors.is_empty());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let config = RetryConfig::from(&RetryPolicy {
+            strategy: RetryStrategy::ExponentialBackoff,
+            initial_interval: Some("2s".to_string()),
+            max_interval: Some("10s".to_string()),
+            ..Default::default()
+        });
+        let delays: Vec<u64> = (1..=4).map(|a| config.delay(a).as_millis()).collect();
+        assert_eq!(delays, vec![2_000, 4_000, 8_000, 10_000]);
+    }
+}
@@ -0,0 +1,109 @@
+//! Signal component schema.
+//!
+//! A Signal node declares a Temporal signal with a typed payload. The signal
+//! handler records every delivery in workflow state; the node itself reads
+//! that state and can block the flow until the signal arrives.
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{fields_type, DataField, TypeDescriptor};
+
+/// Signal component configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalConfig {
+    /// Signal name, as sent by clients
+    pub name: String,
+
+    /// Payload fields; an empty schema leaves the payload untyped
+    #[serde(default)]
+    pub payload: Vec<DataField>,
+
+    /// Whether the node blocks until the signal has been received
+    #[serde(default)]
+    pub wait: bool,
+
+    /// How long to wait before continuing without the signal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+
+    /// State variable that receives the payload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_variable: Option<String>,
+}
+
+impl SignalConfig {
+    /// Type of the signal payload
+    pub fn payload_type(&self) -> TypeDescriptor {
+        if self.payload.is_empty() {
+            TypeDescriptor::Any
+        } else {
+            fields_type(&self.payload)
+        }
+    }
+
+    /// Type of the node result: whether the signal arrived, and its payload
+    pub fn output_type(&self) -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("received", TypeDescriptor::Boolean),
+            ("payload", self.payload_type()),
+        ])
+    }
+
+    /// Whether a signal name can be used to derive a TypeScript identifier
+    pub fn has_valid_name(&self) -> bool {
+        is_valid_handler_name(&self.name)
+    }
+}
+
+/// Whether a signal, query or update name can be used to derive a
+/// TypeScript identifier: letters, digits, `_` and `-`, not starting with a
+/// digit, and not using the `__` prefix Temporal reserves
+pub fn is_valid_handler_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        _ => false,
+    };
+    valid && !name.starts_with("__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::VariableType;
+
+    #[test]
+    fn test_signal_output_type() {
+        let config = SignalConfig {
+            name: "approve".to_string(),
+            payload: vec![DataField::new("approved", VariableType::Boolean).required()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.output_type().to_string(),
+            "{ payload: { approved: boolean }; received: boolean }"
+        );
+
+        let untyped = SignalConfig {
+            name: "ping".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(untyped.payload_type(), TypeDescriptor::Any);
+    }
+
+    #[test]
+    fn test_signal_name_validation() {
+        let named = |name: &str| SignalConfig {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        assert!(named("approve-order").has_valid_name());
+        assert!(named("callback_received").has_valid_name());
+        assert!(!named("order.approved").has_valid_name());
+        assert!(!named("1st").has_valid_name());
+        assert!(!named("__stack_trace").has_valid_name());
+    }
+}
@@ -14,6 +14,9 @@ pub struct StopInput {
     pub result: Option<serde_json::Value>,
 }
 
+/// Error reported by a workflow that was cancelled
+pub const CANCELLED_MESSAGE: &str = "Workflow was cancelled";
+
 /// Stop component output - workflow completion result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,6 +24,10 @@ pub struct StopOutput {
     /// Whether the workflow completed successfully
     pub success: bool,
 
+    /// Whether the workflow stopped because it was cancelled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+
     /// The workflow result
     pub result: serde_json::Value,
 
@@ -33,6 +40,7 @@ impl StopOutput {
     pub fn success(result: serde_json::Value) -> Self {
         Self {
             success: true,
+            cancelled: false,
             result,
             completed_at: Utc::now(),
         }
@@ -42,10 +50,20 @@ impl StopOutput {
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
             success: false,
+            cancelled: false,
             result: serde_json::json!({ "error": error.into() }),
             completed_at: Utc::now(),
         }
     }
+
+    /// Create the output of a workflow that was cancelled, after its cleanup
+    /// branch ran
+    pub fn cancelled() -> Self {
+        Self {
+            cancelled: true,
+            ..Self::failure(CANCELLED_MESSAGE)
+        }
+    }
 }
 
 /// Stop component configuration
@@ -59,6 +77,10 @@ pub struct StopConfig {
     /// Whether to include execution metadata in result
     #[serde(default)]
     pub include_metadata: bool,
+
+    /// TypeScript type of the result, when the workflow declares one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_type: Option<String>,
 }
 
 #[cfg(test)]
@@ -79,6 +101,17 @@ mod tests {
         assert_eq!(output.result["error"], "Something went wrong");
     }
 
+    #[test]
+    fn test_stop_output_cancelled() {
+        let output = StopOutput::cancelled();
+        assert!(!output.success);
+        assert!(output.cancelled);
+        assert_eq!(output.result["error"], CANCELLED_MESSAGE);
+
+        let json = serde_json::to_string(&StopOutput::success(serde_json::json!(1))).unwrap();
+        assert!(!json.contains("cancelled"));
+    }
+
     #[test]
     fn test_stop_input_serialization() {
         let input = StopInput {
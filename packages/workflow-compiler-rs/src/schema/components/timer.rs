@@ -0,0 +1,95 @@
+//! Timer component schema.
+//!
+//! A Timer node waits durably, as a Temporal timer rather than an activity
+//! that sleeps: for a fixed duration, for a duration computed by an
+//! expression, or until an absolute time. It can also race a signal, so the
+//! flow continues early when the signal arrives.
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::schema::TypeDescriptor;
+
+/// How long a timer waits
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimerDelay {
+    /// A duration such as `"30m"` or `"PT1H"`
+    Duration(String),
+    /// An expression evaluating to milliseconds or a duration string
+    DurationExpression(String),
+    /// An RFC 3339 timestamp such as `"2026-01-01T09:00:00Z"`
+    Timestamp(String),
+    /// An expression evaluating to a timestamp or a date string
+    UntilExpression(String),
+}
+
+impl TimerDelay {
+    /// Delay until `until`, which is a timestamp when it starts like a date
+    /// and an expression otherwise
+    pub fn until(until: &str) -> Self {
+        if is_timestamp_literal(until) {
+            TimerDelay::Timestamp(until.trim().to_string())
+        } else {
+            TimerDelay::UntilExpression(until.to_string())
+        }
+    }
+}
+
+/// Timer component configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerConfig {
+    /// How long the timer waits
+    pub delay: TimerDelay,
+
+    /// Signal that ends the wait early
+    pub signal: Option<String>,
+}
+
+impl TimerConfig {
+    /// Type of the node result: whether the signal ended the wait, and when
+    /// the timer completed
+    pub fn output_type(&self) -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("signalled", TypeDescriptor::Boolean),
+            ("completedAt", TypeDescriptor::String),
+        ])
+    }
+}
+
+/// Whether `value` is written as a date (`YYYY-...`) rather than an
+/// expression, which would otherwise read it as a subtraction
+pub fn is_timestamp_literal(value: &str) -> bool {
+    let value = value.trim().as_bytes();
+    value.len() > 4 && value[..4].iter().all(u8::is_ascii_digit) && value[4] == b'-'
+}
+
+/// Parse an RFC 3339 timestamp; the offset is required so the deadline does
+/// not depend on the worker's time zone
+pub fn parse_timestamp(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value.trim()).map_err(|_| {
+        format!(
+            "'{}' is not an RFC 3339 timestamp such as 2026-01-01T09:00:00Z",
+            value
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_until() {
+        assert_eq!(
+            TimerDelay::until("2026-01-01T09:00:00Z"),
+            TimerDelay::Timestamp("2026-01-01T09:00:00Z".to_string())
+        );
+        assert_eq!(
+            TimerDelay::until("input.deadline"),
+            TimerDelay::UntilExpression("input.deadline".to_string())
+        );
+
+        assert!(parse_timestamp("2026-01-01T09:00:00+02:00").is_ok());
+        assert!(parse_timestamp("2026-01-01").is_err());
+        assert!(parse_timestamp("2026-01-01T09:00:00").is_err());
+    }
+}
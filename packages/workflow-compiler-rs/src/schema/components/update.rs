@@ -0,0 +1,117 @@
+//! Update component schema.
+//!
+//! An Update node declares a Temporal workflow update: a typed request that a
+//! client sends and waits on for a typed result. An optional validator
+//! expression rejects requests before they are written to history. The
+//! handler records the argument in workflow state; the node itself reads that
+//! state and can block the flow until the update arrives.
+
+use serde::{Deserialize, Serialize};
+
+use super::is_valid_handler_name;
+use crate::schema::{fields_type, DataField, TypeDescriptor};
+
+/// Name under which validator and return value expressions read the update
+/// argument
+pub const UPDATE_ARGUMENT: &str = "update";
+
+/// Update component configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateConfig {
+    /// Update name, as sent by clients
+    pub name: String,
+
+    /// Argument fields; an empty schema leaves the argument untyped
+    #[serde(default)]
+    pub argument: Vec<DataField>,
+
+    /// Result fields; an empty schema leaves the result untyped
+    #[serde(default)]
+    pub returns: Vec<DataField>,
+
+    /// Expression that must hold for the update to be accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator: Option<String>,
+
+    /// Expression returned to the client
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_value: Option<String>,
+
+    /// Whether the node blocks until the update has been received
+    #[serde(default)]
+    pub wait: bool,
+
+    /// How long to wait before continuing without the update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+
+    /// State variable that receives the argument
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_variable: Option<String>,
+}
+
+impl UpdateConfig {
+    /// Type of the update argument
+    pub fn argument_type(&self) -> TypeDescriptor {
+        if self.argument.is_empty() {
+            TypeDescriptor::Any
+        } else {
+            fields_type(&self.argument)
+        }
+    }
+
+    /// Type of the value returned to the client, if the update returns one
+    pub fn return_type(&self) -> Option<TypeDescriptor> {
+        if !self.returns.is_empty() {
+            Some(fields_type(&self.returns))
+        } else {
+            self.return_value.as_ref().map(|_| TypeDescriptor::Any)
+        }
+    }
+
+    /// Type of the node result: whether the update arrived, and its argument
+    pub fn output_type(&self) -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("received", TypeDescriptor::Boolean),
+            ("args", self.argument_type()),
+        ])
+    }
+
+    /// Whether an update name can be used to derive a TypeScript identifier
+    pub fn has_valid_name(&self) -> bool {
+        is_valid_handler_name(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::VariableType;
+
+    #[test]
+    fn test_update_types() {
+        let config = UpdateConfig {
+            name: "set-discount".to_string(),
+            argument: vec![DataField::new("percent", VariableType::Number).required()],
+            returns: vec![DataField::new("total", VariableType::Number).required()],
+            return_value: Some("{ total: state.total }".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.output_type().to_string(),
+            "{ args: { percent: number }; received: boolean }"
+        );
+        assert_eq!(
+            config.return_type().map(|t| t.to_string()),
+            Some("{ total: number }".to_string())
+        );
+
+        let untyped = UpdateConfig {
+            name: "ping".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(untyped.argument_type(), TypeDescriptor::Any);
+        assert_eq!(untyped.return_type(), None);
+    }
+}
@@ -6,6 +6,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::schema::{TypeDescriptor, Typed};
+
 /// Variable scope determines where the variable is accessible
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -314,6 +316,106 @@ fn default_true() -> bool {
     true
 }
 
+// =============================================================================
+// Type Descriptors
+// =============================================================================
+
+impl Typed for ServiceVariableInput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("name", TypeDescriptor::String),
+            ("initialValue", TypeDescriptor::Any),
+            ("metadata", TypeDescriptor::object([])),
+        ])
+    }
+}
+
+impl Typed for ServiceVariableOutput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("value", TypeDescriptor::Any),
+            ("created", TypeDescriptor::Boolean),
+            ("lastUpdated", TypeDescriptor::String),
+        ])
+    }
+}
+
+impl Typed for ProjectVariableInput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("name", TypeDescriptor::String),
+            ("requestingServiceId", TypeDescriptor::String),
+            ("initialValue", TypeDescriptor::Any),
+            ("metadata", TypeDescriptor::object([])),
+        ])
+    }
+}
+
+impl Typed for ProjectVariableOutput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("value", TypeDescriptor::Any),
+            ("created", TypeDescriptor::Boolean),
+            ("lastUpdated", TypeDescriptor::String),
+            ("serviceId", TypeDescriptor::String),
+        ])
+    }
+}
+
+impl Typed for GetVariableInput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("name", TypeDescriptor::String),
+            ("scope", TypeDescriptor::String),
+            ("serviceId", TypeDescriptor::String),
+        ])
+    }
+}
+
+impl Typed for GetVariableOutput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("value", TypeDescriptor::Any),
+            ("exists", TypeDescriptor::Boolean),
+            ("metadata", VariableMetadata::type_descriptor()),
+        ])
+    }
+}
+
+impl Typed for VariableMetadata {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("variableType", TypeDescriptor::String),
+            ("createdAt", TypeDescriptor::String),
+            ("updatedAt", TypeDescriptor::String),
+            ("scope", TypeDescriptor::String),
+            ("ownerServiceId", TypeDescriptor::String),
+        ])
+    }
+}
+
+impl Typed for SetVariableInput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("name", TypeDescriptor::String),
+            ("value", TypeDescriptor::Any),
+            ("scope", TypeDescriptor::String),
+            ("serviceId", TypeDescriptor::String),
+        ])
+    }
+}
+
+impl Typed for SetVariableOutput {
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::object([
+            ("success", TypeDescriptor::Boolean),
+            ("previousValue", TypeDescriptor::Any),
+            ("created", TypeDescriptor::Boolean),
+            ("updatedAt", TypeDescriptor::String),
+        ])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,253 @@
+//! Durations used by timeouts and retry intervals.
+//!
+//! Workflow definitions write durations as strings, either in the shorthand
+//! Temporal's TypeScript SDK accepts (`"500ms"`, `"30s"`, `"1m"`, `"2h"`,
+//! `"1d"`, combined as `"1h30m"`) or in ISO-8601 (`"PT1M30S"`, `"P1D"`).
+//! Parsing them lets validation reject malformed values and lets code
+//! generation emit a single normalized spelling.
+
+use std::fmt;
+use std::str::FromStr;
+
+const SECOND: u64 = 1_000;
+const MINUTE: u64 = 60 * SECOND;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Shorthand units, longest suffix first so `ms` is not read as `m`
+const SHORTHAND_UNITS: [(&str, u64); 5] = [
+    ("ms", 1),
+    ("s", SECOND),
+    ("m", MINUTE),
+    ("h", HOUR),
+    ("d", DAY),
+];
+
+/// ISO-8601 designators before and after `T`, in the order they must appear
+const ISO_DATE_UNITS: [(char, u64); 2] = [('W', WEEK), ('D', DAY)];
+const ISO_TIME_UNITS: [(char, u64); 3] = [('H', HOUR), ('M', MINUTE), ('S', SECOND)];
+
+/// A non-negative duration with millisecond precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Duration {
+    millis: u64,
+}
+
+impl Duration {
+    /// Duration of `millis` milliseconds
+    pub const fn from_millis(millis: u64) -> Self {
+        Self { millis }
+    }
+
+    /// Length of the duration in milliseconds
+    pub fn as_millis(&self) -> u64 {
+        self.millis
+    }
+
+    /// Whether the duration is zero
+    pub fn is_zero(&self) -> bool {
+        self.millis == 0
+    }
+
+    /// Parse a shorthand or ISO-8601 duration
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("duration is empty".to_string());
+        }
+        if value.starts_with(['P', 'p']) {
+            parse_iso8601(value)
+        } else {
+            parse_shorthand(value)
+        }
+    }
+
+    /// Normalized spelling of a duration string, or the string unchanged when
+    /// it does not parse
+    pub fn normalize(value: &str) -> String {
+        Self::parse(value)
+            .map(|d| d.to_string())
+            .unwrap_or_else(|_| value.to_string())
+    }
+}
+
+impl FromStr for Duration {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
+}
+
+/// Formats in the largest shorthand unit up to hours that divides the
+/// duration exactly, e.g. `"90m"`, `"48h"` or `"1500ms"`
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.millis == 0 {
+            return write!(f, "0s");
+        }
+        let (unit, size) = SHORTHAND_UNITS[..4]
+            .iter()
+            .rev()
+            .find(|(_, size)| self.millis % size == 0)
+            .copied()
+            .unwrap_or(("ms", 1));
+        write!(f, "{}{}", self.millis / size, unit)
+    }
+}
+
+/// Parse `"1h30m"`-style durations: one or more numbers, each with a unit
+fn parse_shorthand(value: &str) -> Result<Duration, String> {
+    let mut millis = 0.0;
+    let mut rest = value;
+
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if number_len == 0 {
+            return Err(format!("expected a number at '{}'", rest));
+        }
+        let number: f64 = rest[..number_len]
+            .parse()
+            .map_err(|_| format!("invalid number '{}'", &rest[..number_len]))?;
+        rest = &rest[number_len..];
+
+        let (unit, size) = SHORTHAND_UNITS
+            .iter()
+            .find(|(unit, _)| rest.starts_with(unit))
+            .copied()
+            .ok_or_else(|| {
+                if rest.is_empty() {
+                    format!("'{}' is missing a unit (ms, s, m, h or d)", value)
+                } else {
+                    format!("unknown unit at '{}', expected ms, s, m, h or d", rest)
+                }
+            })?;
+        rest = &rest[unit.len()..];
+        millis += number * size as f64;
+    }
+
+    Ok(Duration::from_millis(millis.round() as u64))
+}
+
+/// Parse `PnW`, `PnD` and `PnDTnHnMnS` durations; years and months are
+/// rejected because their length varies
+fn parse_iso8601(value: &str) -> Result<Duration, String> {
+    let upper = value.to_ascii_uppercase();
+    let body = &upper[1..];
+    let (date, time) = match body.split_once('T') {
+        Some((_, "")) => return Err(format!("'{}' has no time components after 'T'", value)),
+        Some((date, time)) => (date, Some(time)),
+        None => (body, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return Err(format!("'{}' has no components", value));
+    }
+
+    let mut millis = 0.0;
+    for (part, units, is_date) in [
+        (date, &ISO_DATE_UNITS[..], true),
+        (time.unwrap_or(""), &ISO_TIME_UNITS[..], false),
+    ] {
+        let mut rest = part;
+        let mut allowed = units;
+        while !rest.is_empty() {
+            let number_len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+                .unwrap_or(rest.len());
+            let Some(designator) = rest[number_len..].chars().next().filter(|_| number_len > 0)
+            else {
+                return Err(format!("invalid ISO-8601 duration '{}'", value));
+            };
+            let position = allowed
+                .iter()
+                .position(|(unit, _)| *unit == designator)
+                .ok_or_else(|| match designator {
+                    'Y' | 'M' if is_date => format!(
+                        "'{}' uses years or months, which have no fixed length",
+                        value
+                    ),
+                    _ => format!(
+                        "unexpected '{}' in ISO-8601 duration '{}'",
+                        designator, value
+                    ),
+                })?;
+            let number: f64 = rest[..number_len]
+                .replace(',', ".")
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", &rest[..number_len]))?;
+            millis += number * allowed[position].1 as f64;
+            // Components must appear in order, each at most once
+            allowed = &allowed[position + 1..];
+            rest = &rest[number_len + 1..];
+        }
+    }
+
+    Ok(Duration::from_millis(millis.round() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(value: &str) -> u64 {
+        Duration::parse(value).unwrap().as_millis()
+    }
+
+    #[test]
+    fn test_parse_shorthand() {
+        assert_eq!(millis("500ms"), 500);
+        assert_eq!(millis("30s"), 30 * SECOND);
+        assert_eq!(millis("1m"), MINUTE);
+        assert_eq!(millis("2h"), 2 * HOUR);
+        assert_eq!(millis("1d"), DAY);
+        assert_eq!(millis("1h30m"), 90 * MINUTE);
+        assert_eq!(millis("1.5s"), 1_500);
+
+        assert!(Duration::parse("").is_err());
+        assert!(Duration::parse("30")
+            .unwrap_err()
+            .contains("missing a unit"));
+        assert!(Duration::parse("5 minutes").is_err());
+        assert!(Duration::parse("m").is_err());
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(millis("PT30S"), 30 * SECOND);
+        assert_eq!(millis("PT1M30S"), 90 * SECOND);
+        assert_eq!(millis("P1DT2H"), DAY + 2 * HOUR);
+        assert_eq!(millis("P2W"), 2 * WEEK);
+        assert_eq!(millis("PT0.5S"), 500);
+
+        assert!(Duration::parse("P1M")
+            .unwrap_err()
+            .contains("no fixed length"));
+        assert!(Duration::parse("PT").is_err());
+        assert!(Duration::parse("P").is_err());
+        assert!(Duration::parse("PT1S1M").is_err());
+        assert!(Duration::parse("PT1H1H").is_err());
+    }
+
+    #[test]
+    fn test_display_normalizes() {
+        assert_eq!(Duration::normalize("PT1M"), "1m");
+        assert_eq!(Duration::normalize("1h30m"), "90m");
+        assert_eq!(Duration::normalize("60s"), "1m");
+        assert_eq!(Duration::normalize("1.5s"), "1500ms");
+        assert_eq!(Duration::normalize("P1D"), "24h");
+        assert_eq!(Duration::normalize("0m"), "0s");
+        assert_eq!(Duration::normalize("soon"), "soon");
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Duration::parse("90s").unwrap() > Duration::parse("1m").unwrap());
+        assert_eq!(
+            Duration::parse("PT2H").unwrap(),
+            Duration::parse("120m").unwrap()
+        );
+    }
+}
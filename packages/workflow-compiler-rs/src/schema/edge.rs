@@ -2,6 +2,29 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::schema::TypeDescriptor;
+
+/// Edge type marking an edge that returns control to a Loop node
+pub const LOOP_BACK_EDGE_TYPE: &str = "loop-back";
+
+/// Source handle of the edge a node takes when it fails
+pub const ERROR_HANDLE: &str = "error";
+
+/// Source handle of the edge from the trigger or a phase to the cleanup
+/// branch that runs when the workflow is cancelled
+pub const CANCEL_HANDLE: &str = "cancel";
+
+/// Type of `nodes.<id>.error`, the failure that sent a node down its error
+/// edge
+pub fn failure_details_type() -> TypeDescriptor {
+    TypeDescriptor::object([
+        ("type", TypeDescriptor::String),
+        ("message", TypeDescriptor::String),
+        ("nonRetryable", TypeDescriptor::Boolean),
+        ("details", TypeDescriptor::array(TypeDescriptor::Any)),
+    ])
+}
+
 /// Workflow edge connecting two nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +61,33 @@ impl WorkflowEdge {
         self.label = Some(label.into());
         self
     }
+
+    /// Set the source handle (branch) this edge leaves from
+    pub fn with_source_handle(mut self, handle: impl Into<String>) -> Self {
+        self.source_handle = Some(handle.into());
+        self
+    }
+
+    /// Mark this edge as a loop back-edge
+    pub fn as_loop_back(mut self) -> Self {
+        self.edge_type = Some(LOOP_BACK_EDGE_TYPE.to_string());
+        self
+    }
+
+    /// Whether this edge is explicitly marked as a loop back-edge
+    pub fn is_loop_back(&self) -> bool {
+        self.edge_type.as_deref() == Some(LOOP_BACK_EDGE_TYPE)
+    }
+
+    /// Whether this edge is taken when its source node fails
+    pub fn is_error(&self) -> bool {
+        self.source_handle.as_deref() == Some(ERROR_HANDLE)
+    }
+
+    /// Whether this edge leads to a cleanup branch run on cancellation
+    pub fn is_cancel(&self) -> bool {
+        self.source_handle.as_deref() == Some(CANCEL_HANDLE)
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +112,23 @@ mod tests {
         assert!(json.contains("\"label\":\"on success\""));
     }
 
+    #[test]
+    fn test_edge_with_source_handle() {
+        let edge = WorkflowEdge::new("edge-1", "cond-1", "node-2").with_source_handle("true");
+        let json = serde_json::to_string(&edge).unwrap();
+
+        assert!(json.contains("\"sourceHandle\":\"true\""));
+    }
+
+    #[test]
+    fn test_edge_loop_back() {
+        let edge = WorkflowEdge::new("edge-1", "body-1", "loop-1").as_loop_back();
+        assert!(edge.is_loop_back());
+
+        let json = serde_json::to_string(&edge).unwrap();
+        assert!(json.contains("\"type\":\"loop-back\""));
+    }
+
     #[test]
     fn test_edge_deserialization() {
         let json = r#"{
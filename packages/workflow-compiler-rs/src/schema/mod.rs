@@ -4,15 +4,21 @@
 //! mirroring the TypeScript types in the workflow-builder package.
 
 pub mod components;
+mod duration;
 mod edge;
 mod node;
+mod query;
 mod settings;
+mod types;
 mod variable;
 mod workflow;
 
 pub use components::*;
+pub use duration::*;
 pub use edge::*;
 pub use node::*;
+pub use query::*;
 pub use settings::*;
+pub use types::*;
 pub use variable::*;
 pub use workflow::*;
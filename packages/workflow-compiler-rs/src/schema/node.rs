@@ -1,7 +1,13 @@
 //! Workflow node types and structures.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::schema::{
+    component_port_types, AgentConfig, ApprovalConfig, ChildMode, ChildWorkflowConfig, DataField,
+    MapConfig, ParentClosePolicy, PortTypes, RetryConfig, SignalConfig, TimerConfig, TimerDelay,
+    UpdateConfig, DEFAULT_ITEM_VARIABLE, DEFAULT_MAP_CONCURRENCY, DEFAULT_MAX_TURNS,
+};
 
 /// Node types - exhaustive enum prevents invalid types at compile time
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -12,10 +18,16 @@ pub enum NodeType {
     Agent,
     Conditional,
     Loop,
+    Map,
+    Parallel,
+    Join,
     ChildWorkflow,
     Signal,
+    Update,
     Phase,
     Retry,
+    Timer,
+    Approval,
     StateVariable,
     ApiEndpoint,
     Condition,
@@ -37,10 +49,16 @@ impl std::fmt::Display for NodeType {
             NodeType::Agent => write!(f, "agent"),
             NodeType::Conditional => write!(f, "conditional"),
             NodeType::Loop => write!(f, "loop"),
+            NodeType::Map => write!(f, "map"),
+            NodeType::Parallel => write!(f, "parallel"),
+            NodeType::Join => write!(f, "join"),
             NodeType::ChildWorkflow => write!(f, "child-workflow"),
             NodeType::Signal => write!(f, "signal"),
+            NodeType::Update => write!(f, "update"),
             NodeType::Phase => write!(f, "phase"),
             NodeType::Retry => write!(f, "retry"),
+            NodeType::Timer => write!(f, "timer"),
+            NodeType::Approval => write!(f, "approval"),
             NodeType::StateVariable => write!(f, "state-variable"),
             NodeType::ApiEndpoint => write!(f, "api-endpoint"),
             NodeType::Condition => write!(f, "condition"),
@@ -95,6 +113,56 @@ pub struct RetryPolicy {
     pub max_interval: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub backoff_coefficient: Option<f64>,
+    /// Error types that fail immediately instead of being retried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_retryable_errors: Option<Vec<String>>,
+}
+
+impl RetryPolicy {
+    /// Attempt limit, where `None` means retrying until the activity succeeds
+    pub fn maximum_attempts(&self) -> Option<u32> {
+        match self.strategy {
+            RetryStrategy::None => Some(1),
+            RetryStrategy::KeepTrying => None,
+            RetryStrategy::FailAfterX | RetryStrategy::ExponentialBackoff => self.max_attempts,
+        }
+    }
+
+    /// Interval before the first retry
+    pub fn initial_interval(&self) -> Option<&str> {
+        match self.strategy {
+            RetryStrategy::None => None,
+            RetryStrategy::ExponentialBackoff => {
+                Some(self.initial_interval.as_deref().unwrap_or("1s"))
+            }
+            _ => self.initial_interval.as_deref(),
+        }
+    }
+
+    /// Upper bound on the interval between retries
+    pub fn maximum_interval(&self) -> Option<&str> {
+        match self.strategy {
+            RetryStrategy::None => None,
+            _ => self.max_interval.as_deref(),
+        }
+    }
+
+    /// Factor by which the interval grows after each retry
+    pub fn backoff_coefficient(&self) -> Option<f64> {
+        match self.strategy {
+            RetryStrategy::None => None,
+            RetryStrategy::ExponentialBackoff => Some(self.backoff_coefficient.unwrap_or(2.0)),
+            _ => self.backoff_coefficient,
+        }
+    }
+
+    /// Error types that are not retried
+    pub fn non_retryable_errors(&self) -> &[String] {
+        match self.strategy {
+            RetryStrategy::None => &[],
+            _ => self.non_retryable_errors.as_deref().unwrap_or_default(),
+        }
+    }
 }
 
 /// Node data - all possible configuration fields
@@ -116,6 +184,9 @@ pub struct NodeData {
     pub timeout: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry_policy: Option<RetryPolicy>,
+    /// Activity that undoes this activity when a later step fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compensation_activity: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub condition: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -168,6 +239,87 @@ pub struct NodeData {
     pub value_expression: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub static_value: Option<serde_json::Value>,
+
+    // Loop component fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loop_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_variable: Option<String>,
+
+    // Map component fields (collection and itemVariable as for loops)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<u32>,
+
+    // Parallel component fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_mode: Option<String>,
+
+    // Signal component fields (waitForSignal also applies to Update nodes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_for_signal: Option<bool>,
+
+    // Update component fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_fields: Option<Vec<DataField>>,
+
+    // Child workflow component fields (workflowId names the child definition)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_workflow_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_queue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_close_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_mode: Option<String>,
+
+    // Timer component fields: exactly one of duration, durationExpression
+    // and until (signalName names a signal that ends the wait early)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_expression: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+
+    // Approval component fields (signalName defaults to the node ID and
+    // timeout bounds the wait for a decision)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approvers: Option<Vec<String>>,
+
+    // Agent component fields (tools name the activities the model may call)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Vec<DataField>>,
+
+    // DataIn/DataOut component fields (also the payload schema of Signal
+    // nodes and the argument schema of Update nodes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<DataField>>,
+
+    // Input mapping: argument name -> value template with `{{...}}` references
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_mapping: Option<BTreeMap<String, String>>,
 }
 
 /// Workflow node with strict typing
@@ -178,6 +330,198 @@ pub struct WorkflowNode {
     pub node_type: NodeType,
     pub data: NodeData,
     pub position: Position,
+    /// Phase node containing this node, from React Flow's `parentId`
+    #[serde(
+        default,
+        rename = "parentId",
+        alias = "parentNode",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_id: Option<String>,
+}
+
+impl WorkflowNode {
+    /// Whether this node is a Log activity
+    pub fn is_log(&self) -> bool {
+        self.node_type == NodeType::Activity
+            && (self.data.component_name.as_deref() == Some("Log")
+                || self.data.activity_name.as_deref() == Some("log"))
+    }
+
+    /// Whether this node reads a variable through the GetVariable component
+    pub fn is_get_variable(&self) -> bool {
+        self.node_type == NodeType::StateVariable
+            && matches!(
+                self.data.component_name.as_deref(),
+                Some("GetVariable") | Some("get-variable")
+            )
+    }
+
+    /// Input and output types of the node's component, when it is a built-in one
+    pub fn port_types(&self) -> Option<PortTypes> {
+        if self.is_log() {
+            return component_port_types("Log");
+        }
+        self.data
+            .component_name
+            .as_deref()
+            .and_then(component_port_types)
+    }
+
+    /// Whether this node produces a result that later nodes can reference
+    pub fn produces_result(&self) -> bool {
+        (self.node_type == NodeType::Activity && !self.is_log())
+            || self.node_type == NodeType::Signal
+            || self.node_type == NodeType::Update
+            || self.node_type == NodeType::ChildWorkflow
+            || self.node_type == NodeType::Timer
+            || self.node_type == NodeType::Map
+            || self.node_type == NodeType::Approval
+            || self.node_type == NodeType::Agent
+            || self.is_get_variable()
+    }
+
+    /// Whether this node can route its failures to an `error` edge
+    pub fn handles_errors(&self) -> bool {
+        (self.node_type == NodeType::Activity && !self.is_log())
+            || self.node_type == NodeType::ChildWorkflow
+            || self.node_type == NodeType::Agent
+    }
+
+    /// Signal configuration of a Signal node
+    pub fn signal_config(&self) -> Option<SignalConfig> {
+        if self.node_type != NodeType::Signal {
+            return None;
+        }
+        let name = self.data.signal_name.clone()?;
+        Some(SignalConfig {
+            name,
+            payload: self.data.fields.clone().unwrap_or_default(),
+            wait: self.data.wait_for_signal.unwrap_or(false),
+            timeout: self.data.timeout.clone(),
+            state_variable: self.data.variable_name.clone(),
+        })
+    }
+
+    /// Child workflow configuration of a Child Workflow node
+    pub fn child_workflow_config(&self) -> Option<ChildWorkflowConfig> {
+        if self.node_type != NodeType::ChildWorkflow {
+            return None;
+        }
+        let workflow_type = self.data.workflow_id.clone()?;
+        Some(ChildWorkflowConfig {
+            workflow_type,
+            workflow_id: self.data.child_workflow_id.clone(),
+            task_queue: self.data.task_queue.clone(),
+            parent_close_policy: self
+                .data
+                .parent_close_policy
+                .as_deref()
+                .and_then(ParentClosePolicy::parse),
+            execution_timeout: self.data.timeout.clone(),
+            run_timeout: self.data.run_timeout.clone(),
+            mode: self
+                .data
+                .child_mode
+                .as_deref()
+                .and_then(ChildMode::parse)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Retry configuration of a Retry node
+    pub fn retry_config(&self) -> Option<RetryConfig> {
+        if self.node_type != NodeType::Retry {
+            return None;
+        }
+        self.data.retry_policy.as_ref().map(RetryConfig::from)
+    }
+
+    /// Timer configuration of a Timer node that sets exactly one delay
+    pub fn timer_config(&self) -> Option<TimerConfig> {
+        if self.node_type != NodeType::Timer {
+            return None;
+        }
+        let data = &self.data;
+        let delay = match (&data.duration, &data.duration_expression, &data.until) {
+            (Some(duration), None, None) => TimerDelay::Duration(duration.clone()),
+            (None, Some(expression), None) => TimerDelay::DurationExpression(expression.clone()),
+            (None, None, Some(until)) => TimerDelay::until(until),
+            _ => return None,
+        };
+        Some(TimerConfig {
+            delay,
+            signal: data.signal_name.clone(),
+        })
+    }
+
+    /// Map configuration of a Map node
+    pub fn map_config(&self) -> Option<MapConfig> {
+        if self.node_type != NodeType::Map {
+            return None;
+        }
+        let collection = self.data.collection.clone()?;
+        Some(MapConfig {
+            collection,
+            item_variable: self
+                .data
+                .item_variable
+                .clone()
+                .unwrap_or_else(|| DEFAULT_ITEM_VARIABLE.to_string()),
+            max_concurrency: self.data.max_concurrency.unwrap_or(DEFAULT_MAP_CONCURRENCY),
+            max_items: self.data.max_items,
+        })
+    }
+
+    /// Approval configuration of an Approval node with at least one approver
+    pub fn approval_config(&self) -> Option<ApprovalConfig> {
+        if self.node_type != NodeType::Approval {
+            return None;
+        }
+        let approvers = self.data.approvers.clone().filter(|a| !a.is_empty())?;
+        Some(ApprovalConfig {
+            signal: self
+                .data
+                .signal_name
+                .clone()
+                .unwrap_or_else(|| self.id.clone()),
+            approvers,
+            timeout: self.data.timeout.clone(),
+        })
+    }
+
+    /// Agent configuration of an Agent node
+    pub fn agent_config(&self) -> Option<AgentConfig> {
+        if self.node_type != NodeType::Agent {
+            return None;
+        }
+        let model = self.data.model.clone()?;
+        Some(AgentConfig {
+            model,
+            system_prompt: self.data.system_prompt.clone(),
+            tools: self.data.tools.clone().unwrap_or_default(),
+            max_turns: self.data.max_turns.unwrap_or(DEFAULT_MAX_TURNS),
+            output_schema: self.data.output_schema.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Update configuration of an Update node
+    pub fn update_config(&self) -> Option<UpdateConfig> {
+        if self.node_type != NodeType::Update {
+            return None;
+        }
+        let name = self.data.update_name.clone()?;
+        Some(UpdateConfig {
+            name,
+            argument: self.data.fields.clone().unwrap_or_default(),
+            returns: self.data.return_fields.clone().unwrap_or_default(),
+            validator: self.data.validator.clone(),
+            return_value: self.data.return_value.clone(),
+            wait: self.data.wait_for_signal.unwrap_or(false),
+            timeout: self.data.timeout.clone(),
+            state_variable: self.data.variable_name.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -215,6 +559,7 @@ mod tests {
                 ..Default::default()
             },
             position: Position { x: 100.0, y: 200.0 },
+            parent_id: None,
         };
 
         let json = serde_json::to_string(&node).unwrap();
@@ -233,10 +578,35 @@ mod tests {
             initial_interval: Some("1s".to_string()),
             max_interval: Some("60s".to_string()),
             backoff_coefficient: Some(2.0),
+            non_retryable_errors: Some(vec!["CardDeclined".to_string()]),
         };
 
         let json = serde_json::to_string(&policy).unwrap();
         assert!(json.contains("\"strategy\":\"exponential-backoff\""));
         assert!(json.contains("\"maxAttempts\":5"));
+        assert!(json.contains("\"nonRetryableErrors\":[\"CardDeclined\"]"));
+    }
+
+    #[test]
+    fn test_retry_policy_attempts_and_intervals() {
+        let policy = |strategy| RetryPolicy {
+            strategy,
+            max_attempts: Some(4),
+            ..Default::default()
+        };
+
+        assert_eq!(policy(RetryStrategy::None).maximum_attempts(), Some(1));
+        assert_eq!(policy(RetryStrategy::KeepTrying).maximum_attempts(), None);
+        assert_eq!(
+            policy(RetryStrategy::FailAfterX).maximum_attempts(),
+            Some(4)
+        );
+        assert_eq!(policy(RetryStrategy::FailAfterX).initial_interval(), None);
+
+        let backoff = policy(RetryStrategy::ExponentialBackoff);
+        assert_eq!(backoff.maximum_attempts(), Some(4));
+        assert_eq!(backoff.initial_interval(), Some("1s"));
+        assert_eq!(backoff.backoff_coefficient(), Some(2.0));
+        assert_eq!(backoff.maximum_interval(), None);
     }
 }
@@ -0,0 +1,98 @@
+//! Workflow query definitions.
+//!
+//! Queries let operators read a running workflow's state without going
+//! through its event history. Each query is generated as a typed
+//! `defineQuery` with a handler that reads the workflow's in-memory state.
+
+use serde::{Deserialize, Serialize};
+
+use super::is_valid_handler_name;
+
+/// A query exposed by the workflow
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowQuery {
+    /// Query name, as sent by clients
+    pub name: String,
+
+    /// What the query returns
+    #[serde(flatten)]
+    pub source: QuerySource,
+
+    /// Description emitted as a doc comment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// What a query returns
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "source", rename_all = "kebab-case")]
+pub enum QuerySource {
+    /// Workflow variables; an empty list exposes all of them
+    Variables {
+        #[serde(default)]
+        variables: Vec<String>,
+    },
+    /// Label of the phase the workflow is in
+    CurrentPhase,
+    /// Steps completed so far and the step being executed
+    Progress,
+}
+
+impl WorkflowQuery {
+    /// Create a query returning the given source
+    pub fn new(name: impl Into<String>, source: QuerySource) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            description: None,
+        }
+    }
+
+    /// Set the query description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Whether the name can be used to derive a TypeScript identifier
+    pub fn has_valid_name(&self) -> bool {
+        is_valid_handler_name(&self.name)
+    }
+
+    /// Whether the description can be emitted in a doc comment without
+    /// ending it
+    pub fn has_valid_description(&self) -> bool {
+        !self
+            .description
+            .as_deref()
+            .is_some_and(|d| d.contains("*/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_deserialization() {
+        let queries: Vec<WorkflowQuery> = serde_json::from_value(serde_json::json!([
+            { "name": "getStatus", "source": "variables", "variables": ["status"] },
+            { "name": "phase", "source": "current-phase" },
+            { "name": "progress", "source": "progress", "description": "Steps done" }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            queries[0].source,
+            QuerySource::Variables {
+                variables: vec!["status".to_string()]
+            }
+        );
+        assert_eq!(queries[1].source, QuerySource::CurrentPhase);
+        assert_eq!(
+            queries[2],
+            WorkflowQuery::new("progress", QuerySource::Progress).with_description("Steps done")
+        );
+    }
+}
@@ -3,7 +3,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::RetryPolicy;
+use super::{RetryPolicy, WorkflowQuery};
 
 /// Workflow metadata
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -17,6 +17,12 @@ pub struct WorkflowMetadata {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub task_queue: Option<String>,
+    /// Run compensations concurrently instead of undoing steps newest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_compensation: Option<bool>,
+    /// Queries exposing workflow state to operators
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queries: Vec<WorkflowQuery>,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
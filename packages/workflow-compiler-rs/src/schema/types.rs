@@ -0,0 +1,185 @@
+//! Type descriptors for component inputs and outputs.
+//!
+//! Descriptors are a small structural type system used to check, before code
+//! generation, that the values flowing between nodes have compatible shapes.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::VariableType;
+
+/// Structural type of a value passed between nodes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
+#[serde(tag = "kind", content = "of", rename_all = "lowercase")]
+pub enum TypeDescriptor {
+    /// Type not known statically; compatible with everything
+    #[default]
+    Any,
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array(Box<TypeDescriptor>),
+    /// Object with known fields; an empty map is an arbitrary record
+    Object(BTreeMap<String, TypeDescriptor>),
+}
+
+impl TypeDescriptor {
+    /// Array of `item`
+    pub fn array(item: TypeDescriptor) -> Self {
+        TypeDescriptor::Array(Box::new(item))
+    }
+
+    /// Object with the given fields
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, TypeDescriptor)>) -> Self {
+        TypeDescriptor::Object(
+            fields
+                .into_iter()
+                .map(|(name, ty)| (name.to_string(), ty))
+                .collect(),
+        )
+    }
+
+    /// Whether a value of this type can be used where `expected` is required
+    pub fn is_assignable_to(&self, expected: &TypeDescriptor) -> bool {
+        match (self, expected) {
+            (TypeDescriptor::Any, _) | (_, TypeDescriptor::Any) => true,
+            (TypeDescriptor::Array(found), TypeDescriptor::Array(expected)) => {
+                found.is_assignable_to(expected)
+            }
+            (TypeDescriptor::Object(found), TypeDescriptor::Object(expected)) => {
+                expected.iter().all(|(name, expected)| {
+                    found
+                        .get(name)
+                        .map_or(true, |found| found.is_assignable_to(expected))
+                })
+            }
+            (found, expected) => found == expected,
+        }
+    }
+
+    /// Type of the field `name`, if this is an object that declares it
+    pub fn field(&self, name: &str) -> Option<&TypeDescriptor> {
+        match self {
+            TypeDescriptor::Object(fields) => fields.get(name),
+            _ => None,
+        }
+    }
+
+    /// Common type of two values, falling back to `Any` when they differ
+    pub fn unify(&self, other: &TypeDescriptor) -> TypeDescriptor {
+        match (self, other) {
+            (a, b) if a == b => a.clone(),
+            (TypeDescriptor::Null, other) | (other, TypeDescriptor::Null) => other.clone(),
+            _ => TypeDescriptor::Any,
+        }
+    }
+
+    /// Whether this is a primitive type (not `Any`, an array or an object)
+    pub fn is_primitive(&self) -> bool {
+        matches!(
+            self,
+            TypeDescriptor::Null
+                | TypeDescriptor::Boolean
+                | TypeDescriptor::Number
+                | TypeDescriptor::String
+        )
+    }
+}
+
+impl From<&VariableType> for TypeDescriptor {
+    fn from(variable_type: &VariableType) -> Self {
+        match variable_type {
+            VariableType::String => TypeDescriptor::String,
+            VariableType::Number => TypeDescriptor::Number,
+            VariableType::Boolean => TypeDescriptor::Boolean,
+            VariableType::Object => TypeDescriptor::Object(BTreeMap::new()),
+            VariableType::Array => TypeDescriptor::array(TypeDescriptor::Any),
+            VariableType::Any => TypeDescriptor::Any,
+        }
+    }
+}
+
+/// Displays the descriptor in TypeScript syntax
+impl std::fmt::Display for TypeDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeDescriptor::Any => write!(f, "unknown"),
+            TypeDescriptor::Null => write!(f, "null"),
+            TypeDescriptor::Boolean => write!(f, "boolean"),
+            TypeDescriptor::Number => write!(f, "number"),
+            TypeDescriptor::String => write!(f, "string"),
+            TypeDescriptor::Array(item) => write!(f, "{}[]", item),
+            TypeDescriptor::Object(fields) if fields.is_empty() => {
+                write!(f, "Record<string, unknown>")
+            }
+            TypeDescriptor::Object(fields) => {
+                write!(f, "{{ ")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}: {}", name, ty)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+/// Component data structures with a known type descriptor
+pub trait Typed {
+    fn type_descriptor() -> TypeDescriptor;
+}
+
+/// Input and output types of a component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PortTypes {
+    pub input: TypeDescriptor,
+    pub output: TypeDescriptor,
+}
+
+impl PortTypes {
+    /// Port types of a component taking `I` and producing `O`
+    pub fn of<I: Typed, O: Typed>() -> Self {
+        Self {
+            input: I::type_descriptor(),
+            output: O::type_descriptor(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let ty = TypeDescriptor::object([
+            ("ids", TypeDescriptor::array(TypeDescriptor::Number)),
+            ("name", TypeDescriptor::String),
+        ]);
+        assert_eq!(ty.to_string(), "{ ids: number[]; name: string }");
+        assert_eq!(
+            TypeDescriptor::from(&VariableType::Object).to_string(),
+            "Record<string, unknown>"
+        );
+    }
+
+    #[test]
+    fn test_assignability() {
+        let expected = TypeDescriptor::object([("count", TypeDescriptor::Number)]);
+        let found = TypeDescriptor::object([
+            ("count", TypeDescriptor::Number),
+            ("extra", TypeDescriptor::String),
+        ]);
+        assert!(found.is_assignable_to(&expected));
+        assert!(TypeDescriptor::Any.is_assignable_to(&TypeDescriptor::String));
+        assert!(!TypeDescriptor::Number.is_assignable_to(&TypeDescriptor::String));
+
+        let wrong = TypeDescriptor::object([("count", TypeDescriptor::String)]);
+        assert!(!wrong.is_assignable_to(&expected));
+        assert!(!TypeDescriptor::array(TypeDescriptor::String)
+            .is_assignable_to(&TypeDescriptor::array(TypeDescriptor::Number)));
+    }
+}
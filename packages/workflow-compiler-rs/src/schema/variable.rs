@@ -16,6 +16,19 @@ pub enum VariableType {
     Any,
 }
 
+impl VariableType {
+    /// Convert to TypeScript type string
+    pub fn to_typescript_type(&self) -> &'static str {
+        match self {
+            VariableType::String => "string",
+            VariableType::Number => "number",
+            VariableType::Boolean => "boolean",
+            VariableType::Object => "Record<string, unknown>",
+            VariableType::Array => "unknown[]",
+            VariableType::Any => "unknown",
+        }
+    }
+}
 
 impl std::fmt::Display for VariableType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -71,14 +84,7 @@ impl WorkflowVariable {
 
     /// Convert variable type to TypeScript type string
     pub fn to_typescript_type(&self) -> &'static str {
-        match self.variable_type {
-            VariableType::String => "string",
-            VariableType::Number => "number",
-            VariableType::Boolean => "boolean",
-            VariableType::Object => "Record<string, unknown>",
-            VariableType::Array => "unknown[]",
-            VariableType::Any => "unknown",
-        }
+        self.variable_type.to_typescript_type()
     }
 }
 
@@ -64,6 +64,40 @@ impl WorkflowDefinition {
         self.edges.iter().filter(|e| e.target == node_id).collect()
     }
 
+    /// Phases enclosing a node, innermost first.
+    ///
+    /// Stops at the first parent that is missing or already visited, so a
+    /// cyclic nesting still terminates.
+    pub fn enclosing_phases(&self, node_id: &str) -> Vec<&WorkflowNode> {
+        let mut phases: Vec<&WorkflowNode> = Vec::new();
+        let mut current = self.get_node(node_id);
+        while let Some(parent) = current
+            .and_then(|n| n.parent_id.as_deref())
+            .and_then(|id| self.get_node(id))
+        {
+            if parent.id == node_id || phases.iter().any(|p| p.id == parent.id) {
+                break;
+            }
+            phases.push(parent);
+            current = Some(parent);
+        }
+        phases
+    }
+
+    /// Whether a node is inside a phase, directly or through nested phases
+    pub fn is_in_phase(&self, node_id: &str, phase_id: &str) -> bool {
+        self.enclosing_phases(node_id)
+            .iter()
+            .any(|phase| phase.id == phase_id)
+    }
+
+    /// Whether any node is placed inside the given phase
+    pub fn has_members(&self, phase_id: &str) -> bool {
+        self.nodes
+            .iter()
+            .any(|n| n.parent_id.as_deref() == Some(phase_id))
+    }
+
     /// Check if the workflow has any nodes
     pub fn is_empty(&self) -> bool {
         self.nodes.is_empty()
@@ -100,6 +134,7 @@ mod tests {
                 ..Default::default()
             },
             position: Position { x: 0.0, y: 0.0 },
+            parent_id: None,
         };
 
         let activity = WorkflowNode {
@@ -110,6 +145,7 @@ mod tests {
                 ..Default::default()
             },
             position: Position { x: 100.0, y: 0.0 },
+            parent_id: None,
         };
 
         let end = WorkflowNode {
@@ -120,6 +156,7 @@ mod tests {
                 ..Default::default()
             },
             position: Position { x: 200.0, y: 0.0 },
+            parent_id: None,
         };
 
         WorkflowDefinition {
@@ -204,4 +241,35 @@ mod tests {
         assert_eq!(incoming.len(), 1);
         assert_eq!(incoming[0].source, "activity-1");
     }
+
+    #[test]
+    fn test_enclosing_phases() {
+        let json = r#"{
+            "nodes": [
+                { "id": "outer", "type": "phase", "data": { "label": "Outer" },
+                  "position": { "x": 0, "y": 0 } },
+                { "id": "inner", "type": "phase", "data": { "label": "Inner" },
+                  "position": { "x": 0, "y": 0 }, "parentId": "outer" },
+                { "id": "step", "type": "activity", "data": { "label": "Step" },
+                  "position": { "x": 0, "y": 0 }, "parentNode": "inner" }
+            ],
+            "edges": []
+        }"#;
+        let mut workflow: WorkflowDefinition = serde_json::from_str(json).unwrap();
+
+        let ids = |w: &WorkflowDefinition| -> Vec<String> {
+            w.enclosing_phases("step")
+                .iter()
+                .map(|p| p.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&workflow), vec!["inner", "outer"]);
+        assert!(workflow.is_in_phase("step", "outer"));
+        assert!(!workflow.is_in_phase("outer", "inner"));
+        assert!(workflow.has_members("inner"));
+
+        // Cyclic nesting terminates
+        workflow.nodes[0].parent_id = Some("inner".to_string());
+        assert_eq!(ids(&workflow), vec!["inner", "outer"]);
+    }
 }
@@ -3,6 +3,8 @@
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::expression::Span;
+
 /// Validation error types
 #[derive(Debug, Error, Clone, Serialize)]
 #[serde(tag = "type", content = "details")]
@@ -48,6 +50,45 @@ pub enum ValidationError {
 
     #[error("Unreachable nodes from start: {0:?}")]
     UnreachableNodes(Vec<String>),
+
+    #[error("Conditional node '{node_id}' has outgoing edge '{edge_id}' without a branch handle.")]
+    MissingBranchHandle { node_id: String, edge_id: String },
+
+    #[error("Conditional node '{node_id}' has more than one edge for branch '{handle}'.")]
+    DuplicateBranchHandle { node_id: String, handle: String },
+
+    #[error("Conditional node '{node_id}' is missing its '{handle}' branch.")]
+    MissingBranch { node_id: String, handle: String },
+
+    #[error("Node '{node_id}' references unknown node '{reference}'.")]
+    UnknownNodeReference { node_id: String, reference: String },
+
+    #[error("Node '{node_id}' references node '{reference}', which does not run before it on every path.")]
+    NodeReferenceNotAvailable { node_id: String, reference: String },
+
+    #[error("Node '{node_id}' has an invalid expression in {field} at {span}: {message}")]
+    InvalidExpression {
+        node_id: String,
+        field: String,
+        message: String,
+        span: Span,
+    },
+
+    #[error("Query '{name}' is invalid: {message}")]
+    InvalidQuery { name: String, message: String },
+
+    #[error("Retry policy of {scope} is invalid: {message}")]
+    InvalidRetryPolicy { scope: String, message: String },
+
+    #[error("Workflow {field} is invalid: {message}")]
+    InvalidMetadata { field: String, message: String },
+
+    #[error("Node '{node_id}' expects a value of type {expected} but receives {found}.")]
+    TypeMismatch {
+        node_id: String,
+        expected: String,
+        found: String,
+    },
 }
 
 /// Validation warning types (non-fatal issues)
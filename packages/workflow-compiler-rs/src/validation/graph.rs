@@ -1,8 +1,22 @@
 //! Graph structure validation.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use crate::schema::{NodeType, WorkflowDefinition};
+use crate::analysis::{
+    parse_template, template_references, ControlFlowGraph, DataReference, TypeEnvironment,
+};
+use crate::codegen::{activity_method, is_identifier, result_var};
+use crate::expression::{
+    check_scope, node_expressions, parse, ExprKind, ExpressionError, ExpressionKind,
+    ROOT_IDENTIFIERS,
+};
+use crate::schema::{
+    is_valid_handler_name, parse_timestamp, ChildMode, DataField, Duration, LoopMode, NodeType,
+    ParallelMode, ParentClosePolicy, QuerySource, RetryPolicy, RetryStrategy, TimerDelay,
+    TypeDescriptor, WorkflowDefinition, WorkflowEdge, WorkflowNode, APPROVAL_OUTCOMES,
+    CALL_MODEL_ACTIVITY, CANCEL_HANDLE, DEFAULT_ITEM_VARIABLE, DEFAULT_MAX_LOOP_ITERATIONS,
+    ERROR_HANDLE, PENDING_APPROVALS_QUERY, TIMED_OUT_HANDLE, UPDATE_ARGUMENT,
+};
 
 use super::{ValidationError, ValidationWarning};
 
@@ -18,12 +32,19 @@ pub fn validate_graph_structure(
         return;
     }
 
-    // Check for duplicate node IDs
+    // Check for duplicate node IDs, and IDs that would end the comments
+    // naming their nodes in the generated code
     let mut seen_node_ids = HashSet::new();
     for node in &workflow.nodes {
         if !seen_node_ids.insert(&node.id) {
             errors.push(ValidationError::DuplicateNodeId(node.id.clone()));
         }
+        if node.id.contains(['\n', '\r', '\u{2028}', '\u{2029}']) {
+            errors.push(ValidationError::InvalidConfig {
+                node_id: node.id.clone(),
+                message: "node id must not contain line breaks".to_string(),
+            });
+        }
     }
 
     // Check for duplicate edge IDs
@@ -34,143 +55,1992 @@ pub fn validate_graph_structure(
         }
     }
 
-    // Find trigger nodes (start nodes)
-    let trigger_nodes: Vec<_> = workflow
+    // Find trigger nodes (start nodes)
+    let trigger_nodes: Vec<_> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Trigger)
+        .collect();
+
+    if trigger_nodes.is_empty() {
+        errors.push(ValidationError::NoStartNode);
+    } else if trigger_nodes.len() > 1 {
+        let ids: Vec<_> = trigger_nodes.iter().map(|n| n.id.clone()).collect();
+        errors.push(ValidationError::MultipleStartNodes(ids));
+    }
+
+    // Find end nodes
+    let end_nodes: Vec<_> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::End)
+        .collect();
+
+    if end_nodes.is_empty() {
+        errors.push(ValidationError::NoEndNode);
+    }
+
+    // Check for unreachable nodes
+    if !trigger_nodes.is_empty() && errors.is_empty() {
+        check_reachability(workflow, &trigger_nodes[0].id, errors);
+    }
+
+    // Check for cycles
+    check_cycles(workflow, errors);
+}
+
+/// Validate individual nodes
+pub fn validate_nodes(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let node_ids: HashSet<_> = workflow.nodes.iter().map(|n| &n.id).collect();
+    let outgoing: HashMap<_, Vec<_>> = workflow.nodes.iter().map(|n| {
+        let edges: Vec<_> = workflow.edges.iter().filter(|e| e.source == n.id).collect();
+        (&n.id, edges)
+    }).collect();
+
+    for node in &workflow.nodes {
+        // Check required fields based on node type
+        match node.node_type {
+            NodeType::Activity
+                if node.data.component_id.is_none() && node.data.activity_name.is_none() =>
+            {
+                warnings.push(ValidationWarning::MissingOptionalField {
+                    node_id: node.id.clone(),
+                    field: "componentId or activityName".to_string(),
+                });
+            }
+            NodeType::Signal if node.data.signal_name.is_none() => {
+                errors.push(ValidationError::MissingRequiredField {
+                    node_id: node.id.clone(),
+                    field: "signalName".to_string(),
+                });
+            }
+            NodeType::Update if node.data.update_name.is_none() => {
+                errors.push(ValidationError::MissingRequiredField {
+                    node_id: node.id.clone(),
+                    field: "updateName".to_string(),
+                });
+            }
+            NodeType::Timer
+                if node.data.duration.is_none()
+                    && node.data.duration_expression.is_none()
+                    && node.data.until.is_none() =>
+            {
+                errors.push(ValidationError::MissingRequiredField {
+                    node_id: node.id.clone(),
+                    field: "duration, durationExpression or until".to_string(),
+                });
+            }
+            NodeType::Approval if node.data.approvers.as_ref().map_or(true, |a| a.is_empty()) => {
+                errors.push(ValidationError::MissingRequiredField {
+                    node_id: node.id.clone(),
+                    field: "approvers".to_string(),
+                });
+            }
+            NodeType::Agent if node.data.model.is_none() => {
+                errors.push(ValidationError::MissingRequiredField {
+                    node_id: node.id.clone(),
+                    field: "model".to_string(),
+                });
+            }
+            NodeType::ChildWorkflow if node.data.workflow_id.is_none() => {
+                errors.push(ValidationError::MissingRequiredField {
+                    node_id: node.id.clone(),
+                    field: "workflowId".to_string(),
+                });
+            }
+            NodeType::Conditional | NodeType::Condition if node.data.condition.is_none() => {
+                warnings.push(ValidationWarning::MissingOptionalField {
+                    node_id: node.id.clone(),
+                    field: "condition".to_string(),
+                });
+            }
+            NodeType::Loop => validate_loop(node, errors, warnings),
+            NodeType::Retry => validate_retry(node, &outgoing[&node.id], errors, warnings),
+            NodeType::Map => validate_map(node, &outgoing[&node.id], errors),
+            _ => {}
+        }
+
+        // Check for dead ends (non-end nodes with no outgoing edges)
+        if node.node_type != NodeType::End {
+            if let Some(edges) = outgoing.get(&node.id) {
+                if edges.is_empty() {
+                    warnings.push(ValidationWarning::DeadEnd {
+                        node_id: node.id.clone(),
+                    });
+                }
+            }
+        }
+
+        // Check variable references in config
+        if let Some(config) = &node.data.config {
+            for (key, value) in config {
+                if let Some(var_ref) = extract_variable_reference(value) {
+                    // Check if variable exists
+                    let var_exists = workflow.variables.iter().any(|v| v.name == var_ref);
+                    if !var_exists && !node_ids.contains(&var_ref) {
+                        // Could be referencing a node output, not just a variable
+                        // Only warn, don't error
+                        warnings.push(ValidationWarning::ConfigSuggestion {
+                            node_id: node.id.clone(),
+                            message: format!(
+                                "Config key '{}' references '{}' which may not exist",
+                                key, var_ref
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Node results are bound to constants named after the node ID, so IDs
+    // that only differ in punctuation would declare the same constant twice
+    let mut result_names: HashMap<String, &str> = HashMap::new();
+    for node in workflow.nodes.iter().filter(|n| n.produces_result()) {
+        match result_names.get(&result_var(&node.id)) {
+            Some(other) if *other != node.id => {
+                errors.push(ValidationError::InvalidConfig {
+                    node_id: node.id.clone(),
+                    message: format!(
+                        "result name '{}' is also used by node '{}'",
+                        result_var(&node.id),
+                        other
+                    ),
+                });
+            }
+            _ => {
+                result_names.insert(result_var(&node.id), &node.id);
+            }
+        }
+    }
+}
+
+/// Validate edges
+pub fn validate_edges(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let node_ids: HashSet<_> = workflow.nodes.iter().map(|n| n.id.as_str()).collect();
+
+    for edge in &workflow.edges {
+        // Check source node exists
+        if !node_ids.contains(edge.source.as_str()) {
+            errors.push(ValidationError::InvalidEdgeSource(edge.source.clone()));
+        }
+
+        // Check target node exists
+        if !node_ids.contains(edge.target.as_str()) {
+            errors.push(ValidationError::InvalidEdgeTarget(edge.target.clone()));
+        }
+
+        // Loop back-edges may only return to a Loop node
+        if edge.is_loop_back() {
+            if let Some(target) = workflow.get_node(&edge.target) {
+                if target.node_type != NodeType::Loop {
+                    errors.push(ValidationError::InvalidConfig {
+                        node_id: target.id.clone(),
+                        message: format!(
+                            "edge '{}' is a loop back-edge but '{}' is not a loop node",
+                            edge.id, target.id
+                        ),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Validate a Retry node: it needs a retry policy and a single `body` edge
+/// into the region it retries
+fn validate_retry(
+    node: &WorkflowNode,
+    edges: &[&WorkflowEdge],
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    match &node.data.retry_policy {
+        None => errors.push(ValidationError::MissingRequiredField {
+            node_id: node.id.clone(),
+            field: "retryPolicy".to_string(),
+        }),
+        Some(policy) if policy.strategy == RetryStrategy::None => {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: "retry strategy 'none' runs the region once without retrying".to_string(),
+            });
+        }
+        Some(_) => {}
+    }
+
+    let body = edges
+        .iter()
+        .filter(|e| e.source_handle.as_deref() == Some("body"))
+        .count();
+    let problem = match (body, edges.len() - body) {
+        (0, _) => Some("retry node has no 'body' edge into the region it retries"),
+        (1, 0 | 1) => None,
+        (1, _) => Some("retry node has more than one edge leaving the region"),
+        _ => Some("retry node has more than one 'body' edge"),
+    };
+    if let Some(message) = problem {
+        errors.push(ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Validate Map node configuration and the edges around its body
+fn validate_map(node: &WorkflowNode, edges: &[&WorkflowEdge], errors: &mut Vec<ValidationError>) {
+    let invalid = |message: String| ValidationError::InvalidConfig {
+        node_id: node.id.clone(),
+        message,
+    };
+
+    match node.map_config() {
+        None => errors.push(ValidationError::MissingRequiredField {
+            node_id: node.id.clone(),
+            field: "collection".to_string(),
+        }),
+        Some(config) if !config.has_valid_item_variable() => errors.push(invalid(format!(
+            "itemVariable '{}' is not a valid identifier",
+            config.item_variable
+        ))),
+        Some(config) if ROOT_IDENTIFIERS.contains(&config.item_variable.as_str()) => {
+            errors.push(invalid(format!(
+                "itemVariable '{}' would hide the '{}' that expressions read",
+                config.item_variable, config.item_variable
+            )))
+        }
+        Some(_) => {}
+    }
+    for (field, value) in [
+        ("maxConcurrency", node.data.max_concurrency),
+        ("maxItems", node.data.max_items),
+    ] {
+        if value == Some(0) {
+            errors.push(invalid(format!("{} must be greater than zero", field)));
+        }
+    }
+
+    let body = edges
+        .iter()
+        .filter(|e| e.source_handle.as_deref() == Some("body"))
+        .count();
+    let problem = match (body, edges.len() - body) {
+        (0, _) => Some("map node has no 'body' edge into the region it runs for each item"),
+        (1, 0 | 1) => None,
+        (1, _) => Some("map node has more than one edge leaving the region"),
+        _ => Some("map node has more than one 'body' edge"),
+    };
+    if let Some(message) = problem {
+        errors.push(invalid(message.to_string()));
+    }
+}
+
+/// Validate Loop node configuration for its resolved loop mode
+fn validate_loop(
+    node: &WorkflowNode,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let mode = LoopMode::resolve(
+        node.data.loop_type.as_deref(),
+        node.data.condition.is_some(),
+        node.data.collection.is_some(),
+    );
+    let missing = |field: &str| ValidationError::MissingRequiredField {
+        node_id: node.id.clone(),
+        field: field.to_string(),
+    };
+
+    match mode {
+        LoopMode::While if node.data.condition.is_none() => errors.push(missing("condition")),
+        LoopMode::Count if node.data.max_iterations.is_none() => {
+            errors.push(missing("maxIterations"))
+        }
+        LoopMode::ForEach if node.data.collection.is_none() => errors.push(missing("collection")),
+        _ => {}
+    }
+
+    if node.data.max_iterations == Some(0) {
+        errors.push(ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message: "maxIterations must be greater than zero".to_string(),
+        });
+    } else if mode == LoopMode::While && node.data.max_iterations.is_none() {
+        warnings.push(ValidationWarning::ConfigSuggestion {
+            node_id: node.id.clone(),
+            message: format!(
+                "while loop has no maxIterations; it will stop after {} iterations",
+                DEFAULT_MAX_LOOP_ITERATIONS
+            ),
+        });
+    }
+}
+
+/// Validate the outgoing branches of conditional nodes.
+///
+/// Every edge leaving a conditional must name its branch through
+/// `sourceHandle`, each branch may be used only once, and a `true`/`false`
+/// conditional must provide both branches.
+pub fn validate_branches(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    for node in &workflow.nodes {
+        if !matches!(node.node_type, NodeType::Conditional | NodeType::Condition) {
+            continue;
+        }
+
+        let mut handles = HashSet::new();
+        for edge in workflow.get_outgoing_edges(&node.id) {
+            match edge.source_handle.as_deref() {
+                None | Some("") => errors.push(ValidationError::MissingBranchHandle {
+                    node_id: node.id.clone(),
+                    edge_id: edge.id.clone(),
+                }),
+                Some(handle) => {
+                    if !handles.insert(handle) {
+                        errors.push(ValidationError::DuplicateBranchHandle {
+                            node_id: node.id.clone(),
+                            handle: handle.to_string(),
+                        });
+                    }
+                    if handle
+                        .chars()
+                        .any(|c| c.is_control() || matches!(c, '\u{2028}' | '\u{2029}'))
+                    {
+                        errors.push(ValidationError::InvalidConfig {
+                            node_id: node.id.clone(),
+                            message:
+                                "branch handle must not contain line breaks or control characters"
+                                    .to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let is_boolean = !handles.is_empty()
+            && handles.iter().all(|h| matches!(*h, "true" | "false"));
+        if is_boolean {
+            for handle in ["true", "false"] {
+                if !handles.contains(handle) {
+                    errors.push(ValidationError::MissingBranch {
+                        node_id: node.id.clone(),
+                        handle: handle.to_string(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Validate the `error` edges that nodes take when they fail.
+///
+/// Only nodes whose failures can be caught (activities, child workflows and
+/// agents) may have one, at most one each. An error branch that never
+/// reaches an End node lets the workflow finish without a result.
+pub fn validate_error_branches(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    for node in &workflow.nodes {
+        let error_edges: Vec<&WorkflowEdge> = workflow
+            .get_outgoing_edges(&node.id)
+            .into_iter()
+            .filter(|e| e.is_error())
+            .collect();
+        let Some(edge) = error_edges.first() else {
+            continue;
+        };
+
+        if !node.handles_errors() {
+            errors.push(ValidationError::InvalidConfig {
+                node_id: node.id.clone(),
+                message: format!(
+                    "only activity, child workflow and agent nodes can have an '{}' edge",
+                    ERROR_HANDLE
+                ),
+            });
+            continue;
+        }
+        if error_edges.len() > 1 {
+            errors.push(ValidationError::InvalidConfig {
+                node_id: node.id.clone(),
+                message: format!("node has more than one '{}' edge", ERROR_HANDLE),
+            });
+        }
+        if !reaches_end(workflow, &edge.target) {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: format!(
+                    "the '{}' branch never reaches an End node, so the workflow finishes \
+                     without a result when this node fails",
+                    ERROR_HANDLE
+                ),
+            });
+        }
+    }
+}
+
+/// Validate the on-cancel cleanup branches of the trigger and phases.
+///
+/// A cleanup branch runs while the workflow is being cancelled, so it must not
+/// wait for signals, updates or approvals, and it must not lead back into the nodes that
+/// run before cancellation.
+pub fn validate_cancellation(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let cfg = ControlFlowGraph::new(workflow);
+
+    for node in &workflow.nodes {
+        let cancel_edges: Vec<&WorkflowEdge> = workflow
+            .get_outgoing_edges(&node.id)
+            .into_iter()
+            .filter(|e| e.is_cancel())
+            .collect();
+        let Some(edge) = cancel_edges.first() else {
+            continue;
+        };
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        let has_cleanup = node.node_type == NodeType::Trigger
+            || (node.node_type == NodeType::Phase && workflow.has_members(&node.id));
+        if !has_cleanup {
+            errors.push(invalid(format!(
+                "only the trigger and phases containing nodes can have a '{}' edge",
+                CANCEL_HANDLE
+            )));
+            continue;
+        }
+        if cancel_edges.len() > 1 {
+            errors.push(invalid(format!(
+                "node has more than one '{}' edge",
+                CANCEL_HANDLE
+            )));
+        }
+
+        let cleanup = ControlFlowGraph::rooted_at(workflow, &edge.target);
+        let branch: Vec<&WorkflowNode> = cleanup
+            .order()
+            .iter()
+            .filter_map(|id| workflow.get_node(id))
+            .filter(|n| n.node_type != NodeType::End)
+            .collect();
+        if let Some(rejoined) = branch.iter().find(|n| cfg.is_reachable(&n.id)) {
+            errors.push(invalid(format!(
+                "the '{}' branch leads back into the workflow at '{}'",
+                CANCEL_HANDLE, rejoined.id
+            )));
+        }
+        for member in branch {
+            let wait = if let Some(signal) = member.signal_config().filter(|s| s.wait) {
+                format!("signal '{}'", signal.name)
+            } else if let Some(update) = member.update_config().filter(|u| u.wait) {
+                format!("update '{}'", update.name)
+            } else if let Some(approval) = member.approval_config() {
+                format!("approval signal '{}'", approval.signal)
+            } else {
+                continue;
+            };
+            errors.push(ValidationError::InvalidConfig {
+                node_id: member.id.clone(),
+                message: format!(
+                    "waits for {} in the cleanup branch of '{}', which runs after the workflow \
+                     is cancelled",
+                    wait, node.id
+                ),
+            });
+        }
+    }
+}
+
+/// Whether an End node can be reached from `start`
+fn reaches_end(workflow: &WorkflowDefinition, start: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack = vec![start];
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        if workflow
+            .get_node(id)
+            .is_some_and(|n| n.node_type == NodeType::End)
+        {
+            return true;
+        }
+        stack.extend(
+            workflow
+                .get_outgoing_edges(id)
+                .into_iter()
+                .map(|e| e.target.as_str()),
+        );
+    }
+    false
+}
+
+/// Validate Parallel fan-out and Join fan-in nodes.
+///
+/// The branches of a Parallel node run concurrently, so they must all re-join
+/// at a common node before the workflow can finish.
+pub fn validate_parallel(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let cfg = ControlFlowGraph::new(workflow);
+
+    for node in &workflow.nodes {
+        match node.node_type {
+            NodeType::Parallel => {
+                if let Some(mode) = node.data.parallel_mode.as_deref() {
+                    if ParallelMode::parse(mode).is_none() {
+                        errors.push(ValidationError::InvalidConfig {
+                            node_id: node.id.clone(),
+                            message: format!(
+                                "unknown parallelMode '{}', expected 'all' or 'all-settled'",
+                                mode
+                            ),
+                        });
+                    }
+                }
+
+                let branches = workflow.get_outgoing_edges(&node.id).len();
+                if branches < 2 {
+                    warnings.push(ValidationWarning::ConfigSuggestion {
+                        node_id: node.id.clone(),
+                        message: "parallel node has fewer than two branches".to_string(),
+                    });
+                } else if cfg.is_reachable(&node.id)
+                    && cfg.immediate_post_dominator(&node.id).is_none()
+                {
+                    errors.push(ValidationError::InvalidConfig {
+                        node_id: node.id.clone(),
+                        message: "parallel branches never re-join; connect them to a join node"
+                            .to_string(),
+                    });
+                }
+            }
+            NodeType::Join if workflow.get_incoming_edges(&node.id).len() < 2 => {
+                warnings.push(ValidationWarning::ConfigSuggestion {
+                    node_id: node.id.clone(),
+                    message: "join node has fewer than two incoming branches".to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Validate phase membership and nesting.
+///
+/// A node's parent must be a Phase node and phases may not contain
+/// themselves. The nodes inside a phase form a region that is only entered
+/// through the phase node and left through a single node, so the phase can be
+/// generated as one function.
+pub fn validate_phases(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let invalid = |node_id: &str, message: String| ValidationError::InvalidConfig {
+        node_id: node_id.to_string(),
+        message,
+    };
+
+    let error_count = errors.len();
+    for node in &workflow.nodes {
+        let Some(parent_id) = node.parent_id.as_deref() else {
+            continue;
+        };
+        match workflow.get_node(parent_id) {
+            None => errors.push(invalid(
+                &node.id,
+                format!("parent '{}' does not exist", parent_id),
+            )),
+            Some(parent) if parent.node_type != NodeType::Phase => errors.push(invalid(
+                &node.id,
+                format!("parent '{}' is not a phase node", parent_id),
+            )),
+            Some(_) if parent_id == node.id || workflow.is_in_phase(parent_id, &node.id) => {
+                errors.push(invalid(
+                    &node.id,
+                    "phase is nested inside itself".to_string(),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    if errors.len() > error_count {
+        return;
+    }
+
+    let cfg = ControlFlowGraph::new(workflow);
+    for phase in &workflow.nodes {
+        if phase.node_type != NodeType::Phase || !workflow.has_members(&phase.id) {
+            continue;
+        }
+        let inside = |id: &str| workflow.is_in_phase(id, &phase.id);
+
+        for edge in &workflow.edges {
+            if inside(&edge.target) && edge.source != phase.id && !inside(&edge.source) {
+                errors.push(invalid(
+                    &phase.id,
+                    format!(
+                        "edge '{}' enters the phase at '{}' without passing through the phase node",
+                        edge.id, edge.target
+                    ),
+                ));
+            } else if edge.source == phase.id && !inside(&edge.target) && !edge.is_cancel() {
+                errors.push(invalid(
+                    &phase.id,
+                    format!(
+                        "edge '{}' leads from the phase node to '{}', which is outside the phase",
+                        edge.id, edge.target
+                    ),
+                ));
+            }
+        }
+
+        let members: Vec<&WorkflowNode> = workflow.nodes.iter().filter(|n| inside(&n.id)).collect();
+        let mut exits = BTreeSet::new();
+        for member in &members {
+            for edge in cfg.successors(&member.id) {
+                if !inside(&edge.target) {
+                    exits.insert(edge.target.as_str());
+                }
+            }
+        }
+        if exits.len() > 1 {
+            let exits: Vec<&str> = exits.into_iter().collect();
+            errors.push(invalid(
+                &phase.id,
+                format!(
+                    "phase is left through more than one node: {}",
+                    exits.join(", ")
+                ),
+            ));
+        } else if !exits.is_empty() && members.iter().any(|n| n.node_type == NodeType::End) {
+            errors.push(invalid(
+                &phase.id,
+                "phase both ends the workflow and continues after it; \
+                 move its End node out of the phase"
+                    .to_string(),
+            ));
+        }
+    }
+}
+
+/// Validate the input/result field schemas declared by DataIn/DataOut nodes.
+pub fn validate_data_fields(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    for node_type in [NodeType::DataIn, NodeType::DataOut] {
+        let mut seen = HashSet::new();
+        for node in workflow.nodes.iter().filter(|n| n.node_type == node_type) {
+            for field in node.data.fields.iter().flatten() {
+                if !field.has_valid_name() {
+                    errors.push(ValidationError::InvalidConfig {
+                        node_id: node.id.clone(),
+                        message: format!("field name '{}' is not a valid identifier", field.name),
+                    });
+                } else if !seen.insert(field.name.as_str()) {
+                    errors.push(ValidationError::InvalidConfig {
+                        node_id: node.id.clone(),
+                        message: format!("field '{}' is declared more than once", field.name),
+                    });
+                }
+                if !field.has_valid_description() {
+                    errors.push(ValidationError::InvalidConfig {
+                        node_id: node.id.clone(),
+                        message: format!(
+                            "description of field '{}' must not contain '*/'",
+                            field.name
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    let has_result_schema = workflow
+        .nodes
+        .iter()
+        .any(|n| n.node_type == NodeType::DataOut && n.data.fields.is_some());
+    if has_result_schema {
+        for node in &workflow.nodes {
+            if node.node_type == NodeType::End && node.data.result_mapping.is_none() {
+                warnings.push(ValidationWarning::ConfigSuggestion {
+                    node_id: node.id.clone(),
+                    message: "workflow declares a result type but this end node has no \
+                              resultMapping; the input is returned unchecked"
+                        .to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Validate Signal nodes: signal names, payload schemas, and the state
+/// variables payloads are stored in.
+pub fn validate_signals(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let mut payloads: HashMap<String, (String, Vec<DataField>)> = HashMap::new();
+
+    for node in &workflow.nodes {
+        let Some(config) = node.signal_config() else {
+            continue;
+        };
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        if !config.has_valid_name() {
+            errors.push(invalid(format!(
+                "signal name '{}' may only contain letters, digits, '_' and '-'",
+                config.name
+            )));
+            continue;
+        }
+
+        let mut seen = HashSet::new();
+        for field in &config.payload {
+            if !field.has_valid_name() {
+                errors.push(invalid(format!(
+                    "field name '{}' is not a valid identifier",
+                    field.name
+                )));
+            } else if !seen.insert(field.name.as_str()) {
+                errors.push(invalid(format!(
+                    "field '{}' is declared more than once",
+                    field.name
+                )));
+            }
+            if !field.has_valid_description() {
+                errors.push(invalid(format!(
+                    "description of field '{}' must not contain '*/'",
+                    field.name
+                )));
+            }
+        }
+
+        // Every node for a signal shares one definition, so payloads must agree
+        if !config.payload.is_empty() {
+            match payloads.get(&config.name) {
+                Some((other, payload)) if payload != &config.payload => {
+                    errors.push(invalid(format!(
+                        "signal '{}' is declared with a different payload on node '{}'",
+                        config.name, other
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    payloads.insert(config.name.clone(), (node.id.clone(), config.payload));
+                }
+            }
+        }
+
+        if let Some(variable) = &config.state_variable {
+            if !workflow.variables.iter().any(|v| &v.name == variable) {
+                errors.push(ValidationError::UnknownVariable {
+                    node_id: node.id.clone(),
+                    var_name: variable.clone(),
+                });
+            }
+        }
+
+        if config.timeout.is_some() && !config.wait {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: "timeout only applies when waitForSignal is set".to_string(),
+            });
+        }
+    }
+}
+
+/// Validate Approval nodes.
+///
+/// Each approval waits on a signal of its own, so the signal name must be
+/// usable and not shared with another signal, update or query. Outgoing
+/// edges are selected by outcome: each branch handle must be an outcome used
+/// at most once, and `timed_out` needs a timeout. An outcome with no branch
+/// and no unlabelled edge to fall back on is reported.
+pub fn validate_approvals(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let approvals: Vec<&WorkflowNode> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Approval)
+        .collect();
+    if approvals.is_empty() {
+        return;
+    }
+
+    // Handler names declared by other nodes, and by the queries
+    let mut handlers: HashMap<&str, &str> = HashMap::new();
+    for node in &workflow.nodes {
+        let name = match node.node_type {
+            NodeType::Signal | NodeType::Timer => node.data.signal_name.as_deref(),
+            NodeType::Update => node.data.update_name.as_deref(),
+            _ => None,
+        };
+        if let Some(name) = name {
+            handlers.entry(name).or_insert(&node.id);
+        }
+    }
+    let query_names: HashSet<&str> = workflow
+        .metadata
+        .iter()
+        .flat_map(|m| &m.queries)
+        .map(|q| q.name.as_str())
+        .collect();
+    if query_names.contains(PENDING_APPROVALS_QUERY) {
+        errors.push(ValidationError::InvalidQuery {
+            name: PENDING_APPROVALS_QUERY.to_string(),
+            message: "the workflow's approvals already define this query".to_string(),
+        });
+    }
+
+    for node in approvals {
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        let signal = node.data.signal_name.as_deref().unwrap_or(&node.id);
+        if !is_valid_handler_name(signal) {
+            errors.push(invalid(format!(
+                "signal name '{}' may only contain letters, digits, '_' and '-'; set signalName",
+                signal
+            )));
+        } else if let Some(other) = handlers.get(signal) {
+            errors.push(invalid(format!(
+                "signal '{}' is also used by node '{}'; each approval needs a signal of its own",
+                signal, other
+            )));
+        } else if query_names.contains(signal) {
+            errors.push(invalid(format!(
+                "signal '{}' has the same name as a query",
+                signal
+            )));
+        }
+        handlers.entry(signal).or_insert(&node.id);
+
+        let mut handles = HashSet::new();
+        let mut has_default = false;
+        for edge in workflow.get_outgoing_edges(&node.id) {
+            match edge.source_handle.as_deref() {
+                None | Some("") => has_default = true,
+                Some(handle) if !APPROVAL_OUTCOMES.contains(&handle) => {
+                    errors.push(invalid(format!(
+                        "unknown branch '{}', expected 'approved', 'rejected' or 'timed_out'",
+                        handle
+                    )));
+                }
+                Some(handle) => {
+                    if !handles.insert(handle) {
+                        errors.push(invalid(format!(
+                            "approval has more than one '{}' edge",
+                            handle
+                        )));
+                    }
+                }
+            }
+        }
+        if handles.contains(TIMED_OUT_HANDLE) && node.data.timeout.is_none() {
+            errors.push(invalid(format!(
+                "the '{}' branch is never taken without a timeout",
+                TIMED_OUT_HANDLE
+            )));
+        }
+
+        if !handles.is_empty() && !has_default {
+            for outcome in APPROVAL_OUTCOMES {
+                let possible = outcome != TIMED_OUT_HANDLE || node.data.timeout.is_some();
+                if possible && !handles.contains(outcome) {
+                    warnings.push(ValidationWarning::ConfigSuggestion {
+                        node_id: node.id.clone(),
+                        message: format!("no branch handles the '{}' outcome", outcome),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Validate Agent nodes.
+///
+/// Tools must name activities the workflow runs by their method in the
+/// generated `Activities` interface, and no activity may take the name of the
+/// generated `callModel` activity. The output schema declares each field
+/// once, and an agent needs at least one turn.
+pub fn validate_agents(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let agents: Vec<&WorkflowNode> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Agent)
+        .collect();
+    if agents.is_empty() {
+        return;
+    }
+
+    let activities: HashSet<String> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Activity && !n.is_log())
+        .map(activity_method)
+        .collect();
+    if let Some(node) = workflow
+        .nodes
+        .iter()
+        .find(|n| n.node_type == NodeType::Activity && activity_method(n) == CALL_MODEL_ACTIVITY)
+    {
+        errors.push(ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message: format!(
+                "activity '{}' is generated for the model calls of agent nodes",
+                CALL_MODEL_ACTIVITY
+            ),
+        });
+    }
+
+    for node in agents {
+        let Some(config) = node.agent_config() else {
+            continue;
+        };
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        if config.model.trim().is_empty() {
+            errors.push(invalid("model must not be empty".to_string()));
+        }
+        if config.max_turns == 0 {
+            errors.push(invalid("maxTurns must be greater than zero".to_string()));
+        }
+
+        let mut seen = HashSet::new();
+        for tool in &config.tools {
+            if !is_identifier(tool) {
+                errors.push(invalid(format!(
+                    "tool '{}' is not a valid identifier",
+                    tool
+                )));
+            } else if !activities.contains(tool) {
+                errors.push(invalid(format!(
+                    "tool '{}' is not in the Activities interface; no activity node runs it",
+                    tool
+                )));
+            } else if !seen.insert(tool.as_str()) {
+                errors.push(invalid(format!("tool '{}' is listed more than once", tool)));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for field in &config.output_schema {
+            if !field.has_valid_name() {
+                errors.push(invalid(format!(
+                    "output field name '{}' is not a valid identifier",
+                    field.name
+                )));
+            } else if !seen.insert(field.name.as_str()) {
+                errors.push(invalid(format!(
+                    "output field '{}' is declared more than once",
+                    field.name
+                )));
+            }
+        }
+    }
+}
+
+/// Validate Child Workflow nodes: start mode, parent-close policy and the
+/// child workflow ID template.
+pub fn validate_child_workflows(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    for node in workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::ChildWorkflow)
+    {
+        let data = &node.data;
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        if let Some(mode) = &data.child_mode {
+            if ChildMode::parse(mode).is_none() {
+                errors.push(invalid(format!(
+                    "unknown child mode '{}', expected 'execute' or 'start'",
+                    mode
+                )));
+            }
+        }
+        if let Some(policy) = &data.parent_close_policy {
+            if ParentClosePolicy::parse(policy).is_none() {
+                errors.push(invalid(format!(
+                    "unknown parent close policy '{}', expected 'terminate', 'abandon' \
+                     or 'request-cancel'",
+                    policy
+                )));
+            }
+        }
+        if let Some(template) = &data.child_workflow_id {
+            if let Err(message) = parse_template(template) {
+                errors.push(invalid(format!("invalid childWorkflowId: {}", message)));
+            }
+        }
+        if data.task_queue.as_deref().is_some_and(|q| q.trim().is_empty()) {
+            errors.push(invalid("taskQueue must not be empty".to_string()));
+        }
+
+        // Temporal terminates children when the parent closes by default,
+        // which is rarely what a fire-and-forget child wants
+        let mode = data.child_mode.as_deref().and_then(ChildMode::parse);
+        if mode == Some(ChildMode::Start) && data.parent_close_policy.is_none() {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: "started children are terminated when the parent completes \
+                          unless parentClosePolicy is 'abandon'"
+                    .to_string(),
+            });
+        }
+    }
+}
+
+/// Validate the workflow retry policy and node overrides.
+///
+/// `fail-after-x` needs an attempt limit; options a strategy ignores are
+/// reported so a policy does not silently behave differently than it reads.
+pub fn validate_retry_policies(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    if let Some(policy) = workflow
+        .metadata
+        .as_ref()
+        .and_then(|m| m.retry_policy.as_ref())
+    {
+        for message in retry_policy_errors(policy) {
+            errors.push(ValidationError::InvalidRetryPolicy {
+                scope: "the workflow".to_string(),
+                message,
+            });
+        }
+    }
+
+    for node in &workflow.nodes {
+        let Some(policy) = &node.data.retry_policy else {
+            continue;
+        };
+        for message in retry_policy_errors(policy) {
+            errors.push(ValidationError::InvalidRetryPolicy {
+                scope: format!("node '{}'", node.id),
+                message,
+            });
+        }
+
+        let ignored = match policy.strategy {
+            RetryStrategy::KeepTrying if policy.max_attempts.is_some() => {
+                Some("maxAttempts is ignored by 'keep-trying', which retries until it succeeds")
+            }
+            RetryStrategy::None
+                if policy.max_attempts.is_some()
+                    || policy.initial_interval.is_some()
+                    || policy.max_interval.is_some()
+                    || policy.backoff_coefficient.is_some()
+                    || policy.non_retryable_errors.is_some() =>
+            {
+                Some("retry options are ignored when the strategy is 'none'")
+            }
+            _ => None,
+        };
+        if let Some(message) = ignored {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: message.to_string(),
+            });
+        }
+        if !matches!(node.node_type, NodeType::Activity | NodeType::Retry) {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: "retryPolicy only applies to activity and retry nodes".to_string(),
+            });
+        }
+    }
+}
+
+/// Validate compensation activities.
+///
+/// A compensation runs through the same `Activities` interface as the
+/// workflow's other activities, so it must name one of them.
+pub fn validate_compensations(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let activities: HashSet<String> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Activity)
+        .map(activity_method)
+        .collect();
+
+    for node in &workflow.nodes {
+        let Some(compensation) = node.data.compensation_activity.as_deref() else {
+            continue;
+        };
+        if node.node_type != NodeType::Activity || node.is_log() {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: "compensationActivity only applies to activity nodes".to_string(),
+            });
+        } else if !activities.contains(compensation) {
+            errors.push(ValidationError::InvalidConfig {
+                node_id: node.id.clone(),
+                message: format!(
+                    "compensation activity '{}' is not in the Activities interface; \
+                     no activity node runs it",
+                    compensation
+                ),
+            });
+        }
+    }
+}
+
+/// Validate timeouts and TTLs.
+///
+/// Every duration must parse, and timeouts must be positive. A node that may
+/// wait longer than the whole workflow is allowed to run is reported, since
+/// the workflow timeout cuts it short.
+pub fn validate_timeouts(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let workflow_timeout = workflow
+        .metadata
+        .as_ref()
+        .and_then(|m| m.timeout.as_deref())
+        .and_then(|value| match positive_duration(value) {
+            Ok(timeout) => Some(timeout),
+            Err(message) => {
+                errors.push(ValidationError::InvalidMetadata {
+                    field: "timeout".to_string(),
+                    message,
+                });
+                None
+            }
+        });
+
+    for node in &workflow.nodes {
+        let data = &node.data;
+        let mut check = |field: &str, value: Option<&str>, positive: bool| {
+            let value = value?;
+            let parsed = if positive {
+                positive_duration(value)
+            } else {
+                Duration::parse(value)
+                    .map_err(|message| format!("'{}' is not a valid duration: {}", value, message))
+            };
+            parsed
+                .map_err(|message| {
+                    errors.push(ValidationError::InvalidConfig {
+                        node_id: node.id.clone(),
+                        message: format!("{} {}", field, message),
+                    })
+                })
+                .ok()
+        };
+        let timeout = check("timeout", data.timeout.as_deref(), true);
+        check("runTimeout", data.run_timeout.as_deref(), true);
+        check("ttl", data.ttl.as_deref(), false);
+
+        if let (Some(timeout), Some(workflow_timeout)) = (timeout, workflow_timeout) {
+            if timeout > workflow_timeout {
+                warnings.push(ValidationWarning::ConfigSuggestion {
+                    node_id: node.id.clone(),
+                    message: format!(
+                        "timeout {} exceeds the workflow timeout {}",
+                        timeout, workflow_timeout
+                    ),
+                });
+            }
+        }
+    }
+}
+
+/// Parse a timeout, which must be longer than zero
+fn positive_duration(value: &str) -> Result<Duration, String> {
+    match Duration::parse(value) {
+        Ok(duration) if duration.is_zero() => Err(format!("'{}' must be longer than zero", value)),
+        Ok(duration) => Ok(duration),
+        Err(message) => Err(format!("'{}' is not a valid duration: {}", value, message)),
+    }
+}
+
+/// Validate the bodies of Map nodes.
+///
+/// The body runs once per item inside the map, so it cannot end the
+/// workflow. Every item also adds the activities, child workflows and timers
+/// of the body to the workflow history; a map whose collection may be large
+/// enough to pass Temporal's history warning threshold is reported.
+pub fn validate_maps(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let cfg = ControlFlowGraph::new(workflow);
+
+    for node in &workflow.nodes {
+        let Some(config) = node.map_config() else {
+            continue;
+        };
+        let body: Vec<&WorkflowNode> = cfg
+            .body_region(&node.id)
+            .iter()
+            .filter_map(|id| cfg.node(id))
+            .collect();
+
+        for end in body.iter().filter(|n| n.node_type == NodeType::End) {
+            errors.push(ValidationError::InvalidConfig {
+                node_id: node.id.clone(),
+                message: format!(
+                    "the body reaches End node '{}'; it runs once per item, so it must \
+                     rejoin the flow after the map",
+                    end.id
+                ),
+            });
+        }
+
+        let per_item: u64 = body.iter().map(|n| history_events(n)).sum();
+        if per_item == 0 {
+            continue;
+        }
+        // An array literal has a known size; otherwise maxItems bounds it
+        let items = match parse(&config.collection).map(|expr| expr.kind) {
+            Ok(ExprKind::Array(elements)) => Some(elements.len() as u64),
+            _ => config.max_items.map(u64::from),
+        };
+        let message = match items {
+            Some(items) if items * per_item > HISTORY_WARNING_EVENTS => format!(
+                "mapping {} items adds about {} history events, more than the {} at which \
+                 Temporal warns; consider a child workflow per batch of items",
+                items,
+                items * per_item,
+                HISTORY_WARNING_EVENTS
+            ),
+            Some(_) => continue,
+            None => format!(
+                "the collection size is not bounded; at about {} history events per item, \
+                 more than {} items pass the {} events at which Temporal warns; \
+                 set maxItems to bound it",
+                per_item,
+                HISTORY_WARNING_EVENTS / per_item,
+                HISTORY_WARNING_EVENTS
+            ),
+        };
+        warnings.push(ValidationWarning::ConfigSuggestion {
+            node_id: node.id.clone(),
+            message,
+        });
+    }
+}
+
+/// History size at which Temporal starts warning about a workflow; the
+/// workflow is terminated at five times as many events
+const HISTORY_WARNING_EVENTS: u64 = 10_240;
+
+/// Rough number of history events a node adds each time it runs
+fn history_events(node: &WorkflowNode) -> u64 {
+    match node.node_type {
+        // Scheduled (or initiated), started and completed
+        NodeType::Activity if !node.is_log() => 3,
+        NodeType::ChildWorkflow => 3,
+        // Started and fired
+        NodeType::Timer => 2,
+        // The timeout's timer, and the decision signal
+        NodeType::Approval => 3,
+        // A model call per turn, before any tools it calls
+        NodeType::Agent => 3 * u64::from(node.agent_config().map_or(0, |config| config.max_turns)),
+        _ => 0,
+    }
+}
+
+/// Validate Timer nodes.
+///
+/// A timer sets exactly one delay. Fixed durations must be positive and
+/// timestamps must carry an offset; expressions are checked with the others
+/// by `validate_expressions` and `validate_types`. A timer that outlasts the
+/// workflow timeout is reported, as are signal names that cannot be declared.
+pub fn validate_timers(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    // Malformed workflow timeouts are reported by validate_timeouts
+    let workflow_timeout = workflow
+        .metadata
+        .as_ref()
+        .and_then(|m| m.timeout.as_deref())
+        .and_then(|value| Duration::parse(value).ok());
+
+    for node in workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Timer)
+    {
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        let data = &node.data;
+        let delays = [&data.duration, &data.duration_expression, &data.until];
+        if delays.iter().filter(|d| d.is_some()).count() > 1 {
+            errors.push(invalid(
+                "timer sets more than one of duration, durationExpression and until".to_string(),
+            ));
+            continue;
+        }
+        let Some(config) = node.timer_config() else {
+            continue;
+        };
+
+        match &config.delay {
+            TimerDelay::Duration(value) => match positive_duration(value) {
+                Ok(duration) => {
+                    if let Some(workflow_timeout) = workflow_timeout.filter(|t| duration > *t) {
+                        warnings.push(ValidationWarning::ConfigSuggestion {
+                            node_id: node.id.clone(),
+                            message: format!(
+                                "timer duration {} exceeds the workflow timeout {}",
+                                duration, workflow_timeout
+                            ),
+                        });
+                    }
+                }
+                Err(message) => errors.push(invalid(format!("duration {}", message))),
+            },
+            TimerDelay::Timestamp(value) => {
+                if let Err(message) = parse_timestamp(value) {
+                    errors.push(invalid(format!("until {}", message)));
+                }
+            }
+            TimerDelay::DurationExpression(_) | TimerDelay::UntilExpression(_) => {}
+        }
+
+        if let Some(signal) = &config.signal {
+            if !is_valid_handler_name(signal) {
+                errors.push(invalid(format!(
+                    "signal name '{}' may only contain letters, digits, '_' and '-'",
+                    signal
+                )));
+            }
+        }
+    }
+}
+
+/// Problems that make a retry policy impossible to map to Temporal options
+fn retry_policy_errors(policy: &RetryPolicy) -> Vec<String> {
+    let mut problems = Vec::new();
+    if policy.strategy == RetryStrategy::FailAfterX && policy.max_attempts.is_none() {
+        problems.push("'fail-after-x' requires maxAttempts".to_string());
+    }
+    if policy.max_attempts == Some(0) && policy.strategy != RetryStrategy::KeepTrying {
+        problems.push("maxAttempts must be at least 1".to_string());
+    }
+    if policy.backoff_coefficient.is_some_and(|c| c < 1.0) {
+        problems.push("backoffCoefficient must be at least 1".to_string());
+    }
+
+    let mut interval = |field: &str, value: &Option<String>| {
+        let value = value.as_deref()?;
+        Duration::parse(value)
+            .map_err(|message| {
+                problems.push(format!(
+                    "{} '{}' is not a valid duration: {}",
+                    field, value, message
+                ))
+            })
+            .ok()
+    };
+    let initial = interval("initialInterval", &policy.initial_interval);
+    let max = interval("maxInterval", &policy.max_interval);
+    if let (Some(initial), Some(max)) = (initial, max) {
+        if max < initial {
+            problems.push("maxInterval must not be shorter than initialInterval".to_string());
+        }
+    }
+    problems
+}
+
+/// Validate Update nodes.
+///
+/// Each update has a single handler, so its name must be unique and may not
+/// be shared with a signal or a query. The handler is registered before the
+/// workflow body runs, so its expressions cannot read node outputs.
+pub fn validate_updates(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let signal_names: HashSet<&str> = workflow
         .nodes
         .iter()
-        .filter(|n| n.node_type == NodeType::Trigger)
+        .filter(|n| n.node_type == NodeType::Signal)
+        .filter_map(|n| n.data.signal_name.as_deref())
+        .collect();
+    let query_names: HashSet<&str> = workflow
+        .metadata
+        .iter()
+        .flat_map(|m| &m.queries)
+        .map(|q| q.name.as_str())
         .collect();
 
-    if trigger_nodes.is_empty() {
-        errors.push(ValidationError::NoStartNode);
-    } else if trigger_nodes.len() > 1 {
-        let ids: Vec<_> = trigger_nodes.iter().map(|n| n.id.clone()).collect();
-        errors.push(ValidationError::MultipleStartNodes(ids));
+    let mut declared: HashMap<String, &str> = HashMap::new();
+    for node in &workflow.nodes {
+        let Some(config) = node.update_config() else {
+            continue;
+        };
+        let invalid = |message: String| ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message,
+        };
+
+        if !config.has_valid_name() {
+            errors.push(invalid(format!(
+                "update name '{}' may only contain letters, digits, '_' and '-'",
+                config.name
+            )));
+        } else if let Some(other) = declared.get(&config.name) {
+            errors.push(invalid(format!(
+                "update '{}' is already declared on node '{}'",
+                config.name, other
+            )));
+        } else if signal_names.contains(config.name.as_str()) {
+            errors.push(invalid(format!(
+                "update '{}' has the same name as a signal",
+                config.name
+            )));
+        } else if query_names.contains(config.name.as_str()) {
+            errors.push(invalid(format!(
+                "update '{}' has the same name as a query",
+                config.name
+            )));
+        }
+        declared.entry(config.name.clone()).or_insert(&node.id);
+
+        for (kind, fields) in [("argument", &config.argument), ("return", &config.returns)] {
+            let mut seen = HashSet::new();
+            for field in fields {
+                if !field.has_valid_name() {
+                    errors.push(invalid(format!(
+                        "{} field name '{}' is not a valid identifier",
+                        kind, field.name
+                    )));
+                } else if !seen.insert(field.name.as_str()) {
+                    errors.push(invalid(format!(
+                        "{} field '{}' is declared more than once",
+                        kind, field.name
+                    )));
+                }
+                if !field.has_valid_description() {
+                    errors.push(invalid(format!(
+                        "description of {} field '{}' must not contain '*/'",
+                        kind, field.name
+                    )));
+                }
+            }
+        }
+
+        if !config.returns.is_empty() && config.return_value.is_none() {
+            errors.push(invalid(
+                "returnFields requires a returnValue expression".to_string(),
+            ));
+        }
+
+        for field in node_expressions(node) {
+            let Ok(exprs) = field.parse() else { continue };
+            if exprs.iter().any(|e| !e.node_references().is_empty()) {
+                errors.push(invalid(format!(
+                    "{} cannot read node outputs; update handlers run outside the workflow body",
+                    field.field
+                )));
+            }
+        }
+
+        if let Some(variable) = &config.state_variable {
+            if !workflow.variables.iter().any(|v| &v.name == variable) {
+                errors.push(ValidationError::UnknownVariable {
+                    node_id: node.id.clone(),
+                    var_name: variable.clone(),
+                });
+            }
+        }
+
+        if config.timeout.is_some() && !config.wait {
+            warnings.push(ValidationWarning::ConfigSuggestion {
+                node_id: node.id.clone(),
+                message: "timeout only applies when waitForSignal is set".to_string(),
+            });
+        }
     }
+}
 
-    // Find end nodes
-    let end_nodes: Vec<_> = workflow
+/// Validate the queries declared in the workflow metadata.
+pub fn validate_queries(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let Some(metadata) = &workflow.metadata else {
+        return;
+    };
+    let signal_names: HashSet<String> = workflow
         .nodes
         .iter()
-        .filter(|n| n.node_type == NodeType::End)
+        .filter_map(|n| n.data.signal_name.clone())
         .collect();
 
-    if end_nodes.is_empty() {
-        errors.push(ValidationError::NoEndNode);
-    }
+    let mut seen = HashSet::new();
+    for query in &metadata.queries {
+        let invalid = |message: String| ValidationError::InvalidQuery {
+            name: query.name.clone(),
+            message,
+        };
 
-    // Check for unreachable nodes
-    if !trigger_nodes.is_empty() && errors.is_empty() {
-        check_reachability(workflow, &trigger_nodes[0].id, errors);
-    }
+        if !query.has_valid_name() {
+            errors.push(invalid(
+                "query names may only contain letters, digits, '_' and '-'".to_string(),
+            ));
+        } else if !seen.insert(query.name.as_str()) {
+            errors.push(invalid("query is declared more than once".to_string()));
+        } else if signal_names.contains(&query.name) {
+            errors.push(invalid("a signal with the same name exists".to_string()));
+        }
+        if !query.has_valid_description() {
+            errors.push(invalid("description must not contain '*/'".to_string()));
+        }
 
-    // Check for cycles
-    check_cycles(workflow, errors);
+        if let QuerySource::Variables { variables } = &query.source {
+            if workflow.variables.is_empty() {
+                errors.push(invalid("workflow declares no variables to expose".to_string()));
+            }
+            for name in variables {
+                if !workflow.variables.iter().any(|v| &v.name == name) {
+                    errors.push(invalid(format!("unknown variable '{}'", name)));
+                }
+            }
+        }
+    }
 }
 
-/// Validate individual nodes
-pub fn validate_nodes(
+/// Validate node input mappings.
+///
+/// A mapping may only read the output of a node that runs before the mapped
+/// node on every path, i.e. a node that dominates it.
+pub fn validate_input_mappings(
     workflow: &WorkflowDefinition,
     errors: &mut Vec<ValidationError>,
-    warnings: &mut Vec<ValidationWarning>,
+    _warnings: &mut Vec<ValidationWarning>,
 ) {
-    let node_ids: HashSet<_> = workflow.nodes.iter().map(|n| &n.id).collect();
-    let outgoing: HashMap<_, Vec<_>> = workflow.nodes.iter().map(|n| {
-        let edges: Vec<_> = workflow.edges.iter().filter(|e| e.source == n.id).collect();
-        (&n.id, edges)
-    }).collect();
+    let cfg = ControlFlowGraph::new(workflow);
 
     for node in &workflow.nodes {
-        // Check required fields based on node type
-        match node.node_type {
-            NodeType::Activity => {
-                if node.data.component_id.is_none() && node.data.activity_name.is_none() {
-                    warnings.push(ValidationWarning::MissingOptionalField {
+        for value in node.data.input_mapping.iter().flat_map(|m| m.values()) {
+            let references = match parse_template(value) {
+                Ok(_) => template_references(value),
+                Err(message) => {
+                    errors.push(ValidationError::InvalidConfig {
                         node_id: node.id.clone(),
-                        field: "componentId or activityName".to_string(),
+                        message: format!("invalid input mapping: {}", message),
                     });
+                    continue;
+                }
+            };
+
+            for reference in references {
+                match reference {
+                    DataReference::State(path) => {
+                        let name = &path[0];
+                        if !workflow.variables.iter().any(|v| &v.name == name) {
+                            errors.push(ValidationError::UnknownVariable {
+                                node_id: node.id.clone(),
+                                var_name: name.clone(),
+                            });
+                        }
+                    }
+                    DataReference::NodeOutput { node_id, .. } => {
+                        check_node_reference(workflow, &cfg, node, &node_id, errors)
+                    }
+                    DataReference::NodeError { node_id, .. } => {
+                        check_error_reference(workflow, &cfg, node, &node_id, errors)
+                    }
+                    DataReference::MapItem { node_id, .. } => {
+                        check_item_reference(workflow, &cfg, node, &node_id, errors)
+                    }
+                    DataReference::Input(_) => {}
                 }
             }
-            NodeType::Signal => {
-                if node.data.signal_name.is_none() {
-                    errors.push(ValidationError::MissingRequiredField {
-                        node_id: node.id.clone(),
-                        field: "signalName".to_string(),
-                    });
+        }
+    }
+}
+
+/// Validate the expressions in node conditions, result mappings, variable
+/// values and log templates.
+pub fn validate_expressions(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let cfg = ControlFlowGraph::new(workflow);
+    let locals = loop_item_variables(workflow);
+
+    for node in &workflow.nodes {
+        let mut node_locals = locals.clone();
+        if node.node_type == NodeType::Update {
+            node_locals.push(UPDATE_ARGUMENT);
+        }
+        for field in node_expressions(node) {
+            let invalid = |e: ExpressionError| ValidationError::InvalidExpression {
+                node_id: node.id.clone(),
+                field: field.field.to_string(),
+                message: e.message,
+                span: e.span,
+            };
+
+            let exprs = match field.parse() {
+                Ok(exprs) => exprs,
+                Err(e) => {
+                    errors.push(invalid(e));
+                    continue;
+                }
+            };
+
+            for expr in exprs {
+                if let Err(e) = check_scope(&expr, &node_locals) {
+                    errors.push(invalid(e));
+                    continue;
+                }
+                for (name, _) in expr.state_references() {
+                    if !workflow.variables.iter().any(|v| v.name == name) {
+                        errors.push(ValidationError::UnknownVariable {
+                            node_id: node.id.clone(),
+                            var_name: name,
+                        });
+                    }
+                }
+                for reference in expr.node_references() {
+                    check_node_reference(workflow, &cfg, node, &reference.node_id, errors);
                 }
             }
-            NodeType::ChildWorkflow => {
-                if node.data.workflow_id.is_none() {
-                    errors.push(ValidationError::MissingRequiredField {
-                        node_id: node.id.clone(),
-                        field: "workflowId".to_string(),
-                    });
+        }
+    }
+}
+
+/// Check that values flowing between nodes have compatible types: operands
+/// inside expressions, loop collections, result mappings against the DataOut
+/// schema, variable values against their declaration, and input mappings
+/// against the component's input type.
+pub fn validate_types(
+    workflow: &WorkflowDefinition,
+    errors: &mut Vec<ValidationError>,
+    _warnings: &mut Vec<ValidationWarning>,
+) {
+    let env = TypeEnvironment::new(workflow);
+
+    for node in &workflow.nodes {
+        let mismatch = |expected: &dyn ToString, found: &dyn ToString| {
+            ValidationError::TypeMismatch {
+                node_id: node.id.clone(),
+                expected: expected.to_string(),
+                found: found.to_string(),
+            }
+        };
+
+        let update = node.update_config();
+        let node_env = match &update {
+            Some(config) => env.with_local(UPDATE_ARGUMENT, config.argument_type()),
+            None => env.clone(),
+        };
+
+        let mut field_types = HashMap::new();
+        for field in node_expressions(node) {
+            // Syntax errors are reported by validate_expressions
+            let Ok(exprs) = field.parse() else { continue };
+            for expr in &exprs {
+                match node_env.infer(expr) {
+                    Ok(ty) if field.kind == ExpressionKind::Plain => {
+                        field_types.insert(field.field, ty);
+                    }
+                    Ok(_) => {}
+                    Err(e) => errors.push(mismatch(&e.expected, &e.found)),
                 }
             }
-            NodeType::Conditional | NodeType::Condition => {
-                if node.data.condition.is_none() {
-                    warnings.push(ValidationWarning::MissingOptionalField {
-                        node_id: node.id.clone(),
-                        field: "condition".to_string(),
-                    });
+        }
+
+        let set_variable = node.node_type == NodeType::StateVariable
+            && matches!(
+                node.data.component_name.as_deref(),
+                Some("SetVariable") | Some("set-variable")
+            );
+        let expected_types = [
+            (
+                "collection",
+                matches!(node.node_type, NodeType::Loop | NodeType::Map)
+                    .then(|| TypeDescriptor::array(TypeDescriptor::Any)),
+            ),
+            ("resultMapping", env.result_type().cloned()),
+            (
+                "valueExpression",
+                node.data
+                    .variable_name
+                    .as_deref()
+                    .filter(|_| set_variable)
+                    .and_then(|name| env.variable_type(name))
+                    .cloned(),
+            ),
+            (
+                "validator",
+                update.as_ref().map(|_| TypeDescriptor::Boolean),
+            ),
+            (
+                "returnValue",
+                update.as_ref().and_then(|config| config.return_type()),
+            ),
+        ];
+        for (field, expected) in expected_types {
+            if let (Some(found), Some(expected)) = (field_types.get(field), expected) {
+                if !found.is_assignable_to(&expected) {
+                    errors.push(mismatch(&expected, found));
                 }
             }
-            _ => {}
         }
 
-        // Check for dead ends (non-end nodes with no outgoing edges)
-        if node.node_type != NodeType::End {
-            if let Some(edges) = outgoing.get(&node.id) {
-                if edges.is_empty() {
-                    warnings.push(ValidationWarning::DeadEnd {
-                        node_id: node.id.clone(),
-                    });
+        // Timers accept milliseconds or duration strings, and deadlines as
+        // epoch milliseconds or date strings
+        if node.node_type == NodeType::Timer {
+            for field in ["durationExpression", "until"] {
+                let Some(found) = field_types.get(field) else {
+                    continue;
+                };
+                if !found.is_assignable_to(&TypeDescriptor::Number)
+                    && !found.is_assignable_to(&TypeDescriptor::String)
+                {
+                    errors.push(mismatch(&"number | string", found));
                 }
             }
         }
 
-        // Check variable references in config
-        if let Some(config) = &node.data.config {
-            for (key, value) in config {
-                if let Some(var_ref) = extract_variable_reference(value) {
-                    // Check if variable exists
-                    let var_exists = workflow.variables.iter().any(|v| v.name == var_ref);
-                    if !var_exists && !node_ids.contains(&var_ref) {
-                        // Could be referencing a node output, not just a variable
-                        // Only warn, don't error
-                        warnings.push(ValidationWarning::ConfigSuggestion {
-                            node_id: node.id.clone(),
-                            message: format!(
-                                "Config key '{}' references '{}' which may not exist",
-                                key, var_ref
-                            ),
-                        });
-                    }
+        if let Some(config) = &update {
+            let declared = config
+                .state_variable
+                .as_deref()
+                .and_then(|name| env.variable_type(name));
+            if let Some(declared) = declared {
+                let argument = config.argument_type();
+                if !argument.is_assignable_to(declared) {
+                    errors.push(mismatch(declared, &argument));
+                }
+            }
+        }
+
+        if let Some(signal) = node.signal_config() {
+            let declared = signal
+                .state_variable
+                .as_deref()
+                .and_then(|name| env.variable_type(name));
+            if let Some(declared) = declared {
+                let payload = signal.payload_type();
+                if !payload.is_assignable_to(declared) {
+                    errors.push(mismatch(declared, &payload));
                 }
             }
         }
+
+        let Some(input_type) = node.port_types().map(|types| types.input) else {
+            continue;
+        };
+        for (argument, template) in node.data.input_mapping.iter().flatten() {
+            let Some(expected) = input_type.field(argument) else {
+                continue;
+            };
+            let found = env.template_type(template);
+            if !found.is_assignable_to(expected) {
+                errors.push(mismatch(expected, &found));
+            }
+        }
     }
 }
 
-/// Validate edges
-pub fn validate_edges(
+/// Names bound by for-each loops and maps, which expressions may read as locals
+fn loop_item_variables(workflow: &WorkflowDefinition) -> Vec<&str> {
+    workflow
+        .nodes
+        .iter()
+        .filter(|n| match n.node_type {
+            NodeType::Loop => {
+                LoopMode::resolve(
+                    n.data.loop_type.as_deref(),
+                    n.data.condition.is_some(),
+                    n.data.collection.is_some(),
+                ) == LoopMode::ForEach
+            }
+            NodeType::Map => true,
+            _ => false,
+        })
+        .map(|n| {
+            n.data
+                .item_variable
+                .as_deref()
+                .unwrap_or(DEFAULT_ITEM_VARIABLE)
+        })
+        .collect()
+}
+
+/// Check that `node` may read the output of `reference`: it must exist,
+/// produce a result, and run before `node` on every path, on a concurrent
+/// branch that joined the others before `node` runs, or in a retry region
+/// that succeeded before `node` runs.
+fn check_node_reference(
     workflow: &WorkflowDefinition,
+    cfg: &ControlFlowGraph,
+    node: &WorkflowNode,
+    reference: &str,
     errors: &mut Vec<ValidationError>,
-    _warnings: &mut Vec<ValidationWarning>,
 ) {
-    let node_ids: HashSet<_> = workflow.nodes.iter().map(|n| n.id.as_str()).collect();
+    let Some(source) = workflow.get_node(reference) else {
+        errors.push(ValidationError::UnknownNodeReference {
+            node_id: node.id.clone(),
+            reference: reference.to_string(),
+        });
+        return;
+    };
+    if !source.produces_result() {
+        errors.push(ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message: format!("node '{}' does not produce an output", reference),
+        });
+    } else if source.id == node.id
+        || !(cfg.dominates(&source.id, &node.id)
+            || cfg.runs_after_join(&source.id, &node.id)
+            || cfg.runs_after_retry(&source.id, &node.id))
+        || !runs_on_branch(cfg, &source.id, &node.id, false)
+        // A map's result is complete only after every item has run
+        || (source.node_type == NodeType::Map
+            && cfg.body_region(&source.id).contains(&node.id.as_str()))
+    {
+        errors.push(ValidationError::NodeReferenceNotAvailable {
+            node_id: node.id.clone(),
+            reference: reference.to_string(),
+        });
+    }
+}
 
-    for edge in &workflow.edges {
-        // Check source node exists
-        if !node_ids.contains(edge.source.as_str()) {
-            errors.push(ValidationError::InvalidEdgeSource(edge.source.clone()));
-        }
+/// Check a `nodes.<id>.error` reference: the node must have an error edge,
+/// and the referencing node must only run after that edge was taken.
+fn check_error_reference(
+    workflow: &WorkflowDefinition,
+    cfg: &ControlFlowGraph,
+    node: &WorkflowNode,
+    reference: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(source) = workflow.get_node(reference) else {
+        errors.push(ValidationError::UnknownNodeReference {
+            node_id: node.id.clone(),
+            reference: reference.to_string(),
+        });
+        return;
+    };
+    if !cfg.successors(&source.id).iter().any(|e| e.is_error()) {
+        errors.push(ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message: format!("node '{}' has no '{}' edge", reference, ERROR_HANDLE),
+        });
+    } else if !runs_on_branch(cfg, &source.id, &node.id, true) {
+        errors.push(ValidationError::NodeReferenceNotAvailable {
+            node_id: node.id.clone(),
+            reference: reference.to_string(),
+        });
+    }
+}
 
-        // Check target node exists
-        if !node_ids.contains(edge.target.as_str()) {
-            errors.push(ValidationError::InvalidEdgeTarget(edge.target.clone()));
-        }
+/// Check a `nodes.<id>.item` reference: the node must be a map, and the
+/// referencing node must be part of its body.
+fn check_item_reference(
+    workflow: &WorkflowDefinition,
+    cfg: &ControlFlowGraph,
+    node: &WorkflowNode,
+    reference: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(source) = workflow.get_node(reference) else {
+        errors.push(ValidationError::UnknownNodeReference {
+            node_id: node.id.clone(),
+            reference: reference.to_string(),
+        });
+        return;
+    };
+    if source.node_type != NodeType::Map {
+        errors.push(ValidationError::InvalidConfig {
+            node_id: node.id.clone(),
+            message: format!("node '{}' is not a map node and has no item", reference),
+        });
+    } else if !cfg.body_region(&source.id).contains(&node.id.as_str()) {
+        errors.push(ValidationError::NodeReferenceNotAvailable {
+            node_id: node.id.clone(),
+            reference: reference.to_string(),
+        });
+    }
+}
+
+/// Whether `node_id` only runs once `source_id` has taken its error edge
+/// (`error`) or one of its other edges: a node of that branch dominates it,
+/// and the other branch never leads to it. A node without an error edge
+/// always takes its other edges.
+fn runs_on_branch(cfg: &ControlFlowGraph, source_id: &str, node_id: &str, error: bool) -> bool {
+    let edges = cfg.successors(source_id);
+    if !edges.iter().any(|e| e.is_error()) {
+        return !error;
     }
+    let (branch, other): (Vec<&&WorkflowEdge>, Vec<_>) =
+        edges.iter().partition(|e| e.is_error() == error);
+    branch.iter().any(|e| cfg.dominates(&e.target, node_id))
+        && !other.iter().any(|e| cfg.reaches(&e.target, node_id))
 }
 
 /// Check reachability from start node
@@ -214,15 +2084,28 @@ fn check_reachability(
     }
 }
 
-/// Check for cycles in the graph using DFS
+/// Check for cycles in the graph using DFS.
+///
+/// Cycles passing through a Loop node are the loop's own back-edges and are
+/// allowed, so Loop nodes are left out of the search.
 fn check_cycles(workflow: &WorkflowDefinition, errors: &mut Vec<ValidationError>) {
     let mut visited = HashSet::new();
     let mut rec_stack = HashSet::new();
     let mut cycle_nodes = Vec::new();
 
+    let loop_nodes: HashSet<&str> = workflow
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Loop)
+        .map(|n| n.id.as_str())
+        .collect();
+
     // Build adjacency list using owned strings
     let mut adj: HashMap<String, Vec<String>> = HashMap::new();
     for edge in &workflow.edges {
+        if loop_nodes.contains(edge.source.as_str()) || loop_nodes.contains(edge.target.as_str()) {
+            continue;
+        }
         adj.entry(edge.source.clone())
             .or_default()
             .push(edge.target.clone());
@@ -257,7 +2140,7 @@ fn check_cycles(workflow: &WorkflowDefinition, errors: &mut Vec<ValidationError>
         false
     }
 
-    for node in &workflow.nodes {
+    for node in workflow.nodes.iter().filter(|n| !loop_nodes.contains(n.id.as_str())) {
         if !visited.contains(&node.id)
             && dfs(
                 &node.id,
@@ -285,7 +2168,7 @@ fn extract_variable_reference(value: &serde_json::Value) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{NodeData, Position, WorkflowEdge, WorkflowNode};
+    use crate::schema::{NodeData, Position, WorkflowEdge};
 
     fn create_simple_workflow() -> WorkflowDefinition {
         WorkflowDefinition {
@@ -297,12 +2180,14 @@ mod tests {
                     node_type: NodeType::Trigger,
                     data: NodeData { label: "Start".to_string(), ..Default::default() },
                     position: Position::default(),
+                    parent_id: None,
                 },
                 WorkflowNode {
                     id: "end-1".to_string(),
                     node_type: NodeType::End,
                     data: NodeData { label: "End".to_string(), ..Default::default() },
                     position: Position::default(),
+                    parent_id: None,
                 },
             ],
             edges: vec![WorkflowEdge::new("edge-1", "trigger-1", "end-1")],
@@ -332,6 +2217,7 @@ mod tests {
                 node_type: NodeType::End,
                 data: NodeData { label: "End".to_string(), ..Default::default() },
                 position: Position::default(),
+                parent_id: None,
             }],
             edges: Vec::new(),
             variables: Vec::new(),
@@ -355,6 +2241,7 @@ mod tests {
                 node_type: NodeType::Trigger,
                 data: NodeData { label: "Start".to_string(), ..Default::default() },
                 position: Position::default(),
+                parent_id: None,
             }],
             edges: Vec::new(),
             variables: Vec::new(),
@@ -380,6 +2267,174 @@ mod tests {
         assert!(errors.iter().any(|e| matches!(e, ValidationError::InvalidEdgeSource(_))));
     }
 
+    fn create_conditional_workflow(handles: &[Option<&str>]) -> WorkflowDefinition {
+        let mut workflow = create_simple_workflow();
+        workflow.nodes.push(WorkflowNode {
+            id: "cond-1".to_string(),
+            node_type: NodeType::Conditional,
+            data: NodeData {
+                label: "Check".to_string(),
+                condition: Some("input.ok".to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        });
+        workflow.edges = vec![WorkflowEdge::new("edge-1", "trigger-1", "cond-1")];
+        for (i, handle) in handles.iter().enumerate() {
+            let mut edge = WorkflowEdge::new(format!("branch-{}", i), "cond-1", "end-1");
+            edge.source_handle = handle.map(str::to_string);
+            workflow.edges.push(edge);
+        }
+        workflow
+    }
+
+    #[test]
+    fn test_conditional_branches_valid() {
+        let workflow = create_conditional_workflow(&[Some("true"), Some("false")]);
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_branches(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors.is_empty(), "Expected no errors: {:?}", errors);
+    }
+
+    #[test]
+    fn test_conditional_duplicate_handle() {
+        let workflow = create_conditional_workflow(&[Some("true"), Some("true"), Some("false")]);
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_branches(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::DuplicateBranchHandle { handle, .. } if handle == "true")));
+    }
+
+    #[test]
+    fn test_conditional_missing_false_branch() {
+        let workflow = create_conditional_workflow(&[Some("true")]);
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_branches(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::MissingBranch { handle, .. } if handle == "false")));
+    }
+
+    #[test]
+    fn test_conditional_missing_handle() {
+        let workflow = create_conditional_workflow(&[Some("true"), None]);
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_branches(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::MissingBranchHandle { edge_id, .. } if edge_id == "branch-1")));
+    }
+
+    #[test]
+    fn test_conditional_handle_with_line_break() {
+        let workflow =
+            create_conditional_workflow(&[Some("x');\nprocess.exit(1); //"), Some("default")]);
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_branches(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::InvalidConfig { node_id, message }
+                if node_id == "cond-1" && message.contains("line breaks")
+        )));
+    }
+
+    fn create_loop_workflow(loop_data: NodeData, back_through_loop: bool) -> WorkflowDefinition {
+        let mut workflow = create_simple_workflow();
+        workflow.nodes.push(WorkflowNode {
+            id: "loop-1".to_string(),
+            node_type: NodeType::Loop,
+            data: loop_data,
+            position: Position::default(),
+            parent_id: None,
+        });
+        workflow.nodes.push(WorkflowNode {
+            id: "body-1".to_string(),
+            node_type: NodeType::Activity,
+            data: NodeData {
+                label: "Body".to_string(),
+                activity_name: Some("body".to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        });
+        let back_target = if back_through_loop { "loop-1" } else { "body-1" };
+        workflow.edges = vec![
+            WorkflowEdge::new("edge-1", "trigger-1", "loop-1"),
+            WorkflowEdge::new("edge-2", "loop-1", "body-1"),
+            WorkflowEdge::new("edge-3", "body-1", back_target),
+            WorkflowEdge::new("edge-4", "loop-1", "end-1"),
+        ];
+        workflow
+    }
+
+    #[test]
+    fn test_cycle_through_loop_allowed() {
+        let workflow = create_loop_workflow(
+            NodeData {
+                label: "Loop".to_string(),
+                condition: Some("state.more".to_string()),
+                max_iterations: Some(10),
+                ..Default::default()
+            },
+            true,
+        );
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_graph_structure(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors.is_empty(), "Expected no errors: {:?}", errors);
+    }
+
+    #[test]
+    fn test_cycle_outside_loop_rejected() {
+        let workflow = create_loop_workflow(
+            NodeData {
+                label: "Loop".to_string(),
+                condition: Some("state.more".to_string()),
+                ..Default::default()
+            },
+            false,
+        );
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_graph_structure(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors.iter().any(|e| matches!(e, ValidationError::CycleDetected(_))));
+    }
+
+    #[test]
+    fn test_for_each_loop_requires_collection() {
+        let workflow = create_loop_workflow(
+            NodeData {
+                label: "Loop".to_string(),
+                loop_type: Some("for-each".to_string()),
+                ..Default::default()
+            },
+            true,
+        );
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        validate_nodes(&workflow, &mut errors, &mut warnings);
+
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::MissingRequiredField { field, .. } if field == "collection"
+        )));
+    }
+
     #[test]
     fn test_extract_variable_reference() {
         assert_eq!(
@@ -25,6 +25,66 @@ pub fn validate(workflow: &WorkflowDefinition) -> ValidationResult {
     // Validate edges
     graph::validate_edges(workflow, &mut errors, &mut warnings);
 
+    // Validate conditional branches
+    graph::validate_branches(workflow, &mut errors, &mut warnings);
+
+    // Validate the error edges of nodes whose failures are caught
+    graph::validate_error_branches(workflow, &mut errors, &mut warnings);
+
+    // Validate the cleanup branches run on cancellation
+    graph::validate_cancellation(workflow, &mut errors, &mut warnings);
+
+    // Validate parallel fan-out and fan-in
+    graph::validate_parallel(workflow, &mut errors, &mut warnings);
+
+    // Validate map bodies and the history they add per item
+    graph::validate_maps(workflow, &mut errors, &mut warnings);
+
+    // Validate phase membership and nesting
+    graph::validate_phases(workflow, &mut errors, &mut warnings);
+
+    // Validate DataIn/DataOut field schemas
+    graph::validate_data_fields(workflow, &mut errors, &mut warnings);
+
+    // Validate signal names, payloads and target variables
+    graph::validate_signals(workflow, &mut errors, &mut warnings);
+
+    // Validate update handlers and their names
+    graph::validate_updates(workflow, &mut errors, &mut warnings);
+
+    // Validate approval signals, timeouts and outcome branches
+    graph::validate_approvals(workflow, &mut errors, &mut warnings);
+
+    // Validate agent tools, turn limits and output schemas
+    graph::validate_agents(workflow, &mut errors, &mut warnings);
+
+    // Validate child workflow start options
+    graph::validate_child_workflows(workflow, &mut errors, &mut warnings);
+
+    // Validate the workflow retry policy and per-node overrides
+    graph::validate_retry_policies(workflow, &mut errors, &mut warnings);
+
+    // Validate compensation activities against the Activities interface
+    graph::validate_compensations(workflow, &mut errors, &mut warnings);
+
+    // Validate timeout and TTL durations
+    graph::validate_timeouts(workflow, &mut errors, &mut warnings);
+
+    // Validate timer delays and the signals they race
+    graph::validate_timers(workflow, &mut errors, &mut warnings);
+
+    // Validate queries exposed on the workflow
+    graph::validate_queries(workflow, &mut errors, &mut warnings);
+
+    // Validate references in node input mappings
+    graph::validate_input_mappings(workflow, &mut errors, &mut warnings);
+
+    // Validate conditions, mappings and templates written in the expression language
+    graph::validate_expressions(workflow, &mut errors, &mut warnings);
+
+    // Check that values flowing between nodes have compatible types
+    graph::validate_types(workflow, &mut errors, &mut warnings);
+
     ValidationResult {
         valid: errors.is_empty(),
         errors,
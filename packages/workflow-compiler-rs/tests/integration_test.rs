@@ -1,9 +1,15 @@
 //! Integration tests for the workflow compiler
 
+use std::collections::BTreeMap;
+
 use serde_json::json;
 use workflow_compiler::{
     codegen::{self, CodeGenOptions},
-    schema::{NodeData, NodeType, Position, WorkflowDefinition, WorkflowEdge, WorkflowNode},
+    schema::{
+        DataField, NodeData, NodeType, Position, QuerySource, RetryPolicy, RetryStrategy,
+        VariableType, WorkflowDefinition, WorkflowEdge, WorkflowMetadata, WorkflowNode,
+        WorkflowQuery, WorkflowVariable,
+    },
     validation,
 };
 
@@ -20,6 +26,7 @@ fn create_simple_workflow() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 0.0, y: 0.0 },
+                parent_id: None,
             },
             WorkflowNode {
                 id: "activity-1".to_string(),
@@ -31,6 +38,7 @@ fn create_simple_workflow() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 100.0, y: 0.0 },
+                parent_id: None,
             },
             WorkflowNode {
                 id: "end-1".to_string(),
@@ -40,6 +48,7 @@ fn create_simple_workflow() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 200.0, y: 0.0 },
+                parent_id: None,
             },
         ],
         edges: vec![
@@ -66,6 +75,7 @@ fn create_workflow_with_signal() -> WorkflowDefinition {
                 ..Default::default()
             },
             position: Position { x: 150.0, y: 0.0 },
+            parent_id: None,
         },
     );
 
@@ -92,6 +102,7 @@ fn create_workflow_with_conditional() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 0.0, y: 0.0 },
+                parent_id: None,
             },
             WorkflowNode {
                 id: "condition-1".to_string(),
@@ -102,6 +113,7 @@ fn create_workflow_with_conditional() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 100.0, y: 0.0 },
+                parent_id: None,
             },
             WorkflowNode {
                 id: "activity-approved".to_string(),
@@ -112,6 +124,7 @@ fn create_workflow_with_conditional() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 200.0, y: -50.0 },
+                parent_id: None,
             },
             WorkflowNode {
                 id: "activity-rejected".to_string(),
@@ -122,6 +135,7 @@ fn create_workflow_with_conditional() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 200.0, y: 50.0 },
+                parent_id: None,
             },
             WorkflowNode {
                 id: "end-1".to_string(),
@@ -131,12 +145,13 @@ fn create_workflow_with_conditional() -> WorkflowDefinition {
                     ..Default::default()
                 },
                 position: Position { x: 300.0, y: 0.0 },
+                parent_id: None,
             },
         ],
         edges: vec![
             WorkflowEdge::new("edge-1", "trigger-1", "condition-1"),
-            WorkflowEdge::new("edge-2", "condition-1", "activity-approved"),
-            WorkflowEdge::new("edge-3", "condition-1", "activity-rejected"),
+            WorkflowEdge::new("edge-2", "condition-1", "activity-approved").with_source_handle("true"),
+            WorkflowEdge::new("edge-3", "condition-1", "activity-rejected").with_source_handle("false"),
             WorkflowEdge::new("edge-4", "activity-approved", "end-1"),
             WorkflowEdge::new("edge-5", "activity-rejected", "end-1"),
         ],
@@ -231,6 +246,7 @@ fn test_validation_no_trigger() {
                 ..Default::default()
             },
             position: Position::default(),
+            parent_id: None,
         }],
         edges: vec![],
         variables: vec![],
@@ -258,6 +274,7 @@ fn test_validation_no_end() {
                 ..Default::default()
             },
             position: Position::default(),
+            parent_id: None,
         }],
         edges: vec![],
         variables: vec![],
@@ -283,12 +300,14 @@ fn test_validation_duplicate_node_id() {
                 node_type: NodeType::Trigger,
                 data: NodeData::default(),
                 position: Position::default(),
+                parent_id: None,
             },
             WorkflowNode {
                 id: "same-id".to_string(),
                 node_type: NodeType::End,
                 data: NodeData::default(),
                 position: Position::default(),
+                parent_id: None,
             },
         ],
         edges: vec![WorkflowEdge::new("edge-1", "same-id", "same-id")],
@@ -364,6 +383,7 @@ fn test_codegen_options() {
         default_timeout: "5m".to_string(),
         include_comments: true,
         strict_mode: true,
+        ..CodeGenOptions::new()
     };
 
     let workflow = create_simple_workflow();
@@ -376,3 +396,2804 @@ fn test_codegen_options() {
     assert!(code.workflow.contains("customNameWorkflow"));
     assert!(code.workflow.contains("5m"));
 }
+
+#[test]
+fn test_codegen_follows_edges_not_node_order() {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.push(WorkflowNode {
+        id: "activity-0".to_string(),
+        node_type: NodeType::Activity,
+        data: NodeData {
+            label: "Fetch Data".to_string(),
+            activity_name: Some("fetchData".to_string()),
+            ..Default::default()
+        },
+        position: Position { x: 50.0, y: 0.0 },
+        parent_id: None,
+    });
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "activity-0"),
+        WorkflowEdge::new("edge-2", "activity-0", "activity-1"),
+        WorkflowEdge::new("edge-3", "activity-1", "end-1"),
+    ];
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+
+    let fetch = code.workflow.find("acts.fetchData(").expect("fetchData call");
+    let process = code.workflow.find("acts.processData(").expect("processData call");
+    let stop = code.workflow.find("[WORKFLOW_STOP]").expect("stop code");
+    assert!(fetch < process, "fetchData must run before processData");
+    assert!(process < stop, "processData must run before the end node");
+}
+
+#[test]
+fn test_codegen_emits_join_after_all_branches() {
+    let workflow = create_workflow_with_conditional();
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+
+    let approved = code.workflow.find("acts.processApproved(").unwrap();
+    let rejected = code.workflow.find("acts.processRejected(").unwrap();
+    let stop = code.workflow.find("[WORKFLOW_STOP]").unwrap();
+    assert!(approved < stop && rejected < stop);
+    assert_eq!(code.workflow.matches("[WORKFLOW_STOP]").count(), 1);
+}
+
+#[test]
+fn test_codegen_skips_unreachable_nodes() {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.push(WorkflowNode {
+        id: "island".to_string(),
+        node_type: NodeType::Activity,
+        data: NodeData {
+            label: "Island".to_string(),
+            activity_name: Some("island".to_string()),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(!code.workflow.contains("acts.island("));
+}
+
+#[test]
+fn test_generate_conditional_branches() {
+    let workflow = create_workflow_with_conditional();
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+
+    let if_pos = code.workflow.find("if (input.status === 'approved') {").unwrap();
+    let approved = code.workflow.find("acts.processApproved(").unwrap();
+    let else_pos = code.workflow.find("} else {").unwrap();
+    let rejected = code.workflow.find("acts.processRejected(").unwrap();
+    let stop = code.workflow.find("[WORKFLOW_STOP]").unwrap();
+
+    assert!(if_pos < approved && approved < else_pos);
+    assert!(else_pos < rejected && rejected < stop);
+    assert!(!code.workflow.contains("TODO: condition body"));
+}
+
+#[test]
+fn test_generate_switch_for_named_branches() {
+    let mut workflow = create_workflow_with_conditional();
+    workflow.nodes[1].data.condition = Some("input.status".to_string());
+    workflow.edges[1] = WorkflowEdge::new("edge-2", "condition-1", "activity-approved")
+        .with_source_handle("approved");
+    workflow.edges[2] = WorkflowEdge::new("edge-3", "condition-1", "activity-rejected")
+        .with_source_handle("default");
+
+    assert!(validation::validate(&workflow).valid);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("switch (input.status) {"));
+    assert!(code.workflow.contains("case 'approved': {"));
+    assert!(code.workflow.contains("default: {"));
+}
+
+#[test]
+fn test_generate_switch_quotes_handles() {
+    let mut workflow = create_workflow_with_conditional();
+    workflow.nodes[1].data.condition = Some("input.status".to_string());
+    workflow.edges[1] = WorkflowEdge::new("edge-2", "condition-1", "activity-approved")
+        .with_source_handle("gold\\");
+    workflow.edges[2] = WorkflowEdge::new("edge-3", "condition-1", "activity-rejected")
+        .with_source_handle("x');\nprocess.exit(1); //");
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("case 'gold\\\\': {"));
+    assert!(code
+        .workflow
+        .contains("case 'x\\');\\nprocess.exit(1); //': {"));
+    assert!(!code.workflow.contains("\nprocess.exit(1);"));
+}
+
+#[test]
+fn test_validation_conditional_without_handles() {
+    let mut workflow = create_workflow_with_conditional();
+    workflow.edges[1].source_handle = None;
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("MissingBranchHandle")));
+}
+
+fn create_workflow_with_loop(loop_data: NodeData) -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.push(WorkflowNode {
+        id: "loop-1".to_string(),
+        node_type: NodeType::Loop,
+        data: loop_data,
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "loop-1"),
+        WorkflowEdge::new("edge-2", "loop-1", "activity-1").with_source_handle("body"),
+        WorkflowEdge::new("edge-3", "activity-1", "loop-1").as_loop_back(),
+        WorkflowEdge::new("edge-4", "loop-1", "end-1").with_source_handle("done"),
+    ];
+    workflow
+}
+
+#[test]
+fn test_generate_for_each_loop() {
+    let workflow = create_workflow_with_loop(NodeData {
+        label: "Each Item".to_string(),
+        collection: Some("input.items".to_string()),
+        item_variable: Some("item".to_string()),
+        max_iterations: Some(50),
+        ..Default::default()
+    });
+
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    let loop_pos = code
+        .workflow
+        .find("for (const item of loop_1_items.slice(0, 50)) {")
+        .expect("for-each loop");
+    let body = code.workflow.find("acts.processData(").unwrap();
+    let stop = code.workflow.find("[WORKFLOW_STOP]").unwrap();
+    assert!(loop_pos < body && body < stop);
+}
+
+#[test]
+fn test_validation_cycle_without_loop_node() {
+    let mut workflow = create_simple_workflow();
+    workflow
+        .edges
+        .push(WorkflowEdge::new("edge-3", "activity-1", "trigger-1"));
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("CycleDetected")));
+}
+
+#[test]
+fn test_validation_loop_back_edge_must_target_loop() {
+    let mut workflow = create_workflow_with_loop(NodeData {
+        label: "Loop".to_string(),
+        max_iterations: Some(3),
+        ..Default::default()
+    });
+    workflow.edges[2] = WorkflowEdge::new("edge-3", "activity-1", "trigger-1").as_loop_back();
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("not a loop node")));
+}
+
+fn create_fan_out_workflow(fan_out_type: NodeType) -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes[1] = WorkflowNode {
+        id: "fan-out".to_string(),
+        node_type: fan_out_type,
+        data: NodeData {
+            label: "Fan Out".to_string(),
+            activity_name: Some("prepare".to_string()),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    };
+    for name in ["chargeCard", "reserveStock"] {
+        workflow.nodes.push(WorkflowNode {
+            id: name.to_string(),
+            node_type: NodeType::Activity,
+            data: NodeData {
+                label: name.to_string(),
+                activity_name: Some(name.to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        });
+    }
+    workflow.nodes.push(WorkflowNode {
+        id: "join-1".to_string(),
+        node_type: NodeType::Join,
+        data: NodeData {
+            label: "Join".to_string(),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "fan-out"),
+        WorkflowEdge::new("edge-2", "fan-out", "chargeCard"),
+        WorkflowEdge::new("edge-3", "fan-out", "reserveStock"),
+        WorkflowEdge::new("edge-4", "chargeCard", "join-1"),
+        WorkflowEdge::new("edge-5", "reserveStock", "join-1"),
+        WorkflowEdge::new("edge-6", "join-1", "end-1"),
+    ];
+    workflow
+}
+
+#[test]
+fn test_generate_parallel_fan_out() {
+    let workflow = create_fan_out_workflow(NodeType::Activity);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    let prepare = code.workflow.find("acts.prepare(").unwrap();
+    let all = code.workflow.find("await Promise.all([").expect("Promise.all block");
+    let charge = code.workflow.find("acts.chargeCard(").unwrap();
+    let reserve = code.workflow.find("acts.reserveStock(").unwrap();
+    let stop = code.workflow.find("[WORKFLOW_STOP]").unwrap();
+
+    assert!(prepare < all && all < charge && all < reserve);
+    assert!(charge < stop && reserve < stop);
+    assert_eq!(code.workflow.matches("(async () => {").count(), 2);
+}
+
+#[test]
+fn test_generate_parallel_all_settled() {
+    let mut workflow = create_fan_out_workflow(NodeType::Parallel);
+    workflow.nodes[1].data.parallel_mode = Some("all-settled".to_string());
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("const fan_out_settled = await Promise.allSettled(["));
+    assert!(code.workflow.contains("outcome.status === 'rejected'"));
+    assert!(!code.workflow.contains("acts.prepare("));
+}
+
+#[test]
+fn test_generate_parallel_branch_results() {
+    // The End node combines what both branches produced
+    let mut workflow = create_fan_out_workflow(NodeType::Parallel);
+    workflow.nodes[2].data.result_mapping = Some(
+        "{ charge: nodes.chargeCard.output, stock: nodes.reserveStock.output.sku }".to_string(),
+    );
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "const [{ chargeCard_result }, { reserveStock_result }] = await Promise.all([\n    \
+         (async () => {\n      \
+         const chargeCard_result = await acts.chargeCard(input);\n      \
+         return { chargeCard_result };\n    })(),"
+    ));
+    assert!(code
+        .workflow
+        .contains("{ charge: chargeCard_result, stock: reserveStock_result.sku }"));
+
+    // With allSettled, a failed branch leaves its results undefined
+    workflow.nodes[1].data.parallel_mode = Some("all-settled".to_string());
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "console.warn('[PARALLEL_BRANCH_FAILED]', { nodeId: 'fan-out', reason: outcome.reason });"
+    ));
+    assert!(code.workflow.contains(
+        "  const [fan_out_branch_1, fan_out_branch_2] = fan_out_settled;\n  \
+         const { chargeCard_result } =\n    \
+         fan_out_branch_1.status === 'fulfilled' ? fan_out_branch_1.value : \
+         { chargeCard_result: undefined };\n"
+    ));
+
+    // A branch cannot read the result of a branch running beside it
+    let mut workflow = create_fan_out_workflow(NodeType::Parallel);
+    workflow.nodes[3].data.input_mapping = Some(BTreeMap::from([(
+        "stock".to_string(),
+        "{{nodes.reserveStock.output}}".to_string(),
+    )]));
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    assert!(messages[0].contains("'chargeCard'") && messages[0].contains("'reserveStock'"));
+}
+
+#[test]
+fn test_validation_parallel_without_join() {
+    let mut workflow = create_fan_out_workflow(NodeType::Parallel);
+    workflow.edges.truncate(3);
+    workflow.edges.push(WorkflowEdge::new("edge-4", "chargeCard", "end-1"));
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("never re-join")));
+}
+
+fn create_workflow_with_data_schema() -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.push(WorkflowNode {
+        id: "data-in".to_string(),
+        node_type: NodeType::DataIn,
+        data: NodeData {
+            label: "Order Input".to_string(),
+            fields: Some(vec![
+                DataField::new("orderId", VariableType::String)
+                    .required()
+                    .with_description("Order to process"),
+                DataField::new("priority", VariableType::Number),
+            ]),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.nodes.push(WorkflowNode {
+        id: "data-out".to_string(),
+        node_type: NodeType::DataOut,
+        data: NodeData {
+            label: "Order Result".to_string(),
+            fields: Some(vec![DataField::new("status", VariableType::String).required()]),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.nodes[2].data.result_mapping = Some("{ status: 'done' }".to_string());
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "data-in"),
+        WorkflowEdge::new("edge-2", "data-in", "activity-1"),
+        WorkflowEdge::new("edge-3", "activity-1", "data-out"),
+        WorkflowEdge::new("edge-4", "data-out", "end-1"),
+    ];
+    workflow
+}
+
+#[test]
+fn test_generate_typed_input_and_result() {
+    let workflow = create_workflow_with_data_schema();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("export interface testWorkflowWorkflowInput {"));
+    assert!(code.workflow.contains("/** Order to process */"));
+    assert!(code.workflow.contains("orderId: string;"));
+    assert!(code.workflow.contains("priority?: number;"));
+    assert!(!code.workflow.contains("[key: string]: unknown;"));
+
+    assert!(code.workflow.contains("export interface testWorkflowWorkflowResult {"));
+    assert!(code.workflow.contains("status: string;"));
+    assert!(code.workflow.contains("result?: testWorkflowWorkflowResult;"));
+    assert!(code
+        .workflow
+        .contains("const finalResult: testWorkflowWorkflowResult = { status: 'done' };"));
+}
+
+#[test]
+fn test_generate_untyped_input_without_data_nodes() {
+    let workflow = create_simple_workflow();
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+
+    assert!(code.workflow.contains("[key: string]: unknown;"));
+    assert!(code.workflow.contains("result?: unknown;"));
+}
+
+#[test]
+fn test_validation_duplicate_data_field() {
+    let mut workflow = create_workflow_with_data_schema();
+    workflow.nodes[3]
+        .data
+        .fields
+        .as_mut()
+        .unwrap()
+        .push(DataField::new("orderId", VariableType::Number));
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("declared more than once")));
+}
+
+#[test]
+fn test_data_field_description_stays_in_its_comment() {
+    let mut workflow = create_workflow_with_data_schema();
+    workflow.nodes[3].data.fields.as_mut().unwrap()[1].description =
+        Some("Rush */ process.exit(1); /*\nwhen a = 'high'".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("must not contain '*/'")));
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("/** Rush *\\/ process.exit(1); /* when a = 'high' */"));
+    assert!(!code.workflow.contains("&#x"));
+}
+
+fn create_workflow_with_input_mapping(value: &str) -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.insert(
+        1,
+        WorkflowNode {
+            id: "fetch-order".to_string(),
+            node_type: NodeType::Activity,
+            data: NodeData {
+                label: "Fetch Order".to_string(),
+                activity_name: Some("fetchOrder".to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        },
+    );
+    workflow.nodes[2].data.input_mapping = Some(
+        [
+            ("orderId".to_string(), value.to_string()),
+            ("note".to_string(), "Order {{input.ref}}".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "fetch-order"),
+        WorkflowEdge::new("edge-2", "fetch-order", "activity-1"),
+        WorkflowEdge::new("edge-3", "activity-1", "end-1"),
+    ];
+    workflow
+}
+
+#[test]
+fn test_generate_input_mapping_arguments() {
+    let workflow = create_workflow_with_input_mapping("{{nodes.fetch-order.output.id}}");
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("const fetch_order_result = await acts.fetchOrder(input);"));
+    assert!(code.workflow.contains(
+        "const activity_1_result = await acts.processData({ note: `Order ${input.ref}`, orderId: fetch_order_result.id });"
+    ));
+}
+
+#[test]
+fn test_validation_input_mapping_unknown_node() {
+    let workflow = create_workflow_with_input_mapping("{{nodes.missing.output}}");
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("UnknownNodeReference")));
+}
+
+#[test]
+fn test_validation_input_mapping_rejects_later_node() {
+    let mut workflow = create_workflow_with_input_mapping("{{input.orderId}}");
+    workflow.nodes[1].data.input_mapping = Some(
+        [("previous".to_string(), "{{nodes.activity-1.output}}".to_string())]
+            .into_iter()
+            .collect(),
+    );
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("NodeReferenceNotAvailable")));
+}
+
+#[test]
+fn test_validation_input_mapping_rejects_sibling_branch() {
+    let mut workflow = create_workflow_with_conditional();
+    workflow.nodes[3].data.input_mapping = Some(
+        [("approval".to_string(), "{{nodes.activity-approved.output}}".to_string())]
+            .into_iter()
+            .collect(),
+    );
+
+    let result = validation::validate(&workflow);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| format!("{:?}", e).contains("NodeReferenceNotAvailable")));
+}
+
+#[test]
+fn test_validation_reports_expression_span() {
+    let mut workflow = create_workflow_with_conditional();
+    workflow.nodes[1].data.condition = Some("input.status === 'approved' && eval('x')".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    let error = result
+        .errors
+        .iter()
+        .find_map(|e| match e {
+            validation::ValidationError::InvalidExpression {
+                node_id,
+                field,
+                span,
+                ..
+            } => Some((node_id.clone(), field.clone(), *span)),
+            _ => None,
+        })
+        .expect("InvalidExpression error");
+    assert_eq!(error.0, "condition-1");
+    assert_eq!(error.1, "condition");
+    assert_eq!((error.2.start, error.2.end), (31, 35));
+}
+
+#[test]
+fn test_codegen_rejects_injected_code() {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes[2].data.result_mapping =
+        Some("input; await fetch('https://example.com')".to_string());
+
+    assert!(!validation::validate(&workflow).valid);
+    assert!(codegen::generate(&workflow, &CodeGenOptions::new()).is_err());
+}
+
+#[test]
+fn test_validation_rejects_unknown_expression_names() {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes[2].data.result_mapping = Some("{ env: process.env }".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| e.to_string().contains("unknown name 'process'")));
+}
+
+#[test]
+fn test_generate_compiled_log_template() {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes[1].data.activity_name = Some("log".to_string());
+    workflow.nodes[1].data.log_message =
+        Some("Processing `${upper(input.name ?? 'unnamed')}`".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("`Processing \\`${String(input.name ?? 'unnamed').toUpperCase()}\\``"));
+}
+
+fn type_mismatches(workflow: &WorkflowDefinition) -> Vec<(String, String, String)> {
+    validation::validate(workflow)
+        .errors
+        .into_iter()
+        .filter_map(|e| match e {
+            validation::ValidationError::TypeMismatch {
+                node_id,
+                expected,
+                found,
+            } => Some((node_id, expected, found)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_validation_result_mapping_type_mismatch() {
+    let mut workflow = create_workflow_with_data_schema();
+    workflow.nodes[2].data.result_mapping = Some("{ status: input.priority }".to_string());
+
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![(
+            "end-1".to_string(),
+            "{ status: string }".to_string(),
+            "{ status: number }".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_validation_operand_type_mismatch() {
+    let mut workflow = create_workflow_with_data_schema();
+    workflow.nodes[2].data.result_mapping =
+        Some("{ status: upper(input.orderId) + '-' + input.priority * input.orderId }".to_string());
+
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![(
+            "end-1".to_string(),
+            "number".to_string(),
+            "string".to_string()
+        )]
+    );
+}
+
+#[test]
+fn test_validation_input_mapping_type_mismatch() {
+    let mut workflow = create_workflow_with_data_schema();
+    workflow.nodes[1].data.component_name = Some("Log".to_string());
+    workflow.nodes[1].data.log_message = Some("Processing".to_string());
+    workflow.nodes[1].data.input_mapping = Some(BTreeMap::from([(
+        "message".to_string(),
+        "{{input.priority}}".to_string(),
+    )]));
+
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![(
+            "activity-1".to_string(),
+            "string".to_string(),
+            "number".to_string()
+        )]
+    );
+
+    // Interpolating the value into text produces a string
+    workflow.nodes[1].data.input_mapping = Some(BTreeMap::from([(
+        "message".to_string(),
+        "Priority {{input.priority}}".to_string(),
+    )]));
+    assert!(type_mismatches(&workflow).is_empty());
+}
+
+#[test]
+fn test_validation_node_output_types_propagate() {
+    let mut workflow = create_workflow_with_data_schema();
+    workflow.variables = vec![WorkflowVariable::new("attempts", VariableType::Number)];
+    workflow.nodes[1] = WorkflowNode {
+        id: "read-attempts".to_string(),
+        node_type: NodeType::StateVariable,
+        data: NodeData {
+            label: "Read attempts".to_string(),
+            component_name: Some("GetVariable".to_string()),
+            variable_name: Some("attempts".to_string()),
+            variable_scope: Some("workflow".to_string()),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    };
+    workflow.edges[1].target = "read-attempts".to_string();
+    workflow.edges[2].source = "read-attempts".to_string();
+
+    workflow.nodes[2].data.result_mapping =
+        Some("{ status: nodes['read-attempts'].output.exists }".to_string());
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![(
+            "end-1".to_string(),
+            "{ status: string }".to_string(),
+            "{ status: boolean }".to_string()
+        )]
+    );
+
+    workflow.nodes[2].data.result_mapping =
+        Some("{ status: toString(nodes['read-attempts'].output.value + 1) }".to_string());
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+}
+
+#[test]
+fn test_validation_loop_collection_must_be_array() {
+    let workflow = create_workflow_with_loop(NodeData {
+        label: "Each Item".to_string(),
+        collection: Some("length(input.items)".to_string()),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![(
+            "loop-1".to_string(),
+            "unknown[]".to_string(),
+            "number".to_string()
+        )]
+    );
+}
+
+fn create_workflow_with_typed_signal() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_signal();
+    workflow.variables = vec![WorkflowVariable::new("decision", VariableType::Object)];
+    let signal = &mut workflow.nodes[2].data;
+    signal.wait_for_signal = Some(true);
+    signal.timeout = Some("24h".to_string());
+    signal.variable_name = Some("decision".to_string());
+    signal.fields = Some(vec![
+        DataField::new("approved", VariableType::Boolean).required(),
+        DataField::new("comment", VariableType::String),
+    ]);
+    workflow.nodes[3].data.result_mapping = Some(
+        "{ approved: nodes['signal-1'].output.received && nodes['signal-1'].output.payload.approved }"
+            .to_string(),
+    );
+    workflow
+}
+
+#[test]
+fn test_generate_typed_signal_with_wait() {
+    let workflow = create_workflow_with_typed_signal();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("export interface ApprovalSignalPayload {"));
+    assert!(code.workflow.contains("  comment?: string;"));
+    assert!(code
+        .workflow
+        .contains("export const approvalSignal = defineSignal<[ApprovalSignalPayload]>('approval');"));
+
+    // The handler is registered once, up front, and records the payload in state
+    assert_eq!(code.workflow.matches("setHandler(approvalSignal").count(), 1);
+    assert!(code
+        .workflow
+        .contains("signals.approval = { received: true, payload };"));
+    assert!(code
+        .workflow
+        .contains("state.decision = payload as WorkflowState['decision'];"));
+
+    let handler = code.workflow.find("setHandler(approvalSignal").unwrap();
+    let wait = code
+        .workflow
+        .find("await condition(() => signals.approval.received, '24h');")
+        .expect("signal wait");
+    let result_mapping = code.workflow.find("const finalResult").unwrap();
+    assert!(handler < wait && wait < result_mapping);
+    assert!(code
+        .workflow
+        .contains("const signal_1_result = { ...signals.approval };"));
+    assert!(code
+        .workflow
+        .contains("signal_1_result.received && signal_1_result.payload.approved"));
+}
+
+#[test]
+fn test_signal_payload_description_stays_in_its_comment() {
+    let mut workflow = create_workflow_with_typed_signal();
+    workflow.nodes[2].data.fields.as_mut().unwrap()[1].description =
+        Some("Why */ process.exit(1); /*\nif approved = 'no'".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| e
+        .to_string()
+        .contains("description of field 'comment' must not contain '*/'")));
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("  /** Why *\\/ process.exit(1); /* if approved = 'no' */\n  comment?: string;"));
+    assert!(!code.workflow.contains("&#x"));
+}
+
+#[test]
+fn test_validation_signal_payload_conflict() {
+    let mut workflow = create_workflow_with_typed_signal();
+    let mut second = workflow.nodes[2].clone();
+    second.id = "signal-2".to_string();
+    second.data.fields = Some(vec![DataField::new("approved", VariableType::String)]);
+    workflow.nodes.push(second);
+    workflow.edges[2].target = "signal-2".to_string();
+    workflow
+        .edges
+        .push(WorkflowEdge::new("edge-4", "signal-2", "end-1"));
+
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| e
+        .to_string()
+        .contains("signal 'approval' is declared with a different payload on node 'signal-1'")));
+}
+
+#[test]
+fn test_validation_signal_state_variable() {
+    let mut workflow = create_workflow_with_typed_signal();
+    workflow.variables = vec![WorkflowVariable::new("decision", VariableType::String)];
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![(
+            "signal-1".to_string(),
+            "string".to_string(),
+            "{ approved: boolean; comment: string }".to_string()
+        )]
+    );
+
+    workflow.variables.clear();
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| matches!(
+        e,
+        validation::ValidationError::UnknownVariable { var_name, .. } if var_name == "decision"
+    )));
+}
+
+#[test]
+fn test_validation_signal_name_must_be_identifier_like() {
+    let mut workflow = create_workflow_with_signal();
+    workflow.nodes[2].data.signal_name = Some("order.approved".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(!result.valid);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| e.to_string().contains("signal name 'order.approved'")));
+}
+
+fn create_workflow_with_queries(queries: Vec<WorkflowQuery>) -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.variables = vec![
+        WorkflowVariable::new("status", VariableType::String),
+        WorkflowVariable::new("attempts", VariableType::Number),
+    ];
+    workflow.metadata = Some(WorkflowMetadata {
+        queries,
+        ..Default::default()
+    });
+    workflow
+}
+
+fn query_errors(workflow: &WorkflowDefinition) -> Vec<String> {
+    validation::validate(workflow)
+        .errors
+        .iter()
+        .filter(|e| matches!(e, validation::ValidationError::InvalidQuery { .. }))
+        .map(|e| e.to_string())
+        .collect()
+}
+
+#[test]
+fn test_generate_variable_queries() {
+    let workflow = create_workflow_with_queries(vec![
+        WorkflowQuery::new(
+            "getStatus",
+            QuerySource::Variables {
+                variables: vec!["status".to_string()],
+            },
+        )
+        .with_description("Current order status"),
+        WorkflowQuery::new("get-state", QuerySource::Variables { variables: vec![] }),
+    ]);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("  defineQuery,"));
+    assert!(code.workflow.contains("/** Current order status */"));
+    assert!(code
+        .workflow
+        .contains("export const getStatusQuery = defineQuery<{ status: string }>('getStatus');"));
+    assert!(code.workflow.contains(
+        "export const getStateQuery = defineQuery<{ status: string; attempts: number }>('get-state');"
+    ));
+    assert!(code
+        .workflow
+        .contains("setHandler(getStatusQuery, () => ({ status: state.status }));"));
+    assert!(code.workflow.contains(
+        "setHandler(getStateQuery, () => ({ status: state.status, attempts: state.attempts }));"
+    ));
+
+    // Handlers are registered before the workflow body runs
+    let handler = code.workflow.find("setHandler(getStatusQuery").unwrap();
+    let body = code.workflow.find("// Workflow execution").unwrap();
+    assert!(handler < body);
+
+    // No phase or progress tracking unless a query reads it
+    assert!(!code.workflow.contains("currentPhase"));
+    assert!(!code.workflow.contains("progress.currentStep"));
+}
+
+#[test]
+fn test_generate_phase_and_progress_queries() {
+    let mut workflow = create_workflow_with_queries(vec![
+        WorkflowQuery::new("currentPhase", QuerySource::CurrentPhase),
+        WorkflowQuery::new("progress", QuerySource::Progress),
+    ]);
+    workflow.nodes.insert(
+        1,
+        WorkflowNode {
+            id: "phase-1".to_string(),
+            node_type: NodeType::Phase,
+            data: NodeData {
+                label: "Processing".to_string(),
+                ..Default::default()
+            },
+            position: Position { x: 50.0, y: 0.0 },
+            parent_id: None,
+        },
+    );
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "phase-1"),
+        WorkflowEdge::new("edge-2", "phase-1", "activity-1"),
+        WorkflowEdge::new("edge-3", "activity-1", "end-1"),
+    ];
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("export const currentPhaseQuery = defineQuery<string | undefined>('currentPhase');"));
+    assert!(code
+        .workflow
+        .contains("export const progressQuery = defineQuery<WorkflowProgress>('progress');"));
+    assert!(code.workflow.contains("export interface WorkflowProgress {"));
+    assert!(code.workflow.contains("let currentPhase: string | undefined;"));
+    assert!(code.workflow.contains("totalSteps: 4,"));
+    assert!(code.workflow.contains("currentPhase = 'Processing';"));
+
+    // Each step is marked current before it runs and completed after it
+    let current = code.workflow.find("progress.currentStep = 'activity-1';").unwrap();
+    let activity = code.workflow.find("acts.processData").unwrap();
+    let completed = code.workflow.find("progress.completed.add('activity-1');").unwrap();
+    assert!(current < activity && activity < completed);
+
+    // The end node returns, so it is counted as completed before its code
+    let end_completed = code.workflow.find("progress.completed.add('end-1');").unwrap();
+    let end = code.workflow.find("const finalResult").unwrap();
+    assert!(end_completed < end);
+}
+
+#[test]
+fn test_validation_query_errors() {
+    let workflow = create_workflow_with_queries(vec![WorkflowQuery::new(
+        "getStatus",
+        QuerySource::Variables {
+            variables: vec!["missing".to_string()],
+        },
+    )]);
+    assert_eq!(
+        query_errors(&workflow),
+        vec!["Query 'getStatus' is invalid: unknown variable 'missing'"]
+    );
+
+    let workflow = create_workflow_with_queries(vec![
+        WorkflowQuery::new("progress", QuerySource::Progress),
+        WorkflowQuery::new("progress", QuerySource::CurrentPhase),
+        WorkflowQuery::new("get.status", QuerySource::Progress),
+    ]);
+    assert_eq!(
+        query_errors(&workflow),
+        vec![
+            "Query 'progress' is invalid: query is declared more than once",
+            "Query 'get.status' is invalid: query names may only contain letters, digits, '_' and '-'",
+        ]
+    );
+}
+
+#[test]
+fn test_query_description_stays_in_its_comment() {
+    let query = WorkflowQuery::new("progress", QuerySource::Progress)
+        .with_description("Steps */ process.exit(1); /*\nwhere done = 'yes'");
+    let workflow = create_workflow_with_queries(vec![query]);
+    assert_eq!(
+        query_errors(&workflow),
+        vec!["Query 'progress' is invalid: description must not contain '*/'"]
+    );
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "/** Steps *\\/ process.exit(1); /* where done = 'yes' */\nexport const progressQuery"
+    ));
+    assert!(!code.workflow.contains("&#x"));
+}
+
+#[test]
+fn test_validation_query_name_clashes_with_signal() {
+    let mut workflow = create_workflow_with_signal();
+    workflow.metadata = Some(WorkflowMetadata {
+        queries: vec![WorkflowQuery::new("approval", QuerySource::Progress)],
+        ..Default::default()
+    });
+    assert_eq!(
+        query_errors(&workflow),
+        vec!["Query 'approval' is invalid: a signal with the same name exists"]
+    );
+}
+
+fn create_workflow_with_update() -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.variables = vec![WorkflowVariable::new("total", VariableType::Number)];
+    workflow.nodes.insert(
+        2,
+        WorkflowNode {
+            id: "update-1".to_string(),
+            node_type: NodeType::Update,
+            data: NodeData {
+                label: "Apply Discount".to_string(),
+                update_name: Some("set-discount".to_string()),
+                wait_for_signal: Some(true),
+                validator: Some("update.percent > 0 && update.percent <= 50".to_string()),
+                return_value: Some(
+                    "{ total: state.total * (100 - update.percent) / 100 }".to_string(),
+                ),
+                fields: Some(vec![DataField::new("percent", VariableType::Number).required()]),
+                return_fields: Some(vec![DataField::new("total", VariableType::Number).required()]),
+                ..Default::default()
+            },
+            position: Position { x: 150.0, y: 0.0 },
+            parent_id: None,
+        },
+    );
+    workflow.edges = vec![
+        WorkflowEdge::new("edge-1", "trigger-1", "activity-1"),
+        WorkflowEdge::new("edge-2", "activity-1", "update-1"),
+        WorkflowEdge::new("edge-3", "update-1", "end-1"),
+    ];
+    workflow
+}
+
+fn invalid_config_messages(workflow: &WorkflowDefinition) -> Vec<String> {
+    validation::validate(workflow)
+        .errors
+        .iter()
+        .filter_map(|e| match e {
+            validation::ValidationError::InvalidConfig { message, .. } => Some(message.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_generate_update_with_validator() {
+    let workflow = create_workflow_with_update();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("  defineUpdate,"));
+    assert!(code.workflow.contains("export interface SetDiscountUpdateArgs {"));
+    assert!(code.workflow.contains("export interface SetDiscountUpdateResult {"));
+    assert!(code.workflow.contains(
+        "export const setDiscountUpdate = defineUpdate<SetDiscountUpdateResult, [SetDiscountUpdateArgs]>('set-discount');"
+    ));
+
+    // The handler records the argument and returns the compiled result
+    assert!(code
+        .workflow
+        .contains("updates['set-discount'] = { received: true, args: update };"));
+    assert!(code
+        .workflow
+        .contains("return { total: (state.total * (100 - update.percent)) / 100 };"));
+
+    // The validator is passed as a handler option and throws on rejection
+    assert!(code.workflow.contains("validator: (update) => {"));
+    assert!(code
+        .workflow
+        .contains("if (!((update.percent > 0) && (update.percent <= 50))) {"));
+
+    // The node waits for the update like a signal node waits for its signal
+    let handler = code.workflow.find("setHandler(\n    setDiscountUpdate").unwrap();
+    let wait = code
+        .workflow
+        .find("await condition(() => updates['set-discount'].received);")
+        .unwrap();
+    assert!(handler < wait);
+    assert!(code
+        .workflow
+        .contains("const update_1_result = { ...updates['set-discount'] };"));
+}
+
+#[test]
+fn test_generate_update_without_validator_or_result() {
+    let mut workflow = create_workflow_with_update();
+    let data = &mut workflow.nodes[2].data;
+    data.validator = None;
+    data.return_value = None;
+    data.return_fields = None;
+    data.fields = None;
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("export const setDiscountUpdate = defineUpdate<void, [unknown]>('set-discount');"));
+    assert!(!code.workflow.contains("validator:"));
+    assert!(!code.workflow.contains("      return "));
+}
+
+#[test]
+fn test_update_field_descriptions_stay_in_their_comments() {
+    let mut workflow = create_workflow_with_update();
+    let data = &mut workflow.nodes[2].data;
+    data.fields.as_mut().unwrap()[0].description =
+        Some("Off */ process.exit(1); /*\nwhere x = 'y'".to_string());
+    data.return_fields.as_mut().unwrap()[0].description =
+        Some("New */ process.exit(2); /*".to_string());
+
+    let messages = invalid_config_messages(&workflow);
+    assert!(messages
+        .contains(&"description of argument field 'percent' must not contain '*/'".to_string()));
+    assert!(
+        messages.contains(&"description of return field 'total' must not contain '*/'".to_string())
+    );
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("  /** Off *\\/ process.exit(1); /* where x = 'y' */\n  percent: number;"));
+    assert!(code
+        .workflow
+        .contains("  /** New *\\/ process.exit(2); /* */\n  total: number;"));
+    assert!(!code.workflow.contains("&#x"));
+}
+
+#[test]
+fn test_validation_update_names_unique() {
+    let mut workflow = create_workflow_with_update();
+    let mut second = workflow.nodes[2].clone();
+    second.id = "update-2".to_string();
+    workflow.nodes.push(second);
+    workflow.nodes.push(WorkflowNode {
+        id: "signal-1".to_string(),
+        node_type: NodeType::Signal,
+        data: NodeData {
+            label: "Signal".to_string(),
+            signal_name: Some("approve".to_string()),
+            ..Default::default()
+        },
+        position: Position { x: 0.0, y: 0.0 },
+        parent_id: None,
+    });
+    let mut clash = workflow.nodes[2].clone();
+    clash.id = "update-3".to_string();
+    clash.data.update_name = Some("approve".to_string());
+    workflow.nodes.push(clash);
+    let mut query_clash = workflow.nodes[2].clone();
+    query_clash.id = "update-4".to_string();
+    query_clash.data.update_name = Some("status".to_string());
+    workflow.nodes.push(query_clash);
+    workflow.metadata = Some(WorkflowMetadata {
+        queries: vec![WorkflowQuery::new(
+            "status",
+            QuerySource::Variables { variables: vec![] },
+        )],
+        ..Default::default()
+    });
+
+    let messages = invalid_config_messages(&workflow);
+    assert!(messages.contains(
+        &"update 'set-discount' is already declared on node 'update-1'".to_string()
+    ));
+    assert!(messages.contains(&"update 'approve' has the same name as a signal".to_string()));
+    assert!(messages.contains(&"update 'status' has the same name as a query".to_string()));
+}
+
+#[test]
+fn test_validation_update_expressions() {
+    // Validators must be boolean and results must match the declared type
+    let mut workflow = create_workflow_with_update();
+    workflow.nodes[2].data.validator = Some("update.percent".to_string());
+    workflow.nodes[2].data.return_value = Some("{ total: 'free' }".to_string());
+    assert_eq!(
+        type_mismatches(&workflow),
+        vec![
+            ("update-1".to_string(), "boolean".to_string(), "number".to_string()),
+            (
+                "update-1".to_string(),
+                "{ total: number }".to_string(),
+                "{ total: string }".to_string()
+            ),
+        ]
+    );
+
+    // Handlers run before the workflow body, so node outputs are out of reach
+    let mut workflow = create_workflow_with_update();
+    workflow.nodes[2].data.return_value =
+        Some("{ total: nodes['activity-1'].output.total }".to_string());
+    assert!(invalid_config_messages(&workflow).contains(
+        &"returnValue cannot read node outputs; update handlers run outside the workflow body"
+            .to_string()
+    ));
+}
+
+fn child_workflow_node(id: &str, workflow_id: &str) -> WorkflowNode {
+    WorkflowNode {
+        id: id.to_string(),
+        node_type: NodeType::ChildWorkflow,
+        data: NodeData {
+            label: format!("Run {}", workflow_id),
+            workflow_id: Some(workflow_id.to_string()),
+            ..Default::default()
+        },
+        position: Position { x: 150.0, y: 0.0 },
+        parent_id: None,
+    }
+}
+
+fn create_workflow_with_children(children: Vec<WorkflowNode>) -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.truncate(1);
+    let mut previous = "trigger-1".to_string();
+    for (i, child) in children.into_iter().enumerate() {
+        workflow.edges.push(WorkflowEdge::new(
+            format!("child-edge-{}", i),
+            previous.clone(),
+            child.id.clone(),
+        ));
+        previous = child.id.clone();
+        workflow.nodes.push(child);
+    }
+    workflow.edges.retain(|e| e.id.starts_with("child-edge"));
+    workflow.edges.push(WorkflowEdge::new("child-edge-end", previous, "end-1"));
+    workflow.nodes.push(WorkflowNode {
+        id: "end-1".to_string(),
+        node_type: NodeType::End,
+        data: NodeData {
+            label: "End".to_string(),
+            ..Default::default()
+        },
+        position: Position { x: 300.0, y: 0.0 },
+        parent_id: None,
+    });
+    workflow
+}
+
+#[test]
+fn test_generate_child_workflows_with_unique_results() {
+    let mut shipping = child_workflow_node("ship-order", "shipping");
+    shipping.data.child_workflow_id = Some("ship-{{input.orderId}}".to_string());
+    shipping.data.task_queue = Some("shipping".to_string());
+    shipping.data.parent_close_policy = Some("request-cancel".to_string());
+    shipping.data.timeout = Some("1h".to_string());
+    shipping.data.run_timeout = Some("10m".to_string());
+    shipping.data.input_mapping = Some(BTreeMap::from([(
+        "orderId".to_string(),
+        "{{input.orderId}}".to_string(),
+    )]));
+    let invoice = child_workflow_node("send-invoice", "invoicing");
+    let workflow = create_workflow_with_children(vec![shipping, invoice]);
+
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("  executeChild,"));
+    assert!(code.workflow.contains("  ParentClosePolicy,"));
+    assert!(code
+        .workflow
+        .contains("const ship_order_result = await executeChild('shipping', {"));
+    assert!(code
+        .workflow
+        .contains("const send_invoice_result = await executeChild('invoicing', {"));
+    assert!(!code.workflow.contains("child_result"));
+
+    assert!(code.workflow.contains("args: [{ orderId: input.orderId }],"));
+    assert!(code.workflow.contains("workflowId: `ship-${input.orderId}`,"));
+    assert!(code.workflow.contains("taskQueue: 'shipping',"));
+    assert!(code
+        .workflow
+        .contains("parentClosePolicy: ParentClosePolicy.REQUEST_CANCEL,"));
+    assert!(code.workflow.contains("workflowExecutionTimeout: '1h',"));
+    assert!(code.workflow.contains("workflowRunTimeout: '10m',"));
+
+    // The workflow type only reaches the code as a string literal
+    let workflow = create_workflow_with_children(vec![child_workflow_node(
+        "ship",
+        "shipping\nprocess.exit(1); //",
+    )]);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("// Child workflow: ship - 'shipping\\nprocess.exit(1); //' (execute)"));
+    assert!(!code.workflow.contains("\nprocess.exit(1);"));
+}
+
+#[test]
+fn test_generate_started_child_workflow() {
+    let mut notify = child_workflow_node("notify", "notifications");
+    notify.data.child_mode = Some("start".to_string());
+    notify.data.parent_close_policy = Some("abandon".to_string());
+    let mut workflow = create_workflow_with_children(vec![notify]);
+    workflow.nodes[2].data.result_mapping =
+        Some("{ notification: nodes.notify.output.workflowId }".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains("  startChild,"));
+    assert!(!code.workflow.contains("  executeChild,"));
+    assert!(code
+        .workflow
+        .contains("const notify_handle = await startChild('notifications', {"));
+    assert!(code.workflow.contains(
+        "const notify_result = { workflowId: notify_handle.workflowId, runId: notify_handle.firstExecutionRunId };"
+    ));
+}
+
+#[test]
+fn test_generate_child_workflow_imports_sibling_type() {
+    let workflow = create_workflow_with_children(vec![child_workflow_node("ship", "shipping")]);
+    let mut sibling = create_simple_workflow();
+    sibling.id = Some("shipping".to_string());
+    sibling.name = Some("Ship Order".to_string());
+
+    let options = CodeGenOptions {
+        sibling_workflows: vec![sibling],
+        ..CodeGenOptions::new()
+    };
+    let code = codegen::generate(&workflow, &options).unwrap();
+    assert!(code
+        .workflow
+        .contains("import type { shipOrderWorkflow } from './shipping';"));
+    assert!(code.workflow.contains(
+        "const ship_result = await executeChild<typeof shipOrderWorkflow>('shipOrderWorkflow', {"
+    ));
+}
+
+#[test]
+fn test_validation_child_workflow_options() {
+    let mut child = child_workflow_node("ship", "shipping");
+    child.data.child_mode = Some("later".to_string());
+    child.data.parent_close_policy = Some("keep".to_string());
+    child.data.child_workflow_id = Some("ship-{{input.orderId".to_string());
+    let workflow = create_workflow_with_children(vec![child]);
+
+    let messages = invalid_config_messages(&workflow);
+    assert_eq!(messages.len(), 3, "{:?}", messages);
+    assert!(messages[0].starts_with("unknown child mode 'later'"));
+    assert!(messages[1].starts_with("unknown parent close policy 'keep'"));
+    assert!(messages[2].starts_with("invalid childWorkflowId"));
+
+    // Fire-and-forget children are terminated with the parent by default
+    let mut child = child_workflow_node("notify", "notifications");
+    child.data.child_mode = Some("start".to_string());
+    let result = validation::validate(&create_workflow_with_children(vec![child]));
+    assert!(result.valid);
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, .. } if node_id == "notify"
+    )));
+}
+
+#[test]
+fn test_validation_result_names_unique() {
+    let workflow = create_workflow_with_children(vec![
+        child_workflow_node("ship-order", "shipping"),
+        child_workflow_node("ship_order", "shipping"),
+    ]);
+    assert_eq!(
+        invalid_config_messages(&workflow),
+        vec!["result name 'ship_order_result' is also used by node 'ship-order'"]
+    );
+}
+
+fn retry_policy(strategy: RetryStrategy, max_attempts: Option<u32>) -> RetryPolicy {
+    RetryPolicy {
+        strategy,
+        max_attempts,
+        ..Default::default()
+    }
+}
+
+/// Trigger, three activities with the given retry overrides, and an end node,
+/// under an exponential backoff workflow policy
+fn create_workflow_with_retry_policies(overrides: [Option<RetryPolicy>; 3]) -> WorkflowDefinition {
+    let mut workflow = create_simple_workflow();
+    workflow.nodes.truncate(1);
+    for (i, retry_policy) in overrides.into_iter().enumerate() {
+        workflow.nodes.push(WorkflowNode {
+            id: format!("step-{}", i + 1),
+            node_type: NodeType::Activity,
+            data: NodeData {
+                label: format!("Step {}", i + 1),
+                activity_name: Some(format!("step{}", i + 1)),
+                retry_policy,
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        });
+    }
+    workflow.nodes.push(WorkflowNode {
+        id: "end-1".to_string(),
+        node_type: NodeType::End,
+        data: NodeData::default(),
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges = vec![
+        WorkflowEdge::new("e1", "trigger-1", "step-1"),
+        WorkflowEdge::new("e2", "step-1", "step-2"),
+        WorkflowEdge::new("e3", "step-2", "step-3"),
+        WorkflowEdge::new("e4", "step-3", "end-1"),
+    ];
+    workflow.metadata = Some(WorkflowMetadata {
+        retry_policy: Some(RetryPolicy {
+            max_interval: Some("1m".to_string()),
+            ..retry_policy(RetryStrategy::ExponentialBackoff, Some(5))
+        }),
+        ..Default::default()
+    });
+    workflow
+}
+
+#[test]
+fn test_generate_workflow_retry_policy() {
+    let workflow = create_workflow_with_retry_policies([None, None, None]);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(concat!(
+        "// Activity proxy with default timeout, retrying with exponential backoff\n",
+        "const acts = proxyActivities<Activities>({\n",
+        "  startToCloseTimeout: '1m',\n",
+        "  retry: {\n",
+        "    maximumAttempts: 5,\n",
+        "    initialInterval: '1s',\n",
+        "    maximumInterval: '1m',\n",
+        "    backoffCoefficient: 2.0,\n",
+        "  },\n",
+        "});"
+    )));
+    assert!(!code.workflow.contains("acts_1"));
+    assert!(code
+        .workflow
+        .contains("const step_3_result = await acts.step3("));
+}
+
+#[test]
+fn test_generate_proxy_per_distinct_node_retry_policy() {
+    let fail_after_three = retry_policy(RetryStrategy::FailAfterX, Some(3));
+    let workflow = create_workflow_with_retry_policies([
+        Some(retry_policy(RetryStrategy::KeepTrying, None)),
+        Some(fail_after_three.clone()),
+        Some(fail_after_three),
+    ]);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+
+    // Keep trying leaves the attempt count unlimited
+    assert!(code.workflow.contains(concat!(
+        "// Activity proxy with default timeout, retrying until the activity succeeds\n",
+        "const acts_1 = proxyActivities<Activities>({\n",
+        "  startToCloseTimeout: '1m',\n",
+        "  retry: {\n",
+        "    // maximumAttempts unset: Temporal retries without an attempt limit\n",
+        "  },\n"
+    )));
+    assert!(code.workflow.contains(concat!(
+        "const acts_2 = proxyActivities<Activities>({\n",
+        "  startToCloseTimeout: '1m',\n",
+        "  retry: {\n",
+        "    maximumAttempts: 3,\n",
+        "  },\n"
+    )));
+    assert!(!code.workflow.contains("acts_3"));
+    assert!(code
+        .workflow
+        .contains("const step_1_result = await acts_1.step1("));
+    assert!(code
+        .workflow
+        .contains("const step_2_result = await acts_2.step2("));
+    assert!(code
+        .workflow
+        .contains("const step_3_result = await acts_2.step3("));
+}
+
+#[test]
+fn test_generate_no_retry_strategy() {
+    let workflow = create_workflow_with_retry_policies([
+        Some(retry_policy(RetryStrategy::None, None)),
+        None,
+        None,
+    ]);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(concat!(
+        "// Activity proxy with default timeout, no retries\n",
+        "const acts_1 = proxyActivities<Activities>({\n",
+        "  startToCloseTimeout: '1m',\n",
+        "  retry: {\n",
+        "    maximumAttempts: 1,\n",
+        "  },\n"
+    )));
+}
+
+#[test]
+fn test_validation_retry_policies() {
+    let mut workflow = create_workflow_with_retry_policies([
+        Some(retry_policy(RetryStrategy::FailAfterX, None)),
+        Some(retry_policy(RetryStrategy::KeepTrying, Some(3))),
+        None,
+    ]);
+    if let Some(policy) = workflow
+        .metadata
+        .as_mut()
+        .and_then(|m| m.retry_policy.as_mut())
+    {
+        policy.backoff_coefficient = Some(0.5);
+    }
+
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Retry policy of the workflow is invalid: backoffCoefficient must be at least 1",
+            "Retry policy of node 'step-1' is invalid: 'fail-after-x' requires maxAttempts",
+        ]
+    );
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, .. } if node_id == "step-2"
+    )));
+}
+
+#[test]
+fn test_validation_retry_intervals() {
+    let mut workflow = create_workflow_with_retry_policies([
+        Some(RetryPolicy {
+            initial_interval: Some("10s".to_string()),
+            max_interval: Some("PT5S".to_string()),
+            ..retry_policy(RetryStrategy::ExponentialBackoff, None)
+        }),
+        Some(RetryPolicy {
+            initial_interval: Some("soon".to_string()),
+            ..retry_policy(RetryStrategy::KeepTrying, None)
+        }),
+        None,
+    ]);
+    workflow.metadata.as_mut().unwrap().retry_policy = None;
+
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert_eq!(
+        messages[0],
+        "Retry policy of node 'step-1' is invalid: \
+         maxInterval must not be shorter than initialInterval"
+    );
+    assert!(messages[1].starts_with(
+        "Retry policy of node 'step-2' is invalid: initialInterval 'soon' is not a valid duration"
+    ));
+}
+
+#[test]
+fn test_validation_timeouts() {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes[1].data.timeout = Some("5 minutes".to_string());
+    workflow.nodes[2].data.timeout = Some("0s".to_string());
+    workflow.nodes[3].data.ttl = Some("P1M".to_string());
+    workflow.metadata.as_mut().unwrap().timeout = Some("1x".to_string());
+
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(messages.len(), 4, "{:?}", messages);
+    assert!(messages[0].starts_with("Workflow timeout is invalid: '1x' is not a valid duration"));
+    assert!(messages[1].contains("timeout '5 minutes' is not a valid duration"));
+    assert!(messages[2].ends_with("timeout '0s' must be longer than zero"));
+    assert!(messages[3].contains("ttl 'P1M' is not a valid duration"));
+}
+
+#[test]
+fn test_validation_node_timeout_exceeds_workflow_timeout() {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.metadata.as_mut().unwrap().timeout = Some("PT1H".to_string());
+    workflow.nodes[1].data.timeout = Some("30m".to_string());
+    workflow.nodes[2].data.timeout = Some("2h".to_string());
+
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    let suggestions: Vec<(&str, &str)> = result
+        .warnings
+        .iter()
+        .filter_map(|w| match w {
+            validation::ValidationWarning::ConfigSuggestion { node_id, message } => {
+                Some((node_id.as_str(), message.as_str()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        suggestions,
+        vec![("step-2", "timeout 2h exceeds the workflow timeout 1h")]
+    );
+}
+
+#[test]
+fn test_generate_normalizes_durations() {
+    let workflow = create_workflow_with_retry_policies([
+        Some(RetryPolicy {
+            initial_interval: Some("PT30S".to_string()),
+            max_interval: Some("1h30m".to_string()),
+            ..retry_policy(RetryStrategy::ExponentialBackoff, Some(3))
+        }),
+        None,
+        None,
+    ]);
+    let options = CodeGenOptions {
+        default_timeout: "PT2M".to_string(),
+        ..CodeGenOptions::new()
+    };
+
+    let code = codegen::generate(&workflow, &options).unwrap();
+    assert!(code.workflow.contains("  startToCloseTimeout: '2m',"));
+    assert!(code.workflow.contains("    initialInterval: '30s',"));
+    assert!(code.workflow.contains("    maximumInterval: '90m',"));
+}
+
+/// Retry node wrapping step-1 and step-2, with step-3 running after the region
+fn create_workflow_with_retry_region(policy: Option<RetryPolicy>) -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes.push(WorkflowNode {
+        id: "retry-1".to_string(),
+        node_type: NodeType::Retry,
+        data: NodeData {
+            label: "Charge and Ship".to_string(),
+            retry_policy: policy,
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges = vec![
+        WorkflowEdge::new("e1", "trigger-1", "retry-1"),
+        WorkflowEdge::new("e2", "retry-1", "step-1").with_source_handle("body"),
+        WorkflowEdge::new("e3", "step-1", "step-2"),
+        WorkflowEdge::new("e4", "step-2", "step-3"),
+        WorkflowEdge::new("e5", "retry-1", "step-3").with_source_handle("done"),
+        WorkflowEdge::new("e6", "step-3", "end-1"),
+    ];
+    workflow
+}
+
+#[test]
+fn test_generate_retry_region() {
+    let workflow = create_workflow_with_retry_region(Some(RetryPolicy {
+        initial_interval: Some("2s".to_string()),
+        max_interval: Some("30s".to_string()),
+        non_retryable_errors: Some(vec!["CardDeclined".to_string()]),
+        ..retry_policy(RetryStrategy::ExponentialBackoff, Some(4))
+    }));
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    let retry = code
+        .workflow
+        .find("for (let retry_1_attempt = 1; ; retry_1_attempt++) {")
+        .expect("retry loop");
+    let step1 = code.workflow.find("acts.step1(").unwrap();
+    let step2 = code.workflow.find("acts.step2(").unwrap();
+    let catch = code.workflow.find("} catch (err) {").unwrap();
+    let step3 = code.workflow.find("acts.step3(").unwrap();
+    assert!(retry < step1 && step1 < step2 && step2 < catch && catch < step3);
+
+    assert!(code.workflow.contains(
+        "if (isCancellation(err) || retry_1_attempt >= 4 || \
+         ['CardDeclined'].includes(failureType(err) ?? '')) {"
+    ));
+    assert!(code
+        .workflow
+        .contains("await sleep(Math.min(2000 * 2 ** (retry_1_attempt - 1), 30000));"));
+    assert!(code
+        .workflow
+        .contains("function failureType(error: unknown)"));
+    assert!(code.workflow.contains("  isCancellation,\n"));
+    assert!(code.workflow.contains("  ApplicationFailure,\n"));
+}
+
+#[test]
+fn test_generate_retry_region_results() {
+    let mut workflow =
+        create_workflow_with_retry_region(Some(retry_policy(RetryStrategy::FailAfterX, Some(3))));
+    workflow.nodes[3].data.input_mapping = Some(BTreeMap::from([
+        ("charge".to_string(), "{{nodes.step-1.output}}".to_string()),
+        ("label".to_string(), "{{nodes.step-2.output}}".to_string()),
+    ]));
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    // The attempts return the region's results to the nodes after it
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("  const { step_1_result, step_2_result } = await (async () => {\n"));
+    assert!(code
+        .workflow
+        .contains("        return { step_1_result, step_2_result };\n      } catch (err) {"));
+    assert!(code.workflow.contains("  })();\n"));
+    assert!(!code.workflow.contains("break;"));
+    assert!(code.workflow.contains(
+        "const step_3_result = await acts.step3({ charge: step_1_result, label: step_2_result });"
+    ));
+
+    // A result that only some paths through the region produce stays inside
+    workflow.nodes.push(WorkflowNode {
+        id: "check".to_string(),
+        node_type: NodeType::Conditional,
+        data: NodeData {
+            label: "Check".to_string(),
+            condition: Some("input.express".to_string()),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges[2] = WorkflowEdge::new("e3", "step-1", "check");
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e7", "check", "step-2").with_source_handle("true"));
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e8", "check", "step-3").with_source_handle("false"));
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| matches!(
+        e,
+        validation::ValidationError::NodeReferenceNotAvailable { node_id, reference }
+            if node_id == "step-3" && reference == "step-2"
+    )));
+    assert!(!result.errors.iter().any(|e| matches!(
+        e,
+        validation::ValidationError::NodeReferenceNotAvailable { reference, .. }
+            if reference == "step-1"
+    )));
+}
+
+#[test]
+fn test_generate_retry_region_until_success() {
+    let workflow = create_workflow_with_retry_region(Some(RetryPolicy {
+        initial_interval: Some("PT5S".to_string()),
+        backoff_coefficient: Some(1.0),
+        ..retry_policy(RetryStrategy::KeepTrying, None)
+    }));
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("// Retry: retry-1 (until the region succeeds)"));
+    assert!(code.workflow.contains("if (isCancellation(err)) {"));
+    assert!(code.workflow.contains("await sleep(5000);"));
+    assert!(!code.workflow.contains("failureType"));
+}
+
+#[test]
+fn test_validation_retry_region() {
+    let mut workflow = create_workflow_with_retry_region(None);
+    workflow.edges[1].source_handle = None;
+
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert!(
+        messages.iter().any(|m| m.contains("retryPolicy")),
+        "{:?}",
+        messages
+    );
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("retry node has no 'body' edge")));
+}
+
+#[test]
+fn test_generate_non_retryable_activity_errors() {
+    let workflow = create_workflow_with_retry_policies([
+        Some(RetryPolicy {
+            non_retryable_errors: Some(vec!["InvalidAddress".to_string()]),
+            ..retry_policy(RetryStrategy::FailAfterX, Some(3))
+        }),
+        None,
+        None,
+    ]);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("    nonRetryableErrorTypes: ['InvalidAddress'],\n"));
+    assert!(code.workflow.contains("await acts_1.step1("));
+}
+
+/// Payment phase around step-1 and a nested fraud phase around step-2, with
+/// step-3 reading both results after the phase
+fn create_workflow_with_phases() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.metadata = None;
+    for (id, label, parent_id) in [
+        ("payment", "Payment", None),
+        ("fraud", "Fraud Check", Some("payment")),
+    ] {
+        workflow.nodes.push(WorkflowNode {
+            id: id.to_string(),
+            node_type: NodeType::Phase,
+            data: NodeData {
+                label: label.to_string(),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: parent_id.map(str::to_string),
+        });
+    }
+    workflow.nodes[1].parent_id = Some("payment".to_string());
+    workflow.nodes[2].parent_id = Some("fraud".to_string());
+    workflow.nodes[3].data.input_mapping = Some(BTreeMap::from([
+        ("charge".to_string(), "{{nodes.step-1.output}}".to_string()),
+        ("score".to_string(), "{{nodes.step-2.output}}".to_string()),
+    ]));
+    workflow.edges = vec![
+        WorkflowEdge::new("e1", "trigger-1", "payment"),
+        WorkflowEdge::new("e2", "payment", "step-1"),
+        WorkflowEdge::new("e3", "step-1", "fraud"),
+        WorkflowEdge::new("e4", "fraud", "step-2"),
+        WorkflowEdge::new("e5", "step-2", "step-3"),
+        WorkflowEdge::new("e6", "step-3", "end-1"),
+    ];
+    workflow
+}
+
+#[test]
+fn test_generate_phase_functions() {
+    let workflow = create_workflow_with_phases();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("let currentPhase: string | undefined;"));
+
+    let payment = code
+        .workflow
+        .find("async function payment_phase() {")
+        .unwrap();
+    let step1 = code.workflow.find("acts.step1(").unwrap();
+    let fraud = code
+        .workflow
+        .find("async function fraud_phase() {")
+        .unwrap();
+    let step2 = code.workflow.find("acts.step2(").unwrap();
+    let call = code
+        .workflow
+        .find("const { step_1_result, step_2_result } = await payment_phase();")
+        .unwrap();
+    let step3 = code.workflow.find("acts.step3(").unwrap();
+    assert!(payment < step1 && step1 < fraud && fraud < step2 && step2 < call && call < step3);
+
+    // The nested phase hands its result and the current phase back to its parent
+    assert!(code.workflow.contains(
+        "      currentPhase = 'Payment';\n      return { step_2_result };\n    }\n    \
+         const { step_2_result } = await fraud_phase();"
+    ));
+    assert!(code.workflow.contains("    currentPhase = undefined;\n"));
+    assert!(code
+        .workflow
+        .contains("{ charge: step_1_result, score: step_2_result }"));
+
+    // Labels only reach the code as string literals, so a line break in one
+    // cannot end a comment
+    let mut workflow = create_workflow_with_phases();
+    workflow.nodes[5].data.label = "Pay\nprocess.exit(1); //".to_string();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("// Phase: payment\n  async function payment_phase() {"));
+    assert!(code
+        .workflow
+        .contains("currentPhase = 'Pay\\nprocess.exit(1); //';"));
+    assert!(!code.workflow.contains("\nprocess.exit(1);"));
+
+    let mut workflow = create_workflow_with_phases();
+    workflow.nodes[5].id = "pay\nprocess.exit(1); //".to_string();
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| e
+        .to_string()
+        .contains("node id must not contain line breaks")));
+}
+
+#[test]
+fn test_generate_phase_ending_workflow() {
+    let mut workflow = create_workflow_with_phases();
+    workflow.nodes[3].parent_id = Some("payment".to_string());
+    workflow.nodes[4].parent_id = Some("payment".to_string());
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("async function payment_phase(): Promise<testWorkflowWorkflowOutput> {"));
+    assert!(code.workflow.contains("return await payment_phase();"));
+    assert!(!code.workflow.contains("= await payment_phase();"));
+}
+
+#[test]
+fn test_validation_phase_nesting() {
+    let mut workflow = create_workflow_with_phases();
+    workflow.nodes[4].parent_id = Some("step-3".to_string());
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(
+        messages,
+        vec!["Node 'end-1' has invalid configuration: parent 'step-3' is not a phase node"]
+    );
+
+    // An edge into step-2 that skips the fraud phase node
+    let mut workflow = create_workflow_with_phases();
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e7", "trigger-1", "step-2"));
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert!(messages.iter().any(|m| m.contains(
+        "edge 'e7' enters the phase at 'step-2' without passing through the phase node"
+    )));
+
+    let mut workflow = create_workflow_with_phases();
+    workflow.nodes[5].parent_id = Some("fraud".to_string());
+    let result = validation::validate(&workflow);
+    assert!(result
+        .errors
+        .iter()
+        .any(|e| e.to_string().contains("phase is nested inside itself")));
+}
+
+/// step-1 routes its failures to a notify activity that reads the failure
+/// message and ends the workflow on its own End node
+fn create_workflow_with_error_branch() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes.push(WorkflowNode {
+        id: "notify".to_string(),
+        node_type: NodeType::Activity,
+        data: NodeData {
+            label: "Notify".to_string(),
+            activity_name: Some("notifyFailure".to_string()),
+            input_mapping: Some(
+                [(
+                    "reason".to_string(),
+                    "Charge failed: {{nodes.step-1.error.message}}".to_string(),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.nodes.push(WorkflowNode {
+        id: "end-2".to_string(),
+        node_type: NodeType::End,
+        data: NodeData::default(),
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges.extend([
+        WorkflowEdge::new("e5", "step-1", "notify").with_source_handle("error"),
+        WorkflowEdge::new("e6", "notify", "end-2"),
+    ]);
+    workflow
+}
+
+#[test]
+fn test_generate_error_branch() {
+    let workflow = create_workflow_with_error_branch();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "const step_1_outcome = await settle(async () => {\n    \
+         const step_1_result = await acts.step1(input);\n    \
+         return step_1_result;\n  });\n  \
+         let step_1_result!: SettledValue<typeof step_1_outcome>;\n  \
+         if (step_1_outcome.failed) {"
+    ));
+    let failed = code.workflow.find("if (step_1_outcome.failed) {").unwrap();
+    let notify = code.workflow.find("acts.notifyFailure(").unwrap();
+    let success = code
+        .workflow
+        .find("} else {\n    step_1_result = step_1_outcome.value;")
+        .unwrap();
+    let step2 = code.workflow.find("acts.step2(").unwrap();
+    assert!(failed < notify && notify < success && success < step2);
+
+    assert!(code
+        .workflow
+        .contains("const step_1_error = step_1_outcome.error;"));
+    assert!(code
+        .workflow
+        .contains("{ reason: `Charge failed: ${step_1_error.message}` }"));
+    assert!(code
+        .workflow
+        .contains("function failureDetails(error: unknown): FailureDetails {"));
+    assert!(code.workflow.contains("  isCancellation,\n"));
+}
+
+#[test]
+fn test_validation_error_branch() {
+    // The failure is only available on the error branch, the result only
+    // on the success branch
+    let mut workflow = create_workflow_with_error_branch();
+    workflow.nodes[2].data.input_mapping = Some(
+        [(
+            "reason".to_string(),
+            "{{nodes.step-1.error.message}}".to_string(),
+        )]
+        .into_iter()
+        .collect(),
+    );
+    workflow.nodes[5].data.input_mapping = Some(
+        [("charge".to_string(), "{{nodes.step-1.output}}".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert!(messages.iter().all(|m| m.contains("'step-1'")));
+
+    // An error branch that stops short of an End node
+    let mut workflow = create_workflow_with_error_branch();
+    workflow.edges.retain(|e| e.id != "e6");
+    let result = validation::validate(&workflow);
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, message }
+            if node_id == "step-1" && message.contains("never reaches an End node")
+    )));
+
+    // The error branch rejoins the success path: the node where they meet
+    // also runs when step 1 failed, so it cannot read the result
+    let mut workflow = create_workflow_with_error_branch();
+    workflow.nodes.retain(|n| n.id != "end-2");
+    workflow.edges.retain(|e| e.id != "e6");
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e6", "notify", "step-2"));
+    workflow.nodes[2].data.input_mapping = Some(
+        [("id".to_string(), "{{nodes.step-1.output.id}}".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    assert!(messages[0].contains("'step-2'") && messages[0].contains("'step-1'"));
+
+    // Without the reference the diamond is valid, and the result is
+    // declared where the node after the branches can see it
+    workflow.nodes[2].data.input_mapping = None;
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "  let step_1_result!: SettledValue<typeof step_1_outcome>;\n  \
+         if (step_1_outcome.failed) {"
+    ));
+    let branches_end = code
+        .workflow
+        .find("    step_1_result = step_1_outcome.value;\n  }\n")
+        .unwrap();
+    let step2 = code.workflow.find("acts.step2(").unwrap();
+    assert!(branches_end < step2);
+
+    let mut workflow = create_workflow_with_error_branch();
+    workflow.edges[0].source_handle = Some("error".to_string());
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| e
+        .to_string()
+        .contains("only activity, child workflow and agent nodes can have an 'error' edge")));
+}
+
+/// step-1 and step-2 register compensations; step-3 is the activity that
+/// undoes them
+fn create_workflow_with_compensations(parallel: bool) -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes[1].data.compensation_activity = Some("step3".to_string());
+    workflow.nodes[1].data.input_mapping = Some(
+        [("orderId".to_string(), "{{input.orderId}}".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    workflow.nodes[2].data.compensation_activity = Some("step3".to_string());
+    if let Some(metadata) = workflow.metadata.as_mut() {
+        metadata.parallel_compensation = Some(parallel);
+    }
+    workflow
+}
+
+#[test]
+fn test_generate_compensation_stack() {
+    let workflow = create_workflow_with_compensations(false);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("const compensations: Array<() => Promise<unknown>> = [];"));
+    assert!(code.workflow.contains(
+        "const step_1_result = await acts.step1({ orderId: input.orderId });\n  \
+         compensations.push(() => acts.step3({ input: { orderId: input.orderId }, \
+         result: step_1_result }));"
+    ));
+    assert!(code
+        .workflow
+        .contains("compensations.push(() => acts.step3({ input, result: step_2_result }));"));
+    assert!(code
+        .workflow
+        .contains("await CancellationScope.nonCancellable(() => compensate(compensations));"));
+    assert!(code
+        .workflow
+        .contains("for (const compensation of [...compensations].reverse()) {"));
+    assert!(code.workflow.contains("  CancellationScope,\n"));
+    assert!(!code.workflow.contains("Promise.allSettled"));
+
+    let workflow = create_workflow_with_compensations(true);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "await Promise.allSettled(compensations.map((compensation) => compensation()));"
+    ));
+    assert!(!code.workflow.contains(".reverse()"));
+}
+
+#[test]
+fn test_validation_compensation_activity() {
+    let mut workflow = create_workflow_with_compensations(false);
+    workflow.nodes[2].data.compensation_activity = Some("cancelShipment".to_string());
+    workflow.nodes[4].data.compensation_activity = Some("step3".to_string());
+    let result = validation::validate(&workflow);
+
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Node 'step-2' has invalid configuration: compensation activity 'cancelShipment' \
+             is not in the Activities interface; no activity node runs it"
+        ]
+    );
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, message }
+            if node_id == "end-1" && message.contains("only applies to activity nodes")
+    )));
+}
+
+/// Cleanup branches run on cancellation: the trigger's releases the order,
+/// the payment phase's voids the charge
+fn create_workflow_with_cleanup_branches() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_phases();
+    for (id, activity) in [("release", "releaseOrder"), ("void", "voidCharge")] {
+        workflow.nodes.push(WorkflowNode {
+            id: id.to_string(),
+            node_type: NodeType::Activity,
+            data: NodeData {
+                label: id.to_string(),
+                activity_name: Some(activity.to_string()),
+                ..Default::default()
+            },
+            position: Position::default(),
+            parent_id: None,
+        });
+    }
+    workflow.edges.extend([
+        WorkflowEdge::new("c1", "trigger-1", "release").with_source_handle("cancel"),
+        WorkflowEdge::new("c2", "release", "end-1"),
+        WorkflowEdge::new("c3", "payment", "void").with_source_handle("cancel"),
+        WorkflowEdge::new("c4", "void", "end-1"),
+    ]);
+    workflow
+}
+
+#[test]
+fn test_generate_cleanup_branches() {
+    let workflow = create_workflow_with_cleanup_branches();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "    } catch (err) {\n      \
+         if (isCancellation(err)) {\n        \
+         // Cleanup: payment (on cancel)\n        \
+         await CancellationScope.nonCancellable(async () => {\n          \
+         const void_result = await acts.voidCharge(input);\n        \
+         });\n      }\n      throw err;\n    }"
+    ));
+    assert!(code.workflow.contains(
+        "    if (isCancellation(error)) {\n      \
+         // Cleanup: trigger-1 (on cancel)\n      \
+         await CancellationScope.nonCancellable(async () => {\n        \
+         const release_result = await acts.releaseOrder(input);\n      \
+         });"
+    ));
+    assert!(code.workflow.contains("        cancelled: true,\n"));
+    assert!(code.workflow.contains("  cancelled?: boolean;\n"));
+    // The cleanup branches end at the shared End node without returning
+    assert_eq!(code.workflow.matches("// Stop: end-1").count(), 1);
+}
+
+#[test]
+fn test_validation_cleanup_branch_waits() {
+    let mut workflow = create_workflow_with_cleanup_branches();
+    workflow.nodes.push(WorkflowNode {
+        id: "await-ack".to_string(),
+        node_type: NodeType::Signal,
+        data: NodeData {
+            label: "Await ack".to_string(),
+            signal_name: Some("ack".to_string()),
+            wait_for_signal: Some(true),
+            ..Default::default()
+        },
+        position: Position::default(),
+        parent_id: None,
+    });
+    workflow.edges.retain(|e| e.id != "c2");
+    workflow.edges.extend([
+        WorkflowEdge::new("c5", "release", "await-ack"),
+        WorkflowEdge::new("c6", "await-ack", "end-1"),
+    ]);
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert!(messages.iter().any(|m| m.contains(
+        "waits for signal 'ack' in the cleanup branch of 'trigger-1', \
+         which runs after the workflow is cancelled"
+    )));
+
+    let mut workflow = create_workflow_with_cleanup_branches();
+    workflow
+        .edges
+        .push(WorkflowEdge::new("c5", "step-3", "void").with_source_handle("cancel"));
+    workflow
+        .edges
+        .push(WorkflowEdge::new("c6", "void", "step-3"));
+    let messages: Vec<String> = validation::validate(&workflow)
+        .errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    assert!(messages.iter().any(
+        |m| m.contains("only the trigger and phases containing nodes can have a 'cancel' edge")
+    ));
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("the 'cancel' branch leads back into the workflow at 'step-3'")));
+}
+
+/// Replace an activity of the retry fixture with a Timer node
+fn make_timer(node: &mut WorkflowNode, data: NodeData) {
+    node.node_type = NodeType::Timer;
+    node.data = NodeData {
+        label: node.data.label.clone(),
+        ..data
+    };
+}
+
+#[test]
+fn test_generate_timers() {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    make_timer(
+        &mut workflow.nodes[2],
+        NodeData {
+            duration: Some("PT30M".to_string()),
+            ..Default::default()
+        },
+    );
+    make_timer(
+        &mut workflow.nodes[3],
+        NodeData {
+            until: Some("input.deadline".to_string()),
+            signal_name: Some("approve".to_string()),
+            ..Default::default()
+        },
+    );
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "// Timer: step-2 - wait 30m\n  \
+         await sleep('30m');\n  \
+         const step_2_result = { signalled: false, completedAt: new Date().toISOString() };"
+    ));
+    assert!(code.workflow.contains(
+        "// Timer: step-3 - wait until a computed time, or until signal 'approve'\n  \
+         const step_3_signalled = await condition(() => signals.approve.received, \
+         Math.max(0, new Date(input.deadline).getTime() - Date.now()));\n  \
+         const step_3_result = { signalled: step_3_signalled, \
+         completedAt: new Date().toISOString() };"
+    ));
+    // The raced signal gets a handler like one declared by a Signal node
+    assert!(code
+        .workflow
+        .contains("export const approveSignal = defineSignal"));
+    assert!(!code.activities.contains("step2"));
+}
+
+#[test]
+fn test_validation_timer_delays() {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.metadata.as_mut().unwrap().timeout = Some("1h".to_string());
+    make_timer(
+        &mut workflow.nodes[1],
+        NodeData {
+            duration: Some("0s".to_string()),
+            ..Default::default()
+        },
+    );
+    make_timer(
+        &mut workflow.nodes[2],
+        NodeData {
+            until: Some("2026-01-01".to_string()),
+            duration_expression: Some("input.delayMs".to_string()),
+            ..Default::default()
+        },
+    );
+    make_timer(
+        &mut workflow.nodes[3],
+        NodeData {
+            until: Some("2026-01-01 09:00".to_string()),
+            ..Default::default()
+        },
+    );
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Node 'step-1' has invalid configuration: duration '0s' must be longer than zero",
+            "Node 'step-2' has invalid configuration: timer sets more than one of duration, \
+             durationExpression and until",
+            "Node 'step-3' has invalid configuration: until '2026-01-01 09:00' is not an \
+             RFC 3339 timestamp such as 2026-01-01T09:00:00Z",
+        ]
+    );
+
+    make_timer(
+        &mut workflow.nodes[1],
+        NodeData {
+            duration: Some("2h".to_string()),
+            ..Default::default()
+        },
+    );
+    make_timer(
+        &mut workflow.nodes[2],
+        NodeData {
+            duration_expression: Some("input.delayMs > 0".to_string()),
+            ..Default::default()
+        },
+    );
+    let result = validation::validate(&workflow);
+    assert!(result.errors.iter().any(|e| matches!(
+        e,
+        validation::ValidationError::TypeMismatch { node_id, expected, found }
+            if node_id == "step-2" && expected == "number | string" && found == "boolean"
+    )));
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, message }
+            if node_id == "step-1" && message == "timer duration 2h exceeds the workflow timeout 1h"
+    )));
+}
+
+/// Step 1 maps over `input.lines`, running step 2 for each line; step 3
+/// reads the collected results
+fn create_workflow_with_map() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes[1].node_type = NodeType::Map;
+    workflow.nodes[1].data = NodeData {
+        label: "Each line".to_string(),
+        collection: Some("input.lines".to_string()),
+        item_variable: Some("line".to_string()),
+        max_concurrency: Some(4),
+        ..Default::default()
+    };
+    workflow.nodes[2].data.input_mapping = Some(BTreeMap::from([(
+        "sku".to_string(),
+        "{{nodes.step-1.item.sku}}".to_string(),
+    )]));
+    workflow.nodes[3].data.input_mapping = Some(BTreeMap::from([(
+        "reserved".to_string(),
+        "{{nodes.step-1.output}}".to_string(),
+    )]));
+    workflow.edges[1] = WorkflowEdge::new("e2", "step-1", "step-2").with_source_handle("body");
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e5", "step-1", "step-3"));
+    workflow
+}
+
+#[test]
+fn test_generate_map() {
+    let mut workflow = create_workflow_with_map();
+    workflow.nodes[1].data.max_items = Some(500);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "// Map: step-1 (each item, 4 at a time)\n  \
+         const step_1_items = (input.lines) as any[];\n  \
+         if (step_1_items.length > 500) {\n    \
+         throw ApplicationFailure.nonRetryable(\
+         'step-1 allows at most 500 items, got ' + step_1_items.length, 'MapTooLarge');\n  }\n  \
+         const step_1_result = await mapInBatches(step_1_items, 4, async (line) => {\n    \
+         const step_2_result = await acts.step2({ sku: line.sku });\n    \
+         return step_2_result;\n  });"
+    ));
+    assert!(code
+        .workflow
+        .contains("const step_3_result = await acts.step3({ reserved: step_1_result });"));
+    assert!(code.workflow.contains("async function mapInBatches<T, R>("));
+    assert!(code.workflow.contains("  ApplicationFailure,\n"));
+}
+
+#[test]
+fn test_validation_map_body() {
+    let mut workflow = create_workflow_with_map();
+    // Read the map's result inside its own body, and an item after it
+    workflow.nodes[2].data.input_mapping = Some(BTreeMap::from([(
+        "all".to_string(),
+        "{{nodes.step-1.output}}".to_string(),
+    )]));
+    workflow.nodes[3].data.input_mapping = Some(BTreeMap::from([(
+        "sku".to_string(),
+        "{{nodes.step-1.item.sku}}".to_string(),
+    )]));
+    let result = validation::validate(&workflow);
+    let unavailable: Vec<&str> = result
+        .errors
+        .iter()
+        .filter_map(|e| match e {
+            validation::ValidationError::NodeReferenceNotAvailable { node_id, .. } => {
+                Some(node_id.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(unavailable, vec!["step-2", "step-3"]);
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, message }
+            if node_id == "step-1"
+                && message.contains("more than 3413 items pass the 10240 events")
+    )));
+
+    let mut workflow = create_workflow_with_map();
+    workflow.nodes[1].data.max_items = Some(5_000);
+    workflow.nodes[1].data.item_variable = Some("state".to_string());
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Node 'step-1' has invalid configuration: itemVariable 'state' would hide the \
+             'state' that expressions read"
+        ]
+    );
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, message }
+            if node_id == "step-1"
+                && message.starts_with("mapping 5000 items adds about 15000 history events")
+    )));
+}
+
+/// Step 1 waits for an approval: approving runs step 2 before step 3,
+/// rejecting goes straight to step 3
+fn create_workflow_with_approval() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes[1].node_type = NodeType::Approval;
+    workflow.nodes[1].data = NodeData {
+        label: "Approve order".to_string(),
+        approvers: Some(vec!["finance".to_string(), "ops-lead".to_string()]),
+        signal_name: Some("approve-order".to_string()),
+        ..Default::default()
+    };
+    workflow.edges[1] = WorkflowEdge::new("e2", "step-1", "step-2").with_source_handle("approved");
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e5", "step-1", "step-3").with_source_handle("rejected"));
+    workflow
+}
+
+#[test]
+fn test_generate_approval() {
+    let mut workflow = create_workflow_with_approval();
+    workflow.nodes[1].data.timeout = Some("1d".to_string());
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e6", "step-1", "end-1").with_source_handle("timed_out"));
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    // The decision arrives through a typed signal, and the approval is
+    // listed by the pending approvals query while it waits
+    assert!(code.workflow.contains(
+        "export const approveOrderSignal = \
+         defineSignal<[ApproveOrderSignalPayload]>('approve-order');"
+    ));
+    assert!(code
+        .workflow
+        .contains("  approved: boolean;\n  comment?: string;\n"));
+    assert!(code.workflow.contains(
+        "export const pendingApprovalsQuery = defineQuery<PendingApproval[]>('pendingApprovals');"
+    ));
+    assert!(code
+        .workflow
+        .contains("setHandler(pendingApprovalsQuery, () => [...pendingApprovals.values()]);"));
+
+    assert!(code
+        .workflow
+        .contains("// Approval: step-1 - 2 approvers via signal 'approve-order' (wait up to 24h)"));
+    assert!(code
+        .workflow
+        .contains("    expiresAt: new Date(Date.now() + 86400000).toISOString(),\n"));
+    assert!(code.workflow.contains(
+        "  const step_1_decided = () =>\n    \
+         signals['approve-order'].received && \
+         ['finance', 'ops-lead'].includes(signals['approve-order'].payload.approver);\n  \
+         await condition(step_1_decided, '24h');\n  \
+         pendingApprovals.delete('step-1');"
+    ));
+    assert!(code.workflow.contains(
+        "// Outcome: step-1\n  switch (step_1_result.outcome) {\n    case 'approved': {\n      \
+         const step_2_result = await acts.step2(input);"
+    ));
+    assert!(code
+        .workflow
+        .contains("    case 'timed_out': {\n      break;\n    }\n  }\n"));
+}
+
+#[test]
+fn test_validation_approval_branches() {
+    let mut workflow = create_workflow_with_approval();
+    workflow.nodes[1].data.signal_name = None;
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e6", "step-1", "end-1").with_source_handle("timed_out"));
+    workflow
+        .edges
+        .push(WorkflowEdge::new("e7", "step-1", "end-1").with_source_handle("escalated"));
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Node 'step-1' has invalid configuration: unknown branch 'escalated', expected \
+             'approved', 'rejected' or 'timed_out'",
+            "Node 'step-1' has invalid configuration: the 'timed_out' branch is never taken \
+             without a timeout",
+        ]
+    );
+
+    // Without a timeout or a rejected branch, rejections are not handled
+    let mut workflow = create_workflow_with_approval();
+    workflow.edges.pop();
+    // Approvers only reach the code as string literals
+    workflow.nodes[1].data.approvers = Some(vec!["finance\nprocess.exit(1); //".to_string()]);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("// Approval: step-1 - 1 approver via signal"));
+    assert!(code
+        .workflow
+        .contains("approvers: ['finance\\nprocess.exit(1); //'],"));
+    assert!(!code.workflow.contains("\nprocess.exit(1);"));
+    assert!(result.warnings.iter().any(|w| matches!(
+        w,
+        validation::ValidationWarning::ConfigSuggestion { node_id, message }
+            if node_id == "step-1" && message == "no branch handles the 'rejected' outcome"
+    )));
+
+    // Approval signals cannot be shared
+    let mut workflow = create_workflow_with_approval();
+    workflow.nodes[2].node_type = NodeType::Signal;
+    workflow.nodes[2].data.signal_name = Some("approve-order".to_string());
+    workflow.nodes[1].data.approvers = Some(Vec::new());
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert!(messages.contains(&"Node 'step-1' is missing required field: approvers".to_string()));
+    assert!(messages.contains(
+        &"Node 'step-1' has invalid configuration: signal 'approve-order' is also used by node \
+          'step-2'; each approval needs a signal of its own"
+            .to_string()
+    ));
+}
+
+/// Step 2 is an agent that may call steps 1 and 3 as tools
+fn create_workflow_with_agent() -> WorkflowDefinition {
+    let mut workflow = create_workflow_with_retry_policies([None, None, None]);
+    workflow.nodes[2].node_type = NodeType::Agent;
+    workflow.nodes[2].data = NodeData {
+        label: "Triage".to_string(),
+        model: Some("stub-model".to_string()),
+        system_prompt: Some("Triage the order.".to_string()),
+        tools: Some(vec!["step1".to_string(), "step3".to_string()]),
+        max_turns: Some(4),
+        output_schema: Some(vec![
+            DataField::new("category", VariableType::String).required(),
+            DataField::new("urgent", VariableType::Boolean),
+        ]),
+        ..Default::default()
+    };
+    workflow
+}
+
+#[test]
+fn test_generate_agent() {
+    let workflow = create_workflow_with_agent();
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "// Agent: step-2 - 2 tools (max 4 turns)\n  \
+         const step_2_result = await runAgent<{ category: string; urgent: boolean }>(\n"
+    ));
+    assert!(code.workflow.contains(
+        "      tools: {\n        \
+         step1: { description: 'Step 1', run: (args) => acts.step1(args) },\n        \
+         step3: { description: 'Step 3', run: (args) => acts.step3(args) },\n      },\n      \
+         maxTurns: 4,\n"
+    ));
+    assert!(code.workflow.contains(
+        "      outputSchema: {\"properties\":{\"category\":{\"type\":\"string\"},\
+         \"urgent\":{\"type\":\"boolean\"}},\"required\":[\"category\"],\"type\":\"object\"},\n"
+    ));
+    // Every model call is an activity, and the loop fails once it runs out
+    // of turns
+    assert!(code
+        .workflow
+        .contains("import type { Activities, AgentMessage } from './activities';"));
+    assert!(code
+        .workflow
+        .contains("    const reply = await acts.callModel({\n"));
+    assert!(code.workflow.contains("'AgentMaxTurnsExceeded'"));
+    assert!(code
+        .workflow
+        .contains("Object.hasOwn(agent.tools, call.name)\n"));
+    assert!(code.workflow.contains("  ApplicationFailure,\n"));
+
+    // The model provider is pluggable, and a local stub answers by default
+    assert!(code
+        .activities
+        .contains("  callModel(request: ModelRequest): Promise<ModelResponse>;\n"));
+    assert!(code
+        .activities
+        .contains("let modelProvider: ModelProvider = stubModelProvider;"));
+    assert!(code
+        .activities
+        .contains("export function setModelProvider(provider: ModelProvider): void {"));
+    assert!(code.activities.contains("    callModel,\n"));
+
+    // Workflows without agents do not carry the loop
+    let code = codegen::generate(&create_simple_workflow(), &CodeGenOptions::new()).unwrap();
+    assert!(!code.workflow.contains("runAgent"));
+    assert!(!code.activities.contains("callModel"));
+}
+
+#[test]
+fn test_validation_agent_tools() {
+    let mut workflow = create_workflow_with_agent();
+    workflow.nodes[2].data.tools = Some(vec![
+        "step3".to_string(),
+        "sendEmail".to_string(),
+        "step3".to_string(),
+    ]);
+    workflow.nodes[2].data.max_turns = Some(0);
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Node 'step-2' has invalid configuration: maxTurns must be greater than zero",
+            "Node 'step-2' has invalid configuration: tool 'sendEmail' is not in the Activities \
+             interface; no activity node runs it",
+            "Node 'step-2' has invalid configuration: tool 'step3' is listed more than once",
+        ]
+    );
+
+    // Tools name an activity by its method, which a label-only activity
+    // derives from its label
+    let mut workflow = create_workflow_with_agent();
+    workflow.nodes[3].data.activity_name = None;
+    workflow.nodes[3].data.label = "Send Email".to_string();
+    workflow.nodes[2].data.tools = Some(vec!["sendEmail".to_string()]);
+    let result = validation::validate(&workflow);
+    assert!(result.valid, "{:?}", result.errors);
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code.workflow.contains(
+        "sendEmail: { description: 'Send Email', run: (args) => acts.sendEmail(args) },"
+    ));
+    assert!(code.workflow.contains("= await acts.sendEmail(input);"));
+    assert!(code
+        .activities
+        .contains("  sendEmail(input: Record<string, unknown>): Promise<unknown>;\n"));
+
+    // Only the tool count reaches the comment; the model is a string literal
+    workflow.nodes[2].data.model = Some("stub\nprocess.exit(1); //".to_string());
+    let code = codegen::generate(&workflow, &CodeGenOptions::new()).unwrap();
+    assert!(code
+        .workflow
+        .contains("// Agent: step-2 - 1 tool (max 4 turns)\n"));
+    assert!(code
+        .workflow
+        .contains("      model: 'stub\\nprocess.exit(1); //',\n"));
+    assert!(!code.workflow.contains("\nprocess.exit(1);"));
+
+    workflow.nodes[2].data.tools = Some(vec!["Send Email".to_string()]);
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Node 'step-2' has invalid configuration: tool 'Send Email' is not a valid identifier"
+        ]
+    );
+
+    // The model is required, and callModel is reserved for the model calls
+    let mut workflow = create_workflow_with_agent();
+    workflow.nodes[2].data.model = None;
+    workflow.nodes[1].data.activity_name = Some("callModel".to_string());
+    let result = validation::validate(&workflow);
+    let messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    assert!(messages.contains(&"Node 'step-2' is missing required field: model".to_string()));
+    assert!(messages.contains(
+        &"Node 'step-1' has invalid configuration: activity 'callModel' is generated for the \
+          model calls of agent nodes"
+            .to_string()
+    ));
+}
@@ -247,17 +247,11 @@ fn test_base_long_running_workflow_loads() {
 fn test_base_long_running_workflow_validates() {
     let workflow = load_fixture("base_long_running_workflow");
     let result = validate(&workflow);
-    // Note: This might have warnings about the cycle, but should still be valid
-    // if the loop node handles it correctly
-    // Note: This workflow has a loop which creates a cycle - the validator may detect this
-    // For now, we accept either valid or a cycle-related error
-    let has_cycle_error = result.errors.iter().any(|e| {
-        matches!(e, workflow_compiler::validation::ValidationError::CycleDetected(_))
-    });
+    // The loop back-edge passes through the loop node, so it is not a cycle error
     assert!(
-        result.valid || has_cycle_error,
-        "BaseLongRunningWorkflow validation result: {:?}",
-        result
+        result.valid,
+        "BaseLongRunningWorkflow should be valid: {:?}",
+        result.errors
     );
 }
 
@@ -300,6 +294,20 @@ fn test_base_long_running_workflow_generates_code() {
     );
 }
 
+#[test]
+fn test_base_long_running_workflow_generates_bounded_loop() {
+    let workflow = load_fixture("base_long_running_workflow");
+    let code = generate(&workflow, &CodeGenOptions::new()).unwrap();
+
+    let loop_pos = code
+        .workflow
+        .find("while ((state.iteration < state.maxIterations) && loop_1_iteration < 1000) {")
+        .expect("bounded while loop");
+    let log_pos = code.workflow.find("Processing iteration").unwrap();
+    let stop_pos = code.workflow.find("[WORKFLOW_STOP]").unwrap();
+    assert!(loop_pos < log_pos && log_pos < stop_pos);
+}
+
 // ============================================================================
 // Cross-workflow tests
 // ============================================================================